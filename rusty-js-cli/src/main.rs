@@ -1,11 +1,65 @@
+use std::cell::RefCell;
 use std::io::{BufRead, Write};
+use std::rc::Rc;
 
-use rusty_js_core::Runtime;
+use rusty_js_core::{
+    Diagnostic, Error, FsResolver, ImportMap, JValue, RejectionOperation, Runtime,
+};
+
+/// rejected promises without a handler and their reasons
+type Unhandled = Rc<RefCell<Vec<(JValue, String)>>>;
 
 fn main() {
     let runtime = Runtime::new();
     runtime.clone().attach();
-    
+
+    let unhandled = Unhandled::default();
+    let tracked = unhandled.clone();
+    runtime.set_rejection_handler(move |promise, reason, op| {
+        let mut list = tracked.borrow_mut();
+        match op {
+            RejectionOperation::Reject => list.push((promise, reason.to_string())),
+            RejectionOperation::Handle => list.retain(|(p, _)| *p != promise),
+        }
+    });
+
+    // modules are loaded from the working directory
+    runtime.set_import_resolver(FsResolver::new("."));
+
+    let mut script = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--import-map" => match args.next() {
+                Some(path) => runtime.set_import_map(read_import_map(&path)),
+                None => {
+                    eprintln!("--import-map requires a file");
+                    std::process::exit(1);
+                }
+            },
+            _ => script = Some(arg),
+        }
+    }
+
+    // run a script file and exit
+    if let Some(path) = script {
+        let source = match std::fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("cannot read {}: {}", path, e);
+                std::process::exit(1);
+            }
+        };
+        let mut ok = report(&source, runtime.clone().execute(&path, &source));
+        ok &= report(&source, futures::executor::block_on(runtime.run_event_loop()));
+        ok &= report_unhandled(&unhandled);
+
+        if !ok {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let mut reader  = std::io::BufReader::new(std::io::stdin());
 
     loop{
@@ -15,14 +69,85 @@ fn main() {
         reader.read_line(&mut line);
         let re = runtime.clone().execute("test.js", &line);
 
-        match re{
-            Ok(v) => {
-                println!("{}", v.to_string());
-            },
-            Err(e) => {
-                println!("{}", e.to_string());
+        if let Ok(v) = &re {
+            println!("{}", v.to_string());
+        }
+        report(&line, re);
+
+        // run the timers and tasks started by the line
+        report(&line, futures::executor::block_on(runtime.run_event_loop()));
+        report_unhandled(&unhandled);
+    }
+
+}
+
+/// read the import map, the addresses are relative to the file
+fn read_import_map(path: &str) -> ImportMap {
+    let map = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {}", path, e))
+        .and_then(|json| {
+            // the filesystem resolver names modules by their file:// url
+            let url = std::fs::canonicalize(path)
+                .map(|p| format!("file://{}", p.to_string_lossy()))
+                .unwrap_or_else(|_| path.to_string());
+            ImportMap::parse(&json, &url)
+        });
+    match map {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("invalid import map: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// print the error if any, return false on error
+fn report<T>(source: &str, re: Result<T, Error>) -> bool {
+    match re {
+        Ok(_) => return true,
+        Err(Error::SyntaxError(e)) if !e.diagnostics.is_empty() => {
+            for d in &e.diagnostics {
+                print_diagnostic(source, d);
             }
         }
+        Err(e) => {
+            println!("{}", e.to_string());
+        }
+    }
+    false
+}
+
+/// warn about the promises still unhandled after the event loop,
+/// return false if there is any
+fn report_unhandled(unhandled: &Unhandled) -> bool {
+    let list = std::mem::take(&mut *unhandled.borrow_mut());
+    for (_, reason) in &list {
+        eprintln!("warning: unhandled promise rejection: {}", reason);
     }
-    
+    list.is_empty()
+}
+
+/// print a diagnostic with the source line and the span underlined
+fn print_diagnostic(source: &str, d: &Diagnostic) {
+    let text = d.source_line(source);
+    let gutter = d.line.to_string();
+    let pad = " ".repeat(gutter.len());
+
+    // underline until the end of the span or the end of the line
+    let len = source
+        .get(d.span.start as usize..d.span.end as usize)
+        .map(|s| s.lines().next().unwrap_or_default().chars().count())
+        .unwrap_or(0)
+        .max(1);
+
+    println!("SyntaxError: {}", d.message);
+    println!("{}--> {}:{}:{}", pad, d.filename, d.line, d.column);
+    println!("{} |", pad);
+    println!("{} | {}", gutter, text);
+    println!(
+        "{} | {}{}",
+        pad,
+        " ".repeat(d.column - 1),
+        "^".repeat(len)
+    );
 }
@@ -57,6 +57,11 @@ pub struct CodeGen<'ctx> {
 
     catch_value: PointerValue<'ctx>,
     catch_blocks: Vec<BasicBlock<'ctx>>,
+    /// blocks that are the entry of a catch clause
+    catch_entries: Vec<Block>,
+
+    /// index of the opcode being translated
+    code_index: usize,
 
     for_in_iterators: Vec<PointerValue<'ctx>>,
     for_of_iterators: Vec<PointerValue<'ctx>>,
@@ -286,6 +291,9 @@ impl<'ctx> CodeGen<'ctx> {
 
             catch_value: catch_value,
             catch_blocks: Vec::new(),
+            catch_entries: Vec::new(),
+
+            code_index: 0,
 
             for_in_iterators: Default::default(),
             for_of_iterators: Default::default(),
@@ -294,9 +302,9 @@ impl<'ctx> CodeGen<'ctx> {
     }
 
     pub fn translate_codes(&mut self, codes: &[OpCode]) -> super::Function {
-        for code in codes {
-            let code = *code;
-            self.translate_code(code);
+        for (i, code) in codes.iter().enumerate() {
+            self.code_index = i;
+            self.translate_code(*code);
         }
 
         self.builder.position_at_end(self.switch_block);
@@ -421,6 +429,76 @@ impl<'ctx> CodeGen<'ctx> {
         self.builder.position_at_end(exit);
     }
 
+    /// consume one unit of the execution budget,
+    /// termination is checked again at the entry of catch blocks
+    fn consume_fuel(&mut self) {
+        let runtime = self.func.get_nth_param(1).unwrap().into_pointer_value();
+
+        let func = match self.functions.get("consume_fuel") {
+            Some(f) => *f,
+            None => {
+                let ty = self.context.void_type().fn_type(
+                    &[
+                        self.i64_ty.ptr_type(AddressSpace::default()).into(),
+                        self.binary_fn_result_ty.ptr_type(AddressSpace::default()).into(),
+                    ],
+                    false,
+                );
+                let func = self.module.add_function("consume_fuel\0", ty, None);
+                self.execution_engine.add_global_mapping(&func, operations::consume_fuel as usize);
+                self.functions.insert("consume_fuel", func);
+                func
+            }
+        };
+
+        let re = self.builder.build_alloca(self.binary_fn_result_ty, "fuel_result\0");
+        self.builder.build_call(func, &[runtime.into(), re.into()], "consume_fuel\0");
+
+        let ret = self.builder.build_load(re, "load_result\0").into_struct_value();
+
+        let value = self
+            .builder
+            .build_extract_value(ret, 0, "extract\0")
+            .unwrap()
+            .into_int_value();
+        let is_error = self
+            .builder
+            .build_extract_value(ret, 1, "extract\0")
+            .unwrap()
+            .into_int_value();
+
+        self.handle_error(value, is_error);
+    }
+
+    /// leave the function if the runtime is terminating,
+    /// emitted at the entry of catch blocks
+    fn check_terminating(&mut self) {
+        let runtime = self.func.get_nth_param(1).unwrap().into_pointer_value();
+
+        let func = match self.functions.get("is_terminating") {
+            Some(f) => *f,
+            None => {
+                let ty = self.context.bool_type().fn_type(
+                    &[self.i64_ty.ptr_type(AddressSpace::default()).into()],
+                    false,
+                );
+                let func = self.module.add_function("is_terminating\0", ty, None);
+                self.execution_engine.add_global_mapping(&func, operations::is_terminating as usize);
+                self.functions.insert("is_terminating", func);
+                func
+            }
+        };
+
+        let site = self.builder.build_call(func, &[runtime.into()], "is_terminating\0");
+        let terminating = site.try_as_basic_value().left().unwrap().into_int_value();
+
+        let exit = self.context.append_basic_block(self.func, "exit\0");
+        self.builder
+            .build_conditional_branch(terminating, self.error_exit_block, exit);
+
+        self.builder.position_at_end(exit);
+    }
+
     fn get_or_register_function(&mut self, name:&'ctx str, func: usize, ty:FunctionType<'ctx>) -> FunctionValue<'ctx>{
         match self.functions.get(name){
             Some(f) => *f,
@@ -699,7 +777,7 @@ impl<'ctx> CodeGen<'ctx> {
                     .into_int_value();
                 self.store_reg(result, value);
             },
-            OpCode::Yield { result, arg } => {
+            OpCode::Yield { result, arg } | OpCode::YieldDelegate { result, iterable: arg } => {
                 let resume_block = self.context.append_basic_block(self.func, "await_block\0");
                 let break_block = self
                     .context
@@ -723,9 +801,11 @@ impl<'ctx> CodeGen<'ctx> {
                 // breaking block
                 self.builder.position_at_end(break_block);
 
-                let exit_code = self
-                    .i64_ty
-                    .const_int(ExitCode::Yield as u64, false);
+                let exit_code = match code {
+                    OpCode::YieldDelegate { .. } => ExitCode::YieldDelegate,
+                    _ => ExitCode::Yield,
+                };
+                let exit_code = self.i64_ty.const_int(exit_code as u64, false);
                 let r0 = self.read_reg(Register(0));
                 let r1 = self.read_reg(Register(1));
                 let r2 = self.read_reg(Register(2));
@@ -758,13 +838,25 @@ impl<'ctx> CodeGen<'ctx> {
             }
             OpCode::SwitchToBlock(b) => {
                 self.builder.position_at_end(self.blocks[&b]);
+
+                if self.catch_entries.contains(&b) {
+                    self.check_terminating();
+                }
             }
-            OpCode::Jump { to, line: _ } => {
+            OpCode::Jump { to, line } => {
+                // loop back edge
+                if line as usize <= self.code_index {
+                    self.consume_fuel();
+                }
                 self.builder.build_unconditional_branch(self.blocks[&to]);
             }
-            OpCode::JumpIfTrue { value, to, line: _ } => {
+            OpCode::JumpIfTrue { value, to, line } => {
                 let else_block = self.context.append_basic_block(self.func, "else_block\0");
 
+                if line as usize <= self.code_index {
+                    self.consume_fuel();
+                }
+
                 let value = self.read_reg(value);
                 let is_true = self.to_bool(value);
 
@@ -773,9 +865,13 @@ impl<'ctx> CodeGen<'ctx> {
 
                 self.builder.position_at_end(else_block);
             }
-            OpCode::JumpIfFalse { value, to, line: _ } => {
+            OpCode::JumpIfFalse { value, to, line } => {
                 let else_block = self.context.append_basic_block(self.func, "else_block\0");
 
+                if line as usize <= self.code_index {
+                    self.consume_fuel();
+                }
+
                 let value = self.read_reg(value);
                 let is_true = self.to_bool(value);
 
@@ -789,6 +885,7 @@ impl<'ctx> CodeGen<'ctx> {
             OpCode::EnterTry { catch_block, line:_ } => {
                 let catch = self.context.append_basic_block(self.func, "catch_block\0");
                 self.blocks.insert(catch_block, catch);
+                self.catch_entries.push(catch_block);
 
                 self.catch_blocks.push(catch);
             },
@@ -1397,13 +1494,15 @@ impl<'ctx> CodeGen<'ctx> {
                 let value = site.try_as_basic_value().left().unwrap().into_int_value();
                 self.store_reg(result, value);
             }
-            OpCode::ImportMeta { result } => {
+            OpCode::ImportMeta { result, url } => {
                 let runtime = self.func.get_nth_param(1).unwrap().into_pointer_value();
+                let url = self.context.i32_type().const_int(url.0 as u64, false);
                 let fun = match self.functions.get("import.meta"){
                     Some(v) => *v,
                     None => {
                         let ty = self.i64_ty.fn_type(&[
-                            self.i64_ty.ptr_type(AddressSpace::default()).into()
+                            self.i64_ty.ptr_type(AddressSpace::default()).into(),
+                            self.context.i32_type().into(),
                         ], false);
 
                         let fun = self.module.add_function("import_meta\0", ty, None);
@@ -1413,7 +1512,46 @@ impl<'ctx> CodeGen<'ctx> {
                     }
                 };
                 
-                let site = self.builder.build_call(fun, &[runtime.into()], "import_meta\0");
+                let site = self.builder.build_call(fun, &[runtime.into(), url.into()], "import_meta\0");
+                let value = site.try_as_basic_value().left().unwrap().into_int_value();
+                self.store_reg(result, value);
+            }
+            OpCode::DynamicImport { result, stack_offset, args_len, referrer } => {
+                let runtime = self.func.get_nth_param(1).unwrap().into_pointer_value();
+                let stack_offset = self.i64_ty.const_int(stack_offset as u64 * std::mem::size_of::<JValue>() as u64, false);
+                let argc = self.context.i32_type().const_int(args_len as u64, false);
+                let referrer = self.context.i32_type().const_int(referrer.0 as u64, false);
+
+                let ptr_ty = self.i64_ty.ptr_type(AddressSpace::default());
+
+                let stack = self.func.get_nth_param(4).unwrap().into_pointer_value();
+                let stack = self.builder
+                        .build_ptr_to_int(stack, self.i64_ty, "stack\0");
+                let stack = self
+                    .builder
+                    .build_int_add(stack, stack_offset, "stack_add_offset\0");
+                let stack = self
+                    .builder
+                    .build_int_to_ptr(stack, ptr_ty, "stack_pointer\0");
+
+                let fun = match self.functions.get("dynamic_import"){
+                    Some(v) => *v,
+                    None => {
+                        let ty = self.i64_ty.fn_type(&[
+                            self.i64_ty.ptr_type(AddressSpace::default()).into(),
+                            ptr_ty.into(),
+                            self.context.i32_type().into(),
+                            self.context.i32_type().into(),
+                        ], false);
+
+                        let fun = self.module.add_function("dynamic_import\0", ty, None);
+                        self.execution_engine.add_global_mapping(&fun, operations::dynamic_import as usize);
+                        self.functions.insert("dynamic_import", fun);
+                        fun
+                    }
+                };
+
+                let site = self.builder.build_call(fun, &[runtime.into(), stack.into(), argc.into(), referrer.into()], "dynamic_import\0");
                 let value = site.try_as_basic_value().left().unwrap().into_int_value();
                 self.store_reg(result, value);
             }
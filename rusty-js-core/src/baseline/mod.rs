@@ -28,6 +28,8 @@ pub enum ExitCode {
     // value0 is the yielded value, value[1..2] are registers
     Yield = 3,
     Done = 4,
+    // value0 is the iterable delegated to by yield*, value[1..2] are registers
+    YieldDelegate = 5,
 }
 
 impl ExitCode {
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use crate::{
@@ -15,10 +16,72 @@ use super::{
     object::JObjectValue,
 };
 
+/// an element in the private storage of an object, 7.3.27 PrivateElement
+#[derive(Clone, Copy)]
+pub(crate) enum PrivateElement {
+    Field(JValue),
+    Method(JObject),
+    Accessor(Option<JObject>, Option<JObject>),
+}
+
+impl PrivateElement {
+    pub(crate) unsafe fn trace(&self) {
+        match self {
+            Self::Field(v) => v.trace(),
+            Self::Method(m) => m.trace(),
+            Self::Accessor(getter, setter) => {
+                if let Some(g) = getter {
+                    g.trace();
+                }
+                if let Some(s) = setter {
+                    s.trace();
+                }
+            }
+        }
+    }
+}
+
+/// a private name, 6.2.12 Private Names
+///
+/// every evaluation of a class body has its own brand,
+/// so classes created by the same code never share private names
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PrivateName {
+    pub(crate) brand: u32,
+    /// the interned description, such as '#x'
+    pub(crate) description: u32,
+}
+
+impl Hash for PrivateName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(((self.brand as u64) << 32) | self.description as u64)
+    }
+}
+
+impl PrivateName {
+    /// the private name declared by a class, a value that is not a class has no brand
+    pub(crate) fn of(class: JValue, description: u32) -> Self {
+        let brand = class
+            .as_object()
+            .and_then(|c| c.as_class().map(|c| c.brand))
+            .unwrap_or(0);
+        Self { brand, description }
+    }
+}
+
+/// the private elements of an object, keyed by private name
+pub(crate) type PrivateElements = HashMap<PrivateName, PrivateElement, nohasher::NoHasherBuilder>;
+
 pub struct JSClassInstance {
     pub(crate) class: Arc<JSClass>,
     pub(crate) super_: Option<JObject>,
     pub(crate) constructor_instance: Option<Arc<JSFunctionInstance>>,
+    /// evaluates the private fields of a new instance
+    pub(crate) initializer_instance: Option<Arc<JSFunctionInstance>>,
+    /// the private methods and accessors added to every new instance
+    pub(crate) private_methods: Vec<(u32, PrivateElement)>,
+    /// the brand of the private names declared by this evaluation of the class
+    pub(crate) brand: u32,
 }
 
 impl Clone for JSClassInstance {
@@ -27,6 +90,9 @@ impl Clone for JSClassInstance {
             class: self.class.clone(),
             super_: self.super_.clone(),
             constructor_instance: self.constructor_instance.clone(),
+            initializer_instance: self.initializer_instance.clone(),
+            private_methods: self.private_methods.clone(),
+            brand: self.brand,
         }
     }
 }
@@ -35,6 +101,48 @@ impl JSClassInstance {
     pub fn set_super(&self, super_: JObject) {
         unsafe { (&mut *(self as *const Self as *mut Self)).super_ = Some(super_) };
     }
+
+    /// 7.3.34 InitializeInstanceElements
+    pub(crate) fn initialize_instance_elements(
+        &self,
+        runtime: &Runtime,
+        this: JObject,
+        stack: *mut JValue,
+    ) -> Result<(), JValue> {
+        for (description, element) in &self.private_methods {
+            let name = PrivateName {
+                brand: self.brand,
+                description: *description,
+            };
+            this.add_private_element(runtime, name, *element)?;
+        }
+
+        if let Some(f) = &self.initializer_instance {
+            let (v, err) = f.clone().call(runtime, this.into(), stack, 0);
+            if err {
+                return Err(v);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) unsafe fn trace(&self) {
+        if let Some(f) = &self.constructor_instance {
+            f.trace();
+        }
+
+        if let Some(f) = &self.initializer_instance {
+            f.trace();
+        }
+
+        if let Some(v) = &self.super_ {
+            v.trace();
+        }
+
+        for (_name, element) in &self.private_methods {
+            element.trace();
+        }
+    }
     pub fn call(
         &self,
         runtime: &Runtime,
@@ -71,6 +179,14 @@ impl JSClassInstance {
         );
 
         if let Some(v) = &self.constructor_instance {
+            // a derived class initializes its elements once super() returns
+            if self.super_.is_none() {
+                let re = self.initialize_instance_elements(runtime, this, unsafe { stack.add(argc) });
+                if let Err(e) = re {
+                    return (e, true);
+                }
+            }
+
             let (v, err) = v.clone().call(runtime, this.into(), stack, argc);
             if err {
                 return (v, true);
@@ -92,6 +208,11 @@ impl JSClassInstance {
                 }
             }
 
+            let re = self.initialize_instance_elements(runtime, this, unsafe { stack.add(argc) });
+            if let Err(e) = re {
+                return (e, true);
+            }
+
             return (this.into(), false);
         }
     }
@@ -110,6 +231,18 @@ pub struct JSClass {
 
     pub(crate) props: Vec<u32>,
     pub(crate) static_props: Vec<u32>,
+
+    /// private methods and accessors, keyed by the description of their private name
+    pub(crate) private_methods: HashMap<u32, Arc<JSFunction>, nohasher::NoHasherBuilder>,
+    pub(crate) static_private_methods: HashMap<u32, Arc<JSFunction>, nohasher::NoHasherBuilder>,
+
+    pub(crate) private_get_setters:
+        HashMap<u32, (Option<Arc<JSFunction>>, Option<Arc<JSFunction>>), nohasher::NoHasherBuilder>,
+    pub(crate) static_private_get_setters:
+        HashMap<u32, (Option<Arc<JSFunction>>, Option<Arc<JSFunction>>), nohasher::NoHasherBuilder>,
+
+    /// defines the private fields on a new instance, called with the instance as this
+    pub(crate) field_initializer: Option<Arc<JSFunction>>,
 }
 
 impl JSClass {
@@ -124,7 +257,41 @@ impl JSClass {
 
             props: Vec::new(),
             static_props: Vec::new(),
+
+            private_methods: Default::default(),
+            static_private_methods: Default::default(),
+            private_get_setters: Default::default(),
+            static_private_get_setters: Default::default(),
+            field_initializer: None,
+        }
+    }
+
+    /// create the private methods and accessors from their functions
+    fn private_elements(
+        methods: &HashMap<u32, Arc<JSFunction>, nohasher::NoHasherBuilder>,
+        get_setters: &HashMap<
+            u32,
+            (Option<Arc<JSFunction>>, Option<Arc<JSFunction>>),
+            nohasher::NoHasherBuilder,
+        >,
+        create: &dyn Fn(&Arc<JSFunction>) -> JObject,
+    ) -> Vec<(u32, PrivateElement)> {
+        let mut elements = Vec::new();
+
+        for (name, f) in methods {
+            elements.push((*name, PrivateElement::Method(create(f))));
+        }
+
+        for (name, (getter, setter)) in get_setters {
+            elements.push((
+                *name,
+                PrivateElement::Accessor(
+                    getter.as_ref().map(|g| create(g)),
+                    setter.as_ref().map(|s| create(s)),
+                ),
+            ));
         }
+        elements
     }
 
     pub fn to_mut(&self) -> &mut Self {
@@ -137,16 +304,34 @@ impl JSClass {
     ) -> JSClassInstance {
         let c = if let Some(v) = &self.constructor {
             Some(Arc::new(
-                v.clone().create_instance_with_capture(None, capture_stack),
+                v.clone().create_instance_with_capture(None, capture_stack.clone()),
             ))
         } else {
             None
         };
 
+        let initializer = self.field_initializer.as_ref().map(|f| {
+            Arc::new(f.clone().create_instance_with_capture(None, capture_stack.clone()))
+        });
+
+        let private_methods = Self::private_elements(
+            &self.private_methods,
+            &self.private_get_setters,
+            &|f| {
+                JObject::with_function(
+                    f.clone()
+                        .create_instance_with_capture(None, capture_stack.clone()),
+                )
+            },
+        );
+
         JSClassInstance {
             class: self,
             super_: None,
             constructor_instance: c,
+            initializer_instance: initializer,
+            private_methods,
+            brand: Runtime::current().new_private_brand(),
         }
     }
 
@@ -157,10 +342,24 @@ impl JSClass {
             None
         };
 
+        let initializer = self
+            .field_initializer
+            .as_ref()
+            .map(|f| Arc::new(f.clone().create_instance(None)));
+
+        let private_methods = Self::private_elements(
+            &self.private_methods,
+            &self.private_get_setters,
+            &|f| JObject::with_function(f.clone().create_instance(None)),
+        );
+
         JSClassInstance {
             class: self,
             super_: None,
             constructor_instance: c,
+            initializer_instance: initializer,
+            private_methods,
+            brand: Runtime::current().new_private_brand(),
         }
     }
 
@@ -169,6 +368,7 @@ impl JSClass {
             .clone()
             .create_instance_with_capture(capture_stack.clone());
         let obj = JObject::new();
+        let brand = inst.brand;
         obj.inner.to_mut().wrapped_value = JObjectValue::Class(Arc::new(inst));
 
         let prototype = JObject::new();
@@ -243,6 +443,24 @@ impl JSClass {
             );
         }
 
+        let statics = Self::private_elements(
+            &self.static_private_methods,
+            &self.static_private_get_setters,
+            &|f| {
+                JObject::with_function(
+                    f.clone()
+                        .create_instance_with_capture(None, capture_stack.clone()),
+                )
+            },
+        );
+        for (description, element) in statics {
+            let name = PrivateName {
+                brand,
+                description,
+            };
+            obj.insert_private_element(name, element);
+        }
+
         obj.insert_property(NAMES["prototype"], prototype.into(), PropFlag::NONE);
         obj.insert_property(
             NAMES["name"],
@@ -268,6 +486,7 @@ impl JSClass {
     pub fn create_without_capture(self: Arc<Self>) -> JObject {
         let inst = self.clone().create_instance();
         let obj = JObject::new();
+        let brand = inst.brand;
         obj.inner.to_mut().wrapped_value = JObjectValue::Class(Arc::new(inst));
 
         let prototype = JObject::new();
@@ -330,6 +549,19 @@ impl JSClass {
             );
         }
 
+        let statics = Self::private_elements(
+            &self.static_private_methods,
+            &self.static_private_get_setters,
+            &|f| JObject::with_function(f.clone().create_instance(None)),
+        );
+        for (description, element) in statics {
+            let name = PrivateName {
+                brand,
+                description,
+            };
+            obj.insert_private_element(name, element);
+        }
+
         prototype.insert_property(
             NAMES["constructor"],
             obj.into(),
@@ -358,3 +590,151 @@ impl JSClass {
         return obj;
     }
 }
+
+/// the private elements of objects, 7.3.28 - 7.3.32
+impl JObject {
+    /// 7.3.28 PrivateElementFind
+    fn private_element(&self, name: PrivateName) -> Option<&mut PrivateElement> {
+        self.inner
+            .to_mut()
+            .private_elements
+            .as_mut()?
+            .get_mut(&name)
+    }
+
+    pub(crate) fn has_private_element(&self, name: PrivateName) -> bool {
+        self.private_element(name).is_some()
+    }
+
+    /// add an element without checking whether it exists
+    pub(crate) fn insert_private_element(&self, name: PrivateName, element: PrivateElement) {
+        self.inner
+            .to_mut()
+            .private_elements
+            .get_or_insert_with(Default::default)
+            .insert(name, element);
+    }
+
+    /// 7.3.29 PrivateFieldAdd, 7.3.30 PrivateMethodOrAccessorAdd
+    pub(crate) fn add_private_element(
+        &self,
+        runtime: &Runtime,
+        name: PrivateName,
+        element: PrivateElement,
+    ) -> Result<(), JValue> {
+        if self.has_private_element(name) {
+            return Err(Error::TypeError(format!(
+                "Cannot initialize {} twice on the same object",
+                runtime.get_field_name(name.description)
+            ))
+            .into());
+        }
+        self.insert_private_element(name, element);
+        Ok(())
+    }
+
+    /// 7.3.31 PrivateGet
+    pub(crate) fn private_get(&self, name: PrivateName, ctx: JSContext) -> Result<JValue, JValue> {
+        match self.private_element(name) {
+            Some(PrivateElement::Field(v)) => Ok(*v),
+            Some(PrivateElement::Method(m)) => Ok((*m).into()),
+            Some(PrivateElement::Accessor(Some(getter), _)) => {
+                JValue::from(*getter).call_with_context((*self).into(), &[], ctx)
+            }
+            Some(PrivateElement::Accessor(None, _)) => Err(Error::TypeError(format!(
+                "'{}' was defined without a getter",
+                ctx.runtime.get_field_name(name.description)
+            ))
+            .into()),
+            None => Err(read_error(ctx.runtime, name)),
+        }
+    }
+
+    /// 7.3.32 PrivateSet
+    pub(crate) fn private_set(
+        &self,
+        name: PrivateName,
+        value: JValue,
+        ctx: JSContext,
+    ) -> Result<(), JValue> {
+        match self.private_element(name) {
+            Some(PrivateElement::Field(v)) => {
+                *v = value;
+                Ok(())
+            }
+            Some(PrivateElement::Method(_)) => Err(Error::TypeError(format!(
+                "Private method '{}' is not writable",
+                ctx.runtime.get_field_name(name.description)
+            ))
+            .into()),
+            Some(PrivateElement::Accessor(_, Some(setter))) => {
+                JValue::from(*setter).call_with_context((*self).into(), &[value], ctx)?;
+                Ok(())
+            }
+            Some(PrivateElement::Accessor(_, None)) => Err(Error::TypeError(format!(
+                "'{}' was defined without a setter",
+                ctx.runtime.get_field_name(name.description)
+            ))
+            .into()),
+            None => Err(write_error(ctx.runtime, name)),
+        }
+    }
+}
+
+/// primitives have no private elements
+impl JValue {
+    pub(crate) fn private_get(self, name: PrivateName, ctx: JSContext) -> Result<JValue, JValue> {
+        match self.as_object() {
+            Some(obj) => obj.private_get(name, ctx),
+            None => Err(read_error(ctx.runtime, name)),
+        }
+    }
+
+    pub(crate) fn private_set(self, name: PrivateName, value: JValue, ctx: JSContext) -> Result<(), JValue> {
+        match self.as_object() {
+            Some(obj) => obj.private_set(name, value, ctx),
+            None => Err(write_error(ctx.runtime, name)),
+        }
+    }
+
+    pub(crate) fn private_field_add(
+        self,
+        runtime: &Runtime,
+        name: PrivateName,
+        value: JValue,
+    ) -> Result<(), JValue> {
+        match self.as_object() {
+            Some(obj) => obj.add_private_element(runtime, name, PrivateElement::Field(value)),
+            None => Err(write_error(runtime, name)),
+        }
+    }
+
+    /// #x in obj, a TypeError is thrown if obj is not an object
+    pub(crate) fn has_private_element(self, runtime: &Runtime, name: PrivateName) -> Result<bool, JValue> {
+        match self.as_object() {
+            Some(obj) => Ok(obj.has_private_element(name)),
+            None => Err(Error::TypeError(format!(
+                "Cannot use 'in' operator to search for '{}' in {}",
+                runtime.get_field_name(name.description),
+                self.to_string()
+            ))
+            .into()),
+        }
+    }
+}
+
+fn read_error(runtime: &Runtime, name: PrivateName) -> JValue {
+    Error::TypeError(format!(
+        "Cannot read private member {} from an object whose class did not declare it",
+        runtime.get_field_name(name.description)
+    ))
+    .into()
+}
+
+fn write_error(runtime: &Runtime, name: PrivateName) -> JValue {
+    Error::TypeError(format!(
+        "Cannot write private member {} to an object whose class did not declare it",
+        runtime.get_field_name(name.description)
+    ))
+    .into()
+}
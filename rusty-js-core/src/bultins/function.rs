@@ -8,13 +8,15 @@ use crate::runtime::{Runtime, DEFAULT_STACK_SIZE};
 use crate::value::JValue;
 use crate::utils::string_interner::NAMES;
 
+use super::generator::JSGenerator;
 use super::object_property::PropFlag;
 use super::object::{JObject, JObjectValue};
+use super::promise::Promise;
 
 #[derive(Clone)]
 pub struct JSFunctionInstance {
-    capture_stack: CaptureStack,
-    this: Option<JValue>,
+    pub(crate) capture_stack: CaptureStack,
+    pub(crate) this: Option<JValue>,
     pub(crate) func: Arc<JSFunction>,
 }
 
@@ -83,11 +85,7 @@ impl JSFunctionInstance {
             PropFlag::CONFIGURABLE,
         );
         obj.insert_property(NAMES["prototype"], proto.into(), PropFlag::NONE);
-        obj.insert_property(
-            NAMES["__proto__"],
-            rt.prototypes.function.into(),
-            Default::default(),
-        );
+        obj.set_prototype(rt.prototypes.function.into());
 
         obj.inner.to_mut().wrapped_value = JObjectValue::Function(Arc::new(self));
         return obj;
@@ -189,6 +187,8 @@ impl JSFunction {
         argc: usize,
         capture_stack: CaptureStack,
     ) -> Result<JValue, JValue> {
+        runtime.consume_fuel()?;
+
         self.call_count += 1;
 
         if self.call_count == 10 {
@@ -238,13 +238,13 @@ impl JSFunction {
                 };
 
             } else if let Some(c) = &mut self.baseline_clousure {
-                let stack = unsafe { std::slice::from_raw_parts_mut(stack, DEFAULT_STACK_SIZE) };
+                let stack = unsafe { std::slice::from_raw_parts_mut(stack, runtime.stack.len()) };
                 let op_stack = unsafe {
                     std::slice::from_raw_parts_mut(
                         stack
                             .as_mut_ptr()
                             .add(self.largest_stack_offset as usize + argc),
-                        DEFAULT_STACK_SIZE,
+                        runtime.stack.len(),
                     )
                 };
                 let args = unsafe { std::slice::from_raw_parts_mut(stack.as_mut_ptr(), argc) };
@@ -263,7 +263,7 @@ impl JSFunction {
                     args,
                 )
             } else {
-                let stack = unsafe { std::slice::from_raw_parts_mut(stack, DEFAULT_STACK_SIZE) };
+                let stack = unsafe { std::slice::from_raw_parts_mut(stack, runtime.stack.len()) };
                 let cd = capture_stack.data();
                 let cap = cd.as_ref().and_then(|v| Some(v.as_ref().as_ref()));
                 let cap = unsafe { std::mem::transmute_copy(&cap) };
@@ -280,21 +280,78 @@ impl JSFunction {
 
                 intpr.run(this, args, &self.bytecodes)
             }
+        } else if self.is_generator {
+            let args = unsafe { std::slice::from_raw_parts(stack, argc) };
+            Ok(JSGenerator::create(self, runtime, this, args, capture_stack).into())
         } else {
-            todo!()
+            let args = unsafe { std::slice::from_raw_parts(stack, argc) };
+            Ok(self.call_async(runtime, this, args, capture_stack).into())
         }
     }
 
+    /// 27.7.5.1 AsyncFunctionStart, the body runs until its first await
+    /// and the promise is settled by the task driving the rest of it
+    fn call_async(
+        &self,
+        runtime: &Runtime,
+        this: JValue,
+        args: &[JValue],
+        capture_stack: CaptureStack,
+    ) -> JObject {
+        // the arguments are copied to the stack of the body
+        let stack = runtime
+            .to_mut()
+            .get_async_stack(DEFAULT_STACK_SIZE * JValue::SIZE);
+        stack[..args.len()].copy_from_slice(args);
+        let argc = args.len();
+        let len = stack.len();
+        let ptr = stack.as_mut_ptr();
+
+        let args = unsafe { std::slice::from_raw_parts(ptr, argc) };
+        let body_stack = unsafe { std::slice::from_raw_parts_mut(ptr.add(argc), len - argc) };
+
+        let cd = capture_stack.data();
+        let cap = cd.as_ref().and_then(|v| Some(v.as_ref().as_ref()));
+        let cap = unsafe { std::mem::transmute_copy(&cap) };
+
+        let intpr = Interpreter::function(
+            runtime,
+            body_stack,
+            argc + self.largest_stack_offset as usize,
+            capture_stack,
+            cap,
+        );
+        let body = intpr.run_async(this, args, self.bytecodes.clone());
+
+        // the capture stack and the stack are kept alive by the body
+        let rt = runtime.arc();
+        let id = runtime.spawn_task(async move {
+            let re = body.await;
+            drop(cd);
+            rt.drop_async_stack(stack);
+            re
+        });
+
+        let mut cx = std::task::Context::from_waker(futures::task::noop_waker_ref());
+        let _ = runtime.poll_future(id, &mut cx);
+        JObject::with_promise(Promise::Pending { id })
+    }
+
     fn compile(&mut self, rt: &Runtime) {
+        // the baseline tier is disabled
+        if rt.baseline_engine.is_none() {
+            return;
+        }
+
         if self.baseline_jit.is_none() {
             let ptr = &mut self.baseline_jit as *mut _ as usize;
             let runtime = Runtime::current();
             let bytecodes = self.bytecodes.clone();
 
-            rt.worker_task_sender.send(Box::new(move ||{
+            rt.spawn_worker_task(Box::new(move ||{
                 // todo: identify the static borrow and use arc instead
-                let ctx_a= runtime.baseline_context.clone();
-                let module_a = runtime.baseline_module.clone();
+                let ctx_a= runtime.baseline_context.as_ref().unwrap().clone();
+                let module_a = runtime.baseline_module.as_ref().unwrap().clone();
                 let engine_a = runtime.baseline_engine.as_ref().unwrap().clone();
 
                 let ctx = unsafe{std::mem::transmute_copy(&ctx_a.as_ref())};
@@ -311,7 +368,7 @@ impl JSFunction {
                 unsafe{
                     (ptr as *mut Option<Arc<baseline::Function>>).write(Some(Arc::new(func)));
                 }
-            })).expect("failed to send task");
+            }));
         }
 
     }
@@ -1,72 +1,588 @@
-use std::ops::DerefMut;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 
-use futures::lock::Mutex as FutureMutex;
-use parking_lot::Mutex;
-
-use crate::{baseline, Promise};
-use crate::{error::Error, value::JValue, utils::string_interner::NAMES, JObject, Runtime};
+use crate::bultins::function::{CaptureStack, JSFunction};
+use crate::bultins::promise::{self, PromiseFuture};
+use crate::interpreter::Interpreter;
+use crate::runtime::DEFAULT_STACK_SIZE;
+use crate::utils::string_interner::{NAMES, SYMBOLS};
+use crate::{error::Error, value::JValue, JObject, JSContext, Runtime};
 
+use super::object::JObjectValue;
 use super::object_property::PropFlag;
 
-pub enum GeneratorResult {
-    Await(JValue),
+type Body = Pin<Box<dyn Future<Output = Result<JValue, JValue>>>>;
+
+/// how a suspended generator is resumed,
+/// 27.5.3.3 GeneratorResume and 27.5.3.4 GeneratorResumeAbrupt
+#[derive(Clone, Copy)]
+pub enum GeneratorResume {
+    Next(JValue),
+    Throw(JValue),
+    Return(JValue),
+}
+
+impl GeneratorResume {
+    unsafe fn trace(&self) {
+        match self {
+            Self::Next(v) | Self::Throw(v) | Self::Return(v) => v.trace(),
+        }
+    }
+}
+
+/// the completion of a yield expression, a throw completion is an error
+pub(crate) enum Resumed {
+    /// the value is the result of the expression
+    Normal(JValue),
+    /// the generator returns with the value
+    Return(JValue),
+}
+
+/// what the body hands to the caller when suspended
+#[derive(Clone, Copy)]
+enum Suspended {
+    /// wrapped in an iterator result
     Yield(JValue),
+    /// the iterator result of the iterator delegated to, returned as is
+    Delegate(JValue),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GeneratorState {
+    SuspendedStart,
+    SuspendedYield,
+    Executing,
+    Completed,
+}
+
+/// how far the body ran when it was polled
+enum Step {
+    Suspended(Suspended),
+    Completed(Result<JValue, JValue>),
+    /// an async generator is awaiting
+    Pending,
+}
+
+/// shared by a generator and its body
+pub(crate) struct GeneratorChannel {
+    pub(crate) is_async: bool,
+    runtime: Arc<Runtime>,
+    resume: Cell<Option<GeneratorResume>>,
+    suspended: Cell<Option<Suspended>>,
+    /// the values only held by the suspended body, the registers, `this` and temps
+    roots: RefCell<Vec<JValue>>,
+    /// the iterator and its next method delegated to by yield*
+    delegated: Cell<Option<(JValue, JValue)>>,
+}
+
+impl GeneratorChannel {
+    /// keep the values of the body alive while it is suspended
+    pub(crate) fn set_roots(&self, roots: Vec<JValue>) {
+        *self.roots.borrow_mut() = roots;
+    }
+
+    /// suspend the body until the generator is resumed
+    async fn suspend(&self, value: Suspended) -> GeneratorResume {
+        self.suspended.set(Some(value));
+        std::future::poll_fn(|_cx| match self.resume.take() {
+            Some(r) => Poll::Ready(r),
+            None => Poll::Pending,
+        })
+        .await
+    }
+
+    /// await in an async generator, the body is always suspended
+    pub(crate) async fn await_value(&self, value: JValue) -> Result<JValue, JValue> {
+        let mut suspended = false;
+        std::future::poll_fn(|cx| {
+            if std::mem::replace(&mut suspended, true) {
+                return Poll::Ready(());
+            }
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await;
+
+        PromiseFuture::new(self.runtime.clone(), value)
+            .await
+            .map_err(Runtime::value_from_error)
+    }
+
+    /// 27.5.3.7 GeneratorYield and 27.6.3.8 AsyncGeneratorYield
+    pub(crate) async fn yield_value(&self, value: JValue) -> Result<Resumed, JValue> {
+        let value = if self.is_async {
+            self.await_value(value).await?
+        } else {
+            value
+        };
+
+        match self.suspend(Suspended::Yield(value)).await {
+            GeneratorResume::Next(v) => Ok(Resumed::Normal(v)),
+            GeneratorResume::Throw(e) => Err(e),
+            GeneratorResume::Return(v) if self.is_async => {
+                Ok(Resumed::Return(self.await_value(v).await?))
+            }
+            GeneratorResume::Return(v) => Ok(Resumed::Return(v)),
+        }
+    }
+
+    /// 15.5.5 yield* iterable, the resumptions of the generator are forwarded to the iterator.
+    ///
+    /// the stack is the operational stack of the body
+    pub(crate) async fn delegate(
+        &self,
+        stack: *mut JValue,
+        iterable: JValue,
+    ) -> Result<Resumed, JValue> {
+        let re = self.delegate_to(stack, iterable).await;
+        // the iterator is no longer reachable by the body
+        self.delegated.set(None);
+        re
+    }
+
+    async fn delegate_to(&self, stack: *mut JValue, iterable: JValue) -> Result<Resumed, JValue> {
+        let ctx = JSContext {
+            stack,
+            runtime: &self.runtime,
+        };
+
+        let (iterator, from_sync) = self.get_iterator(iterable, ctx)?;
+        let next = iterator.get_property(NAMES["next"], ctx)?;
+        // marked by the generator while the body is suspended
+        self.delegated.set(Some((iterator, next)));
+        let mut received = GeneratorResume::Next(JValue::UNDEFINED);
+
+        loop {
+            let inner_result = match received {
+                GeneratorResume::Next(v) => {
+                    let re = next.call_with_context(iterator, &[v], ctx)?;
+                    self.inner_result(re, from_sync).await?
+                }
+                GeneratorResume::Throw(e) => {
+                    let throw = iterator.get_method(NAMES["throw"], ctx)?;
+                    if throw.is_undefined() {
+                        // the iterator is closed before the protocol violation is reported
+                        self.close_iterator(iterator, from_sync, ctx).await?;
+                        return Err(Error::TypeError(
+                            "the iterator does not provide a 'throw' method".to_string(),
+                        )
+                        .into());
+                    }
+                    let re = throw.call_with_context(iterator, &[e], ctx)?;
+                    self.inner_result(re, from_sync).await?
+                }
+                GeneratorResume::Return(v) => {
+                    let ret = iterator.get_method(NAMES["return"], ctx)?;
+                    if ret.is_undefined() {
+                        let v = if self.is_async {
+                            self.await_value(v).await?
+                        } else {
+                            v
+                        };
+                        return Ok(Resumed::Return(v));
+                    }
+                    let re = ret.call_with_context(iterator, &[v], ctx)?;
+                    let re = self.inner_result(re, from_sync).await?;
+                    if re.get_property(NAMES["done"], ctx)?.to_bool() {
+                        let value = self.inner_value(re, from_sync, ctx).await?;
+                        return Ok(Resumed::Return(value));
+                    }
+                    re
+                }
+            };
+
+            if inner_result.get_property(NAMES["done"], ctx)?.to_bool() {
+                let value = self.inner_value(inner_result, from_sync, ctx).await?;
+                return Ok(Resumed::Normal(value));
+            }
+
+            received = if self.is_async {
+                // the value of an async iterator is not awaited again
+                let value = self.inner_value(inner_result, from_sync, ctx).await?;
+                self.suspend(Suspended::Yield(value)).await
+            } else {
+                self.suspend(Suspended::Delegate(inner_result)).await
+            };
+        }
+    }
+
+    /// 7.4.2 GetIterator, an async generator falls back to the sync iterator.
+    ///
+    /// return the iterator and whether it is a sync iterator used asynchronously
+    fn get_iterator(&self, iterable: JValue, ctx: JSContext) -> Result<(JValue, bool), JValue> {
+        if self.is_async {
+            let method = iterable.get_method(SYMBOLS["asyncIterator"], ctx)?;
+            if !method.is_undefined() {
+                let iterator = method.call_with_context(iterable, &[], ctx)?;
+                if !iterator.is_object() {
+                    return Err(Error::TypeError(
+                        "[Symbol.asyncIterator]() must return an object".to_string(),
+                    )
+                    .into());
+                }
+                return Ok((iterator, false));
+            }
+        }
+
+        let method = iterable.get_method(SYMBOLS["iterator"], ctx)?;
+        if method.is_undefined() {
+            return Err(Error::TypeError("yield* operand is not iterable".to_string()).into());
+        }
+        let iterator = method.call_with_context(iterable, &[], ctx)?;
+        if !iterator.is_object() {
+            return Err(
+                Error::TypeError("[Symbol.iterator]() must return an object".to_string()).into(),
+            );
+        }
+        Ok((iterator, self.is_async))
+    }
+
+    /// the result of next, throw or return of the iterator must be an object
+    async fn inner_result(&self, result: JValue, from_sync: bool) -> Result<JValue, JValue> {
+        let result = if self.is_async && !from_sync {
+            self.await_value(result).await?
+        } else {
+            result
+        };
+        if !result.is_object() {
+            return Err(Error::TypeError(format!(
+                "iterator result {} is not an object",
+                result.to_string()
+            ))
+            .into());
+        }
+        Ok(result)
+    }
+
+    /// the value of an iterator result, the value of a sync iterator is awaited
+    /// as by 27.1.4.4 AsyncFromSyncIteratorContinuation
+    async fn inner_value(
+        &self,
+        result: JValue,
+        from_sync: bool,
+        ctx: JSContext<'_>,
+    ) -> Result<JValue, JValue> {
+        let value = result.get_property(NAMES["value"], ctx)?;
+        if from_sync {
+            return self.await_value(value).await;
+        }
+        Ok(value)
+    }
+
+    /// 7.4.8 IteratorClose and 7.4.10 AsyncIteratorClose with a normal completion
+    async fn close_iterator(
+        &self,
+        iterator: JValue,
+        from_sync: bool,
+        ctx: JSContext<'_>,
+    ) -> Result<(), JValue> {
+        let ret = iterator.get_method(NAMES["return"], ctx)?;
+        if ret.is_undefined() {
+            return Ok(());
+        }
+        let re = ret.call_with_context(iterator, &[], ctx)?;
+        self.inner_result(re, from_sync).await?;
+        Ok(())
+    }
+}
+
+struct GeneratorInner {
+    state: GeneratorState,
+    channel: Rc<GeneratorChannel>,
+    body: Option<Body>,
+    /// the stack of the body, freed when the body completes
+    stack: Option<&'static mut [JValue]>,
+
+    /// 27.6.3.1 the requests of an async generator, completed in order
+    queue: VecDeque<(GeneratorResume, JObject)>,
+    /// an async generator has a task running its requests
+    driving: bool,
+}
+
+impl GeneratorInner {
+    fn complete(&mut self) {
+        self.state = GeneratorState::Completed;
+        // the body is dropped before its stack
+        self.body = None;
+        self.channel.roots.borrow_mut().clear();
+        self.channel.delegated.set(None);
+        if let Some(stack) = self.stack.take() {
+            self.channel.runtime.drop_async_stack(stack);
+        }
+    }
+}
+
+impl Drop for GeneratorInner {
+    fn drop(&mut self) {
+        self.complete();
+    }
 }
 
-//#[cfg(nightly)]
 #[derive(Clone)]
 pub struct JSGenerator {
     pub(crate) is_async: bool,
-    pub(crate) generator: Arc<Mutex<dyn Iterator<Item = Result<JValue, JValue>>>>,
-    // stores done or not
-    lock: Arc<FutureMutex<bool>>,
+    inner: Rc<RefCell<GeneratorInner>>,
 }
 
 impl JSGenerator {
-    pub fn resume(&self, mut value: JValue, runtime: &Runtime) -> Result<JValue, JValue> {
-        if self.is_async {
-            let lock = self.lock.clone();
-            let generator = self.generator.clone();
+    /// 10.2.1.4 OrdinaryCallEvaluateBody of a generator function,
+    /// the body is started by the first call to next
+    pub(crate) fn create(
+        func: &JSFunction,
+        runtime: &Runtime,
+        this: JValue,
+        args: &[JValue],
+        capture_stack: CaptureStack,
+    ) -> JObject {
+        let channel = Rc::new(GeneratorChannel {
+            is_async: func.is_async,
+            runtime: runtime.arc(),
+            resume: Cell::new(None),
+            suspended: Cell::new(None),
+            roots: RefCell::new(Vec::new()),
+            delegated: Cell::new(None),
+        });
 
-            // create a future
-            let f = async move {
-                let lock_guard = lock.lock().await;
+        // the arguments are copied to the stack of the body
+        let stack = runtime
+            .to_mut()
+            .get_async_stack(DEFAULT_STACK_SIZE * JValue::SIZE);
+        stack[..args.len()].copy_from_slice(args);
+        let argc = args.len();
+        let len = stack.len();
+        let ptr = stack.as_mut_ptr();
 
-                if *lock_guard {
-                    let obj = JObject::new();
-                    obj.insert_property(NAMES["value"], JValue::UNDEFINED, PropFlag::THREE);
-                    obj.insert_property(NAMES["done"], true.into(), PropFlag::THREE);
-                    return Ok(obj.into());
-                };
+        let args = unsafe { std::slice::from_raw_parts(ptr, argc) };
+        let body_stack = unsafe { std::slice::from_raw_parts_mut(ptr.add(argc), len - argc) };
 
-                let mut regs = [JValue::UNDEFINED; 3];
+        let cd = capture_stack.data();
+        let cap = cd.as_ref().and_then(|v| Some(v.as_ref().as_ref()));
+        let cap = unsafe { std::mem::transmute_copy(&cap) };
 
-                let re = loop {
-                    let runtime = Runtime::current();
-                };
-
-                drop(lock_guard);
-                return re;
-            };
+        let intpr = Interpreter::function(
+            runtime,
+            body_stack,
+            argc + func.largest_stack_offset as usize,
+            capture_stack,
+            cap,
+        );
+        let body = intpr.run_generator(this, args, func.bytecodes.clone(), channel.clone());
+        // the capture stack is kept alive by the body
+        let body = Box::pin(async move {
+            let re = body.await;
+            drop(cd);
+            re
+        });
 
-            let p = runtime.to_mut().run_async(f);
-            let obj = JObject::with_promise(p);
+        let generator = JSGenerator {
+            is_async: func.is_async,
+            inner: Rc::new(RefCell::new(GeneratorInner {
+                state: GeneratorState::SuspendedStart,
+                channel,
+                body: Some(body),
+                stack: Some(stack),
+                queue: VecDeque::new(),
+                driving: false,
+            })),
+        };
 
-            return Ok(obj.into());
+        let inner = runtime.allocate_obj();
+        inner.__proto__ = Some(if func.is_async {
+            runtime.prototypes.async_generator
         } else {
-            let value = self.generator.lock().next();
-
-            match value {
-                Some(v) => v,
-                None => {
-                    let obj = JObject::new();
-                    obj.insert_property(NAMES["value"], JValue::UNDEFINED, PropFlag::THREE);
-                    obj.insert_property(NAMES["done"], true.into(), PropFlag::THREE);
-                    return Ok(obj.into());
+            runtime.prototypes.generator
+        });
+        inner.wrapped_value = JObjectValue::Generator(Box::new(generator));
+        JObject { inner }
+    }
+
+    /// poll the body, it is resumed with the value if it is suspended at a yield
+    fn poll_body(&self, resume: Option<GeneratorResume>, cx: &mut Context) -> Step {
+        // the body is taken out while running, the generator may be resumed again by it
+        let (body, channel) = {
+            let mut inner = self.inner.borrow_mut();
+            inner.state = GeneratorState::Executing;
+            (inner.body.take(), inner.channel.clone())
+        };
+        let mut body = match body {
+            Some(b) => b,
+            None => {
+                self.inner.borrow_mut().complete();
+                return Step::Completed(Ok(JValue::UNDEFINED));
+            }
+        };
+
+        channel.resume.set(resume);
+        let p = body.as_mut().poll(cx);
+
+        let mut inner = self.inner.borrow_mut();
+        inner.body = Some(body);
+        match p {
+            Poll::Ready(re) => {
+                inner.complete();
+                Step::Completed(re)
+            }
+            Poll::Pending => match channel.suspended.take() {
+                Some(s) => {
+                    inner.state = GeneratorState::SuspendedYield;
+                    Step::Suspended(s)
                 }
+                None => Step::Pending,
+            },
+        }
+    }
+
+    /// 27.5.3.3 GeneratorResume and 27.5.3.4 GeneratorResumeAbrupt
+    pub(crate) fn resume(&self, ctx: JSContext, resume: GeneratorResume) -> Result<JValue, JValue> {
+        let state = self.inner.borrow().state;
+        if state == GeneratorState::Executing {
+            return Err(Error::TypeError("Generator is already running".to_string()).into());
+        }
+
+        if state == GeneratorState::SuspendedStart {
+            if let GeneratorResume::Throw(_) | GeneratorResume::Return(_) = resume {
+                self.inner.borrow_mut().complete();
             }
         }
+
+        let state = self.inner.borrow().state;
+        let step = match (state, resume) {
+            (GeneratorState::Completed, GeneratorResume::Next(_)) => {
+                Step::Completed(Ok(JValue::UNDEFINED))
+            }
+            (GeneratorState::Completed, GeneratorResume::Return(v)) => Step::Completed(Ok(v)),
+            (GeneratorState::Completed, GeneratorResume::Throw(e)) => Step::Completed(Err(e)),
+            (GeneratorState::SuspendedStart, _) => {
+                let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+                self.poll_body(None, &mut cx)
+            }
+            (_, resume) => {
+                let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+                self.poll_body(Some(resume), &mut cx)
+            }
+        };
+
+        match step {
+            Step::Suspended(Suspended::Yield(v)) => Ok(iter_result(ctx.runtime, v, false)),
+            Step::Suspended(Suspended::Delegate(r)) => Ok(r),
+            Step::Completed(Ok(v)) => Ok(iter_result(ctx.runtime, v, true)),
+            Step::Completed(Err(e)) => Err(e),
+            Step::Pending => unreachable!("a generator cannot await"),
+        }
+    }
+
+    /// 27.6.3.2 AsyncGeneratorEnqueue, the promise is settled when the request is completed
+    pub(crate) fn enqueue(&self, runtime: &Runtime, resume: GeneratorResume) -> JObject {
+        let promise = promise::new_promise(runtime);
+
+        let mut inner = self.inner.borrow_mut();
+        inner.queue.push_back((resume, promise));
+        if inner.driving {
+            return promise;
+        }
+        inner.driving = true;
+        drop(inner);
+
+        let generator = self.clone();
+        let rt = runtime.arc();
+        let task = runtime.spawn_task(std::future::poll_fn(move |cx| {
+            rt.enter_execution();
+            let p = generator.drain(&rt, cx);
+            rt.interrupt.exit();
+            p
+        }));
+
+        // the body runs until it awaits or yields
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let _ = runtime.poll_future(task, &mut cx);
+
+        promise
     }
+
+    /// 27.6.3.10 AsyncGeneratorDrainQueue, run the requests until the queue is empty
+    fn drain(&self, runtime: &Runtime, cx: &mut Context) -> Poll<Result<JValue, JValue>> {
+        loop {
+            let (resume, promise, state) = {
+                let mut inner = self.inner.borrow_mut();
+                match inner.queue.front() {
+                    Some((r, p)) => (*r, *p, inner.state),
+                    None => {
+                        inner.driving = false;
+                        return Poll::Ready(Ok(JValue::UNDEFINED));
+                    }
+                }
+            };
+
+            let step = match (state, resume) {
+                // awaiting in the body
+                (GeneratorState::Executing, _) => self.poll_body(None, cx),
+                (GeneratorState::SuspendedStart, GeneratorResume::Next(_)) => {
+                    self.poll_body(None, cx)
+                }
+                (GeneratorState::SuspendedStart, _) => {
+                    self.inner.borrow_mut().complete();
+                    continue;
+                }
+                (GeneratorState::SuspendedYield, resume) => self.poll_body(Some(resume), cx),
+                (GeneratorState::Completed, GeneratorResume::Next(_)) => {
+                    Step::Completed(Ok(JValue::UNDEFINED))
+                }
+                (GeneratorState::Completed, GeneratorResume::Throw(e)) => Step::Completed(Err(e)),
+                // 27.6.3.9 AsyncGeneratorAwaitReturn, the value is awaited by a body
+                (GeneratorState::Completed, GeneratorResume::Return(v)) => {
+                    let channel = self.inner.borrow().channel.clone();
+                    self.inner.borrow_mut().body =
+                        Some(Box::pin(async move { channel.await_value(v).await }));
+                    self.poll_body(None, cx)
+                }
+            };
+
+            let re = match step {
+                Step::Pending => return Poll::Pending,
+                Step::Suspended(Suspended::Yield(v)) => Ok(iter_result(runtime, v, false)),
+                Step::Suspended(Suspended::Delegate(r)) => Ok(r),
+                Step::Completed(Ok(v)) => Ok(iter_result(runtime, v, true)),
+                Step::Completed(Err(e)) => Err(e),
+            };
+
+            // 27.6.3.4 AsyncGeneratorCompleteStep
+            self.inner.borrow_mut().queue.pop_front();
+            match re {
+                Ok(v) => promise::fulfill_promise(runtime, promise, v),
+                Err(e) => promise::reject_promise(runtime, promise, e),
+            }
+        }
+    }
+
+    pub(crate) unsafe fn trace(&self) {
+        let inner = self.inner.borrow();
+        for (resume, promise) in &inner.queue {
+            resume.trace();
+            promise.trace();
+        }
+        if let Some(r) = inner.channel.resume.get() {
+            r.trace();
+        }
+        if let Some(Suspended::Yield(v) | Suspended::Delegate(v)) = inner.channel.suspended.get() {
+            v.trace();
+        }
+        inner.channel.roots.borrow().iter().for_each(|v| v.trace());
+        if let Some((iterator, next)) = inner.channel.delegated.get() {
+            iterator.trace();
+            next.trace();
+        }
+    }
+}
+
+/// 7.4.14 CreateIterResultObject ( value, done )
+fn iter_result(runtime: &Runtime, value: JValue, done: bool) -> JValue {
+    let obj = runtime.create_object();
+    obj.insert_property(NAMES["value"], value, PropFlag::THREE);
+    obj.insert_property(NAMES["done"], done.into(), PropFlag::THREE);
+    obj.into()
 }
@@ -40,6 +40,8 @@ pub struct BuiltinPrototypes {
     pub data_view: JObject,
     pub weak_ref: JObject,
     pub finalization_registry: JObject,
+    pub generator: JObject,
+    pub async_generator: JObject,
 }
 
 impl BuiltinPrototypes {
@@ -69,6 +71,8 @@ impl BuiltinPrototypes {
                 data_view: o,
                 weak_ref: o,
                 finalization_registry: o,
+                generator: o,
+                async_generator: o,
             }
         }
     }
@@ -96,13 +100,45 @@ impl BuiltinPrototypes {
             data_view: rt.create_object().into(),
             weak_ref: rt.create_object().into(),
             finalization_registry: rt.create_object().into(),
+            generator: rt.create_object().into(),
+            async_generator: rt.create_object().into(),
         };
     }
 
+    /// all the prototypes in declaration order
+    pub(crate) fn all(&self) -> [JObject; 23] {
+        [
+            self.object,
+            self.function,
+            self.boolean,
+            self.symbol,
+            self.error,
+            self.number,
+            self.bigint,
+            self.date,
+            self.string,
+            self.regex,
+            self.array,
+            self.typed_array,
+            self.promise,
+            self.map,
+            self.set,
+            self.weakmap,
+            self.weakset,
+            self.array_buffer,
+            self.data_view,
+            self.weak_ref,
+            self.finalization_registry,
+            self.generator,
+            self.async_generator,
+        ]
+    }
+
     #[inline]
     pub unsafe fn trace(&self) {
         self.array.trace();
         self.array_buffer.trace();
+        self.async_generator.trace();
         self.bigint.trace();
         self.boolean.trace();
         self.data_view.trace();
@@ -110,6 +146,7 @@ impl BuiltinPrototypes {
         self.error.trace();
         self.finalization_registry.trace();
         self.function.trace();
+        self.generator.trace();
         self.map.trace();
         self.number.trace();
         self.regex.trace();
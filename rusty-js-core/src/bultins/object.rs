@@ -14,10 +14,10 @@ use crate::{JSBigInt, JSContext};
 
 use crate::utils::string_interner::NAMES;
 
-use super::class::JSClassInstance;
+use super::class::{JSClassInstance, PrivateElements};
 use super::function::JSFunctionInstance;
 use super::generator::JSGenerator;
-use super::promise::Promise;
+use super::promise::{Promise, PromiseResolver};
 use super::proxy::Proxy;
 use super::regex::RegExp;
 use super::strings::JSString;
@@ -172,9 +172,24 @@ impl JObject {
         let rt = Runtime::current();
         let inner = rt.allocate_obj();
         inner.__proto__ = Some(rt.prototypes.error);
+
+        let name = e.name();
+        let message = e.message();
         inner.wrapped_value = JObjectValue::Error(Box::new(e));
 
-        return JObject { inner: inner };
+        let obj = JObject { inner: inner };
+        obj.insert_property(
+            NAMES["name"],
+            JValue::create_static_string(name),
+            PropFlag::CONFIGURABLE,
+        );
+        obj.insert_property(
+            NAMES["message"],
+            JValue::create_string(message.into()),
+            PropFlag::CONFIGURABLE,
+        );
+
+        return obj;
     }
 
     pub fn with_promise(p: Promise) -> Self {
@@ -263,6 +278,13 @@ impl JObject {
         self.as_native_function().is_some()
     }
 
+    pub fn is_promise_resolver(&self) -> bool {
+        match &self.inner.wrapped_value {
+            JObjectValue::PromiseResolver(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn as_regexp(&self) -> Option<&mut RegExp> {
         match &mut self.inner.to_mut().wrapped_value {
             JObjectValue::Regex(r) => Some(r),
@@ -292,6 +314,17 @@ impl JObject {
         }
     }
 
+    pub fn as_generator(&self) -> Option<&JSGenerator> {
+        match &self.inner.wrapped_value {
+            JObjectValue::Generator(g) => Some(g.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn is_generator(&self) -> bool {
+        self.as_generator().is_some()
+    }
+
     pub fn is_error(&self) -> bool {
         self.as_error().is_some()
     }
@@ -390,7 +423,7 @@ impl JObject {
             if cell.flag.is_getter() {
                 let this = JValue::create_object(*self);
 
-                return cell.value.call(this, &[], ctx);
+                return cell.value.call_with_context(this, &[], ctx);
             } else if cell.flag.is_setter() {
                 return Ok(JValue::UNDEFINED);
             }
@@ -410,7 +443,7 @@ impl JObject {
                     if cell.flag.is_getter() {
                         let this = JValue::create_object(p);
 
-                        return cell.value.call(this, &[], ctx);
+                        return cell.value.call_with_context(this, &[], ctx);
                     } else if cell.flag.is_setter() {
                         return Ok(JValue::UNDEFINED);
                     }
@@ -431,6 +464,11 @@ impl JObject {
                 self.inner.to_mut().__proto__ = Some(obj);
             }
         }
+        self.insert_own_property(key, value, flag);
+    }
+
+    /// inserts an own data property, a `__proto__` key does not change the prototype
+    pub(crate) fn insert_own_property(&self, key: PropKey, value: JValue, flag: PropFlag) {
         self.inner.to_mut().values.insert(
             key,
             PropCell {
@@ -441,6 +479,26 @@ impl JObject {
         );
     }
 
+    /// reads an own property, a `__proto__` key reads the own value instead of the prototype
+    pub(crate) fn get_own_property(&self, key: PropKey, ctx: JSContext) -> Result<JValue, JValue> {
+        match self.inner.values.get(&key) {
+            Some(cell) if cell.flag.is_getter() => {
+                cell.value.call_with_context(JValue::create_object(*self), &[], ctx)
+            }
+            Some(cell) if !cell.flag.is_setter() => Ok(cell.value),
+            _ => Ok(JValue::UNDEFINED),
+        }
+    }
+
+    /// sets the prototype without creating an own `__proto__` property
+    pub fn set_prototype(&self, proto: JValue) {
+        if let Some(obj) = proto.as_object() {
+            self.inner.to_mut().__proto__ = Some(obj);
+        } else if proto.is_null() {
+            self.inner.to_mut().__proto__ = None;
+        }
+    }
+
     pub fn insert_property_builtin(&self, key: PropKey, value: JValue) {
         self.insert_property(key, value, PropFlag::BUILTIN)
     }
@@ -465,7 +523,7 @@ impl JObject {
             if cell.flag.is_setter() {
                 let this = JValue::create_object(*self);
 
-                cell.setter.call(this, &[value], ctx)?;
+                cell.setter.call_with_context(this, &[value], ctx)?;
                 return Ok(());
             } else if cell.flag.is_getter() {
                 // do nothing
@@ -570,19 +628,32 @@ impl JObject {
         if let Some(f) = self.as_native_function() {
             let mut guard = f.write();
             let args = unsafe { std::slice::from_raw_parts(stack, argc) };
-            let re = (guard.deref_mut())(
+            let top = unsafe { stack.add(argc) };
+
+            // calls from the host made by the native start above its arguments
+            let previous = runtime.to_mut().host_stack_top.replace(top);
+            let re = (guard.deref_mut())(JSContext { stack: top, runtime }, this, args);
+            runtime.to_mut().host_stack_top = previous;
+            match re {
+                Ok(v) => return (v, false),
+                Err(e) => return (e, true),
+            }
+        };
+
+        if let JObjectValue::PromiseResolver(r) = &self.inner.wrapped_value {
+            let args = unsafe { std::slice::from_raw_parts(stack, argc) };
+            let re = r.call(
                 JSContext {
                     stack: unsafe { stack.add(argc) },
                     runtime,
                 },
-                this,
-                args,
+                args.first().copied().unwrap_or(JValue::UNDEFINED),
             );
             match re {
                 Ok(v) => return (v, false),
                 Err(e) => return (e, true),
             }
-        };
+        }
 
         return (JValue::from(Error::CallOnNonFunction), true);
     }
@@ -620,6 +691,12 @@ impl JObject {
         }
 
         self.inner.wrapped_value.trace();
+
+        if let Some(elements) = &self.inner.private_elements {
+            for element in elements.values() {
+                element.trace();
+            }
+        }
     }
 }
 
@@ -651,6 +728,9 @@ pub struct JObjectInner {
     pub(crate) values: PropMap,
 
     pub(crate) wrapped_value: JObjectValue,
+
+    // 8 bytes, the private fields, methods and accessors
+    pub(crate) private_elements: Option<Box<PrivateElements>>,
 }
 
 impl Default for JObjectInner {
@@ -662,6 +742,7 @@ impl Default for JObjectInner {
             values: Default::default(),
             __proto__: None,
             wrapped_value: JObjectValue::Empty,
+            private_elements: None,
         }
     }
 }
@@ -705,6 +786,7 @@ pub enum JObjectValue {
 
     Regex(Box<RegExp>),
     Promise(Box<Promise>),
+    PromiseResolver(Box<PromiseResolver>),
     Proxy(Box<Proxy>),
 
     Map(Box<HashMap<JValue, JValue>>),
@@ -825,6 +907,11 @@ impl JObjectValue {
                     return a.get(v as usize).and_then(|v| Some(v.1));
                 }
             }
+            Self::Module(id) => {
+                // the exports of a module namespace
+                let rt = Runtime::current();
+                return rt.get_namespace_property(*id, key);
+            }
             _ => {}
         };
         None
@@ -844,15 +931,11 @@ impl JObjectValue {
             Self::Function(f) => {
                 f.trace();
             }
-            Self::Generator(_g) => {}
+            Self::Generator(g) => {
+                g.trace();
+            }
             Self::Class(c) => {
-                if let Some(f) = &c.constructor_instance {
-                    f.trace();
-                }
-
-                if let Some(v) = &c.super_ {
-                    v.trace();
-                }
+                c.trace();
             }
             Self::Map(m) => {
                 for (key, v) in m.iter() {
@@ -872,10 +955,16 @@ impl JObjectValue {
                     Promise::Rejected(r) => {
                         r.trace();
                     }
+                    Promise::Unresolved { reactions } => {
+                        reactions.iter().for_each(|r| r.trace());
+                    }
                     Promise::Pending { id: _ } => {}
                     Promise::ForeverPending => {}
                 };
             }
+            Self::PromiseResolver(r) => {
+                r.promise.trace();
+            }
             Self::Set(s) => {
                 for (key, _) in s.iter() {
                     key.trace();
@@ -3,7 +3,7 @@ use std::{ops, collections::HashMap};
 use crate::{Runtime, JValue, utils::nohasher::NoHasherBuilder};
 
 #[derive(Debug, Clone, Copy)]
-pub struct PropFlag(u8);
+pub struct PropFlag(pub(crate) u8);
 
 impl PropFlag {
     pub const NONE: PropFlag = PropFlag(0);
@@ -75,7 +75,9 @@ impl ToProperyKey for PropKey {
 
 impl ToProperyKey for JValue {
     fn to_key(&self, runtime: &Runtime) -> PropKey {
-        if let Some(s) = self.as_string() {
+        if let Some(sym) = self.as_symbol() {
+            return PropKey(sym);
+        } else if let Some(s) = self.as_string() {
             let id = runtime.register_field_name(s.as_ref());
             return PropKey(id);
         } else {
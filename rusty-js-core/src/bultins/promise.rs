@@ -1,10 +1,268 @@
-use crate::runtime::AsyncId;
+use std::cell::Cell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::bultins::object::{JObject, JObjectValue};
+use crate::error::Error;
+use crate::runtime::{AsyncId, Microtask};
+use crate::utils::string_interner::NAMES;
 use crate::value::JValue;
+use crate::{JSContext, Runtime};
 
 #[derive(Clone)]
 pub enum Promise {
-    Pending { id: AsyncId },
+    /// backed by a future registered with `Runtime::run_async`
+    Pending {
+        id: AsyncId,
+    },
+    /// waiting to be settled by its resolving functions
+    Unresolved {
+        reactions: Vec<PromiseReaction>,
+    },
     Fulfilled(JValue),
     Rejected(JValue),
     ForeverPending,
 }
+
+/// a pair of handlers registered by `then`,
+/// the result of the handler settles the derived promise
+#[derive(Clone)]
+pub struct PromiseReaction {
+    pub(crate) capability: Option<JObject>,
+    pub(crate) on_fulfilled: JValue,
+    pub(crate) on_rejected: JValue,
+}
+
+impl PromiseReaction {
+    pub(crate) unsafe fn trace(&self) {
+        if let Some(p) = &self.capability {
+            p.trace();
+        }
+        self.on_fulfilled.trace();
+        self.on_rejected.trace();
+    }
+}
+
+/// the resolve or reject function of a promise,
+/// the pair shares the already resolved flag
+#[derive(Clone)]
+pub struct PromiseResolver {
+    pub(crate) promise: JObject,
+    pub(crate) reject: bool,
+    pub(crate) already_resolved: Rc<Cell<bool>>,
+}
+
+impl PromiseResolver {
+    pub(crate) fn call(&self, ctx: JSContext, value: JValue) -> Result<JValue, JValue> {
+        if self.already_resolved.replace(true) {
+            return Ok(JValue::UNDEFINED);
+        }
+        if self.reject {
+            reject_promise(ctx.runtime, self.promise, value);
+        } else {
+            resolve_promise(ctx, self.promise, value);
+        }
+        Ok(JValue::UNDEFINED)
+    }
+}
+
+/// create a promise that is settled by its resolving functions
+pub(crate) fn new_promise(runtime: &Runtime) -> JObject {
+    let inner = runtime.allocate_obj();
+    inner.__proto__ = Some(runtime.prototypes.promise);
+    inner.wrapped_value = JObjectValue::Promise(Box::new(Promise::Unresolved {
+        reactions: Vec::new(),
+    }));
+    JObject { inner }
+}
+
+/// 27.2.1.3 CreateResolvingFunctions ( promise )
+pub(crate) fn create_resolving_functions(
+    runtime: &Runtime,
+    promise: JObject,
+) -> (JObject, JObject) {
+    let already_resolved = Rc::new(Cell::new(false));
+
+    let create = |reject: bool| {
+        let inner = runtime.allocate_obj();
+        inner.__proto__ = Some(runtime.prototypes.function);
+        inner.wrapped_value = JObjectValue::PromiseResolver(Box::new(PromiseResolver {
+            promise,
+            reject,
+            already_resolved: already_resolved.clone(),
+        }));
+        JObject { inner }
+    };
+
+    let resolve = create(false);
+    runtime.user_own_value(resolve.into());
+    let reject = create(true);
+    runtime.user_drop_value(resolve.into());
+
+    (resolve, reject)
+}
+
+/// 27.2.1.3.2 Promise Resolve Functions, steps 7 to 16
+pub(crate) fn resolve_promise(ctx: JSContext, promise: JObject, resolution: JValue) {
+    if let Some(obj) = resolution.as_object() {
+        if obj == promise {
+            reject_promise(
+                ctx.runtime,
+                promise,
+                Error::TypeError("chaining cycle detected for promise".to_string()).into(),
+            );
+            return;
+        }
+
+        let then = match resolution.get_property(NAMES["then"], ctx) {
+            Ok(v) => v,
+            Err(e) => {
+                reject_promise(ctx.runtime, promise, e);
+                return;
+            }
+        };
+
+        if then.is_callable() {
+            ctx.runtime.enqueue_microtask(Microtask::ResolveThenable {
+                promise,
+                thenable: resolution,
+                then,
+            });
+            return;
+        }
+    }
+    fulfill_promise(ctx.runtime, promise, resolution);
+}
+
+/// 27.2.1.4 FulfillPromise ( promise, value )
+pub(crate) fn fulfill_promise(runtime: &Runtime, promise: JObject, value: JValue) {
+    settle_promise(runtime, promise, Promise::Fulfilled(value), value, false);
+}
+
+/// 27.2.1.7 RejectPromise ( promise, reason )
+pub(crate) fn reject_promise(runtime: &Runtime, promise: JObject, reason: JValue) {
+    settle_promise(runtime, promise, Promise::Rejected(reason), reason, true);
+}
+
+fn settle_promise(
+    runtime: &Runtime,
+    promise: JObject,
+    state: Promise,
+    argument: JValue,
+    rejected: bool,
+) {
+    let p = match promise.as_promise() {
+        Some(p) => p,
+        None => return,
+    };
+    let reactions = match p {
+        Promise::Unresolved { reactions } => std::mem::take(reactions),
+        _ => return,
+    };
+    *p = state;
+    runtime.wake_promise(promise);
+
+    if rejected && reactions.is_empty() {
+        runtime.track_rejection(promise, argument);
+    }
+
+    // 27.2.1.8 TriggerPromiseReactions ( reactions, argument )
+    for reaction in reactions {
+        runtime.enqueue_microtask(Microtask::Reaction {
+            reaction,
+            argument,
+            rejected,
+        });
+    }
+}
+
+/// 27.2.5.4.1 PerformPromiseThen ( promise, onFulfilled, onRejected [ , resultCapability ] )
+pub(crate) fn perform_then(runtime: &Runtime, promise: JObject, reaction: PromiseReaction) {
+    let p = match promise.as_promise() {
+        Some(p) => p,
+        None => return,
+    };
+
+    // a future backed promise is polled by the microtask checkpoint
+    if let Promise::Pending { id } = p {
+        runtime.await_future(*id, promise);
+        *p = Promise::Unresolved {
+            reactions: Vec::new(),
+        };
+    }
+
+    if let Promise::Rejected(_) = p {
+        runtime.promise_handled(promise);
+    }
+
+    match p {
+        Promise::Unresolved { reactions } => reactions.push(reaction),
+        Promise::Fulfilled(v) => runtime.enqueue_microtask(Microtask::Reaction {
+            reaction,
+            argument: *v,
+            rejected: false,
+        }),
+        Promise::Rejected(e) => runtime.enqueue_microtask(Microtask::Reaction {
+            reaction,
+            argument: *e,
+            rejected: true,
+        }),
+        // never settles, the reaction is never run
+        Promise::ForeverPending => {}
+        Promise::Pending { .. } => unreachable!(),
+    }
+}
+
+/// a future settled with the promise, created by `JValue::into_future`
+pub(crate) struct PromiseFuture {
+    runtime: Arc<Runtime>,
+    value: JValue,
+}
+
+impl PromiseFuture {
+    pub(crate) fn new(runtime: Arc<Runtime>, value: JValue) -> Self {
+        runtime.user_own_value(value);
+        Self { runtime, value }
+    }
+}
+
+impl Future for PromiseFuture {
+    type Output = Result<JValue, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let promise = match self.value.as_object() {
+            Some(obj) if obj.is_promise() => obj,
+            // other values are settled immediately, like await
+            _ => return Poll::Ready(Ok(self.value)),
+        };
+
+        match promise.as_promise().unwrap() {
+            Promise::Fulfilled(v) => Poll::Ready(Ok(*v)),
+            Promise::Rejected(e) => {
+                self.runtime.promise_handled(promise);
+                Poll::Ready(Err(Runtime::error_from_value(*e)))
+            }
+            Promise::ForeverPending => Poll::Ready(Err(Error::AwaitOnForeverPendingPromise)),
+            Promise::Pending { id } => {
+                let id = *id;
+                let rt = &self.runtime;
+                rt.with_attached(|| rt.poll_future(id, cx))
+                    .map(|re| re.map_err(Runtime::error_from_value))
+            }
+            // woken when the promise is settled
+            Promise::Unresolved { .. } => {
+                self.runtime.register_promise_waker(promise, cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for PromiseFuture {
+    fn drop(&mut self) {
+        self.runtime.user_drop_value(self.value);
+    }
+}
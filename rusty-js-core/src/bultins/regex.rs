@@ -41,6 +41,33 @@ impl RegExpFlags {
     pub fn sticky(self) -> bool {
         self & Self::Sticky
     }
+
+    /// the flags as written in a regex literal
+    pub fn to_flags_string(self) -> String {
+        let mut s = String::new();
+        if self.has_indices() {
+            s.push('d');
+        }
+        if self.global() {
+            s.push('g');
+        }
+        if self.ignore_case() {
+            s.push('i');
+        }
+        if self.multiline() {
+            s.push('m');
+        }
+        if self.dotall() {
+            s.push('s');
+        }
+        if self.unicode() {
+            s.push('u');
+        }
+        if self.sticky() {
+            s.push('y');
+        }
+        s
+    }
 }
 
 impl std::ops::BitOr for RegExpFlags {
@@ -65,6 +92,8 @@ impl std::ops::BitAnd for RegExpFlags {
 
 #[derive(Clone)]
 pub struct RegExp {
+    /// the pattern the regex is compiled from
+    pub source: String,
     pub flags: RegExpFlags,
     pub last_index: usize,
     pub matcher: regress::Regex,
@@ -114,6 +143,7 @@ impl RegExp {
             Err(e) => return Err(e.to_string()),
         };
         return Ok(Self {
+            source: pattern.to_string(),
             flags: f,
             last_index: 0,
             matcher: m,
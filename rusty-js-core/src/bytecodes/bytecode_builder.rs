@@ -1,7 +1,8 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
 use num_traits::ToPrimitive;
+use swc_atoms::JsWord;
 use swc_ecmascript::ast::*;
 
 use super::function_builder_context::{DeclareKind, FunctionBuilderContext};
@@ -86,6 +87,10 @@ impl FunctionBuilder {
     fn build_function(&mut self, func: &Function) -> Result<(), Error> {
         let mut i = 0;
         for p in &func.params {
+            // the `this` parameter of TypeScript only declares a type
+            if matches!(&p.pat, Pat::Ident(b) if &*b.id.sym == "this") {
+                continue;
+            }
             self.translate_param(p, i)?;
             i += 1;
         }
@@ -164,6 +169,11 @@ impl FunctionBuilder {
                 self.bytecode.push(OpCode::Debugger);
             }
             Stmt::Decl(d) => match d {
+                // ambient declarations and overload signatures have no value
+                Decl::Class(c) if c.declare => {}
+                Decl::Fn(f) if f.declare || f.function.body.is_none() => {}
+                Decl::Var(v) if v.declare => {}
+
                 Decl::Class(class) => {
                     let c =
                         self.translate_class(&class.class, Some(class.ident.sym.to_string()))?;
@@ -196,18 +206,16 @@ impl FunctionBuilder {
                         self.translate_vardeclare(d, v.kind)?;
                     }
                 }
-                Decl::TsEnum(_e) => {
-                    todo!("TS enum")
-                }
-                Decl::TsInterface(_i) => {
-                    todo!("TS interface")
-                }
-                Decl::TsModule(_m) => {
-                    todo!("TS module")
+                Decl::TsEnum(e) => {
+                    if !e.declare {
+                        self.translate_enum(e)?;
+                    }
                 }
-                Decl::TsTypeAlias(_t) => {
-                    todo!("TS type alias")
+                Decl::TsModule(m) => {
+                    self.translate_namespace(m)?;
                 }
+                // types are erased
+                Decl::TsInterface(_) | Decl::TsTypeAlias(_) => {}
             },
             Stmt::DoWhile(d) => {
                 let (header, exit) = self.start_loop(label);
@@ -693,6 +701,45 @@ impl FunctionBuilder {
 
     fn try_check_error(&mut self, throw_value: Register) {}
 
+    /// resolve a private name by the enclosing class bodies, returns its description
+    fn private_name(&self, p: &PrivateName) -> Result<u32, Error> {
+        match self.ctx.get_private_name(&p.id.sym) {
+            Some(name) => Ok(name),
+            None => Err(Error::SyntaxError(
+                format!(
+                    "Private field '#{}' must be declared in an enclosing class",
+                    &p.id.sym
+                )
+                .into(),
+            )),
+        }
+    }
+
+    /// read the class declaring a private name into a register not in use,
+    /// the private names of a class are different on every evaluation of the class
+    fn load_private_name(
+        &mut self,
+        p: &PrivateName,
+        in_use: &[Register],
+    ) -> Result<(Register, u32), Error> {
+        let name = self.private_name(p)?;
+
+        let class = [self.r1, self.r2, self.r3]
+            .into_iter()
+            .find(|r| !in_use.contains(r))
+            .unwrap();
+
+        // the class is held by a variable named after the private name
+        self.bytecode.push(self.ctx.get(
+            &(
+                swc_atoms::JsWord::from(format!("#{}", &p.id.sym)),
+                swc_common::SyntaxContext::empty(),
+            ),
+            class,
+        ));
+        Ok((class, name))
+    }
+
     fn translate_expr(&mut self, expr: &Expr) -> Result<Register, Error> {
         match expr {
             Expr::Array(a) => {
@@ -788,7 +835,7 @@ impl FunctionBuilder {
                             //self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
                             let r = self.translate_pat_assign(&p, rhs, DeclareKind::None)?;
                             self.bytecode.push(OpCode::Mov {
-                                from: rhs,
+                                from: r,
                                 to: self.r1,
                             });
                         }
@@ -814,14 +861,14 @@ impl FunctionBuilder {
                                         MemberProp::PrivateName(p) => {
                                             self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
 
-                                            let id = self
-                                                .runtime
-                                                .register_field_name(&format!("#{}", p.id.sym));
+                                            let (class, name) =
+                                                self.load_private_name(p, &[obj, self.r3])?;
 
-                                            self.bytecode.push(OpCode::WriteFieldStatic {
+                                            self.bytecode.push(OpCode::WritePrivateField {
                                                 obj: obj,
                                                 value: self.r3,
-                                                field_id: id,
+                                                class,
+                                                name,
                                             });
                                         }
                                         MemberProp::Computed(c) => {
@@ -979,14 +1026,14 @@ impl FunctionBuilder {
                                     MemberProp::PrivateName(p) => {
                                         self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
 
-                                        let id = self
-                                            .runtime
-                                            .register_field_name(&format!("#{}", p.id.sym));
+                                        let (class, name) =
+                                            self.load_private_name(p, &[obj, self.r3])?;
 
-                                        self.bytecode.push(OpCode::WriteFieldStatic {
+                                        self.bytecode.push(OpCode::WritePrivateField {
                                             obj: obj,
                                             value: self.r3,
-                                            field_id: id,
+                                            class,
+                                            name,
                                         });
                                     }
                                     MemberProp::Computed(c) => {
@@ -1037,16 +1084,15 @@ impl FunctionBuilder {
             Expr::Bin(b) => {
                 // #name in object
                 if b.left.is_private_name() && b.op == BinaryOp::In {
-                    let name = b.left.as_private_name().unwrap();
-                    let id = self
-                        .runtime
-                        .register_field_name(&format!("#{}", &name.id.sym));
                     let r = self.translate_expr(&b.right)?;
+                    let (class, name) =
+                        self.load_private_name(b.left.as_private_name().unwrap(), &[r])?;
 
                     self.bytecode.push(OpCode::PrivateIn {
                         result: self.r1,
-                        name: id,
                         right: r,
+                        class,
+                        name,
                     });
                     return Ok(self.r1);
                 }
@@ -1080,7 +1126,7 @@ impl FunctionBuilder {
                                 let id = match re{
                                     Ok(r) => r,
                                     Err(e) => {
-                                        return Err(Error::SyntaxError(e))
+                                        return Err(Error::SyntaxError(e.into()))
                                     }
                                 };
                                 JValue::create_object(unsafe{self.runtime.get_regex_object(id)})
@@ -1830,9 +1876,29 @@ impl FunctionBuilder {
                             stack_offset: self.ctx.current_stack_offset(),
                             args_len: c.args.len() as u16
                         });
+
+                        // the private elements of a derived class are added once super() returns
+                        self.bytecode.push(self.ctx.get(
+                            &(
+                                swc_atoms::JsWord::from(SUPER_CONSTRUCTOR_VAR_NAME),
+                                Default::default(),
+                            ),
+                            self.r3,
+                        ));
+                        self.bytecode.push(OpCode::InitializeInstanceElements {
+                            class: self.r3,
+                            this: self.r2,
+                        });
                     }
-                    Callee::Import(i) => {
-                        todo!("dynamic import")
+                    Callee::Import(_i) => {
+                        let url = self.runtime.module_url.clone().unwrap_or_default();
+                        let referrer = self.runtime.to_mut().register_string(&url);
+                        self.bytecode.push(OpCode::DynamicImport {
+                            result: self.r1,
+                            stack_offset: self.ctx.current_stack_offset(),
+                            args_len: c.args.len() as u16,
+                            referrer
+                        });
                     }
                 }
 
@@ -1943,25 +2009,7 @@ impl FunctionBuilder {
                     self.bytecode.push(OpCode::LoadNull { result: self.r1 });
                 }
                 Lit::Num(n) => {
-                    // check if number can be contained in f32
-                    if n.value as f32 as f64 == n.value {
-                        self.bytecode.push(OpCode::LoadStaticFloat32 {
-                            result: self.r1,
-                            value: n.value as f32,
-                        });
-                        
-                    } else {
-                        // register number to the runtime
-                        let id = self
-                            .runtime
-                            .to_mut()
-                            .unamed_constant(JValue::create_number(n.value));
-
-                        self.bytecode.push(OpCode::LoadStaticFloat {
-                            result: self.r1,
-                            id,
-                        });
-                    }
+                    self.load_number(n.value);
                 },
                 Lit::Regex(r) => {
                     // create the regex
@@ -1970,7 +2018,7 @@ impl FunctionBuilder {
                     // return an error if compiling regex failed
                     let id = match re {
                         Ok(v) => v,
-                        Err(e) => return Err(Error::SyntaxError(e)),
+                        Err(e) => return Err(Error::SyntaxError(e.into())),
                     };
                     self.bytecode.push(OpCode::CreateRegExp {
                         result: self.r1,
@@ -2030,14 +2078,15 @@ impl FunctionBuilder {
                         });
                     }
                     MemberProp::PrivateName(p) => {
-                        // register the static field name
-                        let id = self.runtime.register_field_name(&format!("#{}", &p.id));
+                        // resolve the private name
+                        let (class, name) = self.load_private_name(p, &[obj])?;
 
                         // read the field from object
-                        self.bytecode.push(OpCode::ReadFieldStatic {
+                        self.bytecode.push(OpCode::ReadPrivateField {
                             obj: obj,
                             result: self.r1,
-                            field_id: id,
+                            class,
+                            name,
                         });
                     }
                 };
@@ -2045,8 +2094,13 @@ impl FunctionBuilder {
             Expr::MetaProp(m) => {
                 match m.kind {
                     MetaPropKind::ImportMeta => {
-                        // read import.meta
-                        self.bytecode.push(OpCode::ImportMeta { result: self.r1 });
+                        // read import.meta of the module being built
+                        let url = self.runtime.module_url.clone().unwrap_or_default();
+                        let url = self.runtime.to_mut().register_string(&url);
+                        self.bytecode.push(OpCode::ImportMeta {
+                            result: self.r1,
+                            url,
+                        });
                     }
                     MetaPropKind::NewTarget => {
                         // read new.target
@@ -2332,8 +2386,6 @@ impl FunctionBuilder {
                             self.bytecode.push(OpCode::SwitchToBlock(exit));
                         }
                         MemberProp::PrivateName(p) => {
-                            let id = self.runtime.register_field_name(&format!("#{}", &p.id));
-                            
                             let should_read = self.create_block();
                             let exit = self.create_block();
 
@@ -2345,10 +2397,13 @@ impl FunctionBuilder {
 
                             self.bytecode.push(OpCode::SwitchToBlock(should_read));
 
-                            self.bytecode.push(OpCode::ReadFieldStatic{
+                            let (class, name) = self.load_private_name(p, &[obj])?;
+
+                            self.bytecode.push(OpCode::ReadPrivateField {
                                 obj: obj,
                                 result: self.r1,
-                                field_id: id,
+                                class,
+                                name,
                             });
 
                             self.bytecode.push(OpCode::Jump { to: exit, line: 0 });
@@ -2449,7 +2504,10 @@ impl FunctionBuilder {
                 self.translate_expr(&p.expr)?;
             }
             Expr::PrivateName(p) => {
-                todo!("private name expr {}", &p.id)
+                // only valid on the left of 'in', which is handled by the binary expression
+                return Err(Error::SyntaxError(
+                    format!("Unexpected private name #{}", &p.id.sym).into(),
+                ));
             }
             Expr::Seq(s) => {
                 let mut r = self.r1;
@@ -2705,13 +2763,13 @@ impl FunctionBuilder {
                             MemberProp::PrivateName(p) => {
                                 self.bytecode.push(OpCode::ReadTemp { value: self.r2 });
 
-                                let id =
-                                    self.runtime.register_field_name(&format!("#{}", &p.id.sym));
+                                let (class, name) = self.load_private_name(p, &[obj, self.r2])?;
 
-                                self.bytecode.push(OpCode::WriteFieldStatic {
+                                self.bytecode.push(OpCode::WritePrivateField {
                                     obj: obj,
                                     value: self.r2,
-                                    field_id: id,
+                                    class,
+                                    name,
                                 });
                             }
                             MemberProp::Computed(c) => {
@@ -2776,7 +2834,10 @@ impl FunctionBuilder {
                 };
 
                 if y.delegate {
-                    todo!()
+                    self.bytecode.push(OpCode::YieldDelegate {
+                        result: self.r1,
+                        iterable: arg,
+                    });
                 } else {
                     self.bytecode.push(OpCode::Yield {
                         result: self.r1,
@@ -2785,23 +2846,18 @@ impl FunctionBuilder {
                 }
             }
 
-            Expr::TsAs(t) => {
-                todo!()
-            }
-            Expr::TsConstAssertion(c) => {
-                todo!()
-            }
-            Expr::TsInstantiation(i) => {
-                todo!()
-            }
-            Expr::TsNonNull(n) => {
-                todo!()
-            }
-            Expr::TsTypeAssertion(t) => {
-                todo!()
-            }
-            Expr::TsSatisfaction(s) => {
-                todo!()
+            // types are erased, only the expression is evaluated
+            Expr::TsAs(TsAsExpr { expr, .. })
+            | Expr::TsConstAssertion(TsConstAssertion { expr, .. })
+            | Expr::TsInstantiation(TsInstantiation { expr, .. })
+            | Expr::TsNonNull(TsNonNullExpr { expr, .. })
+            | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+            | Expr::TsSatisfaction(TsSatisfactionExpr { expr, .. }) => {
+                let v = self.translate_expr(expr)?;
+                self.bytecode.push(OpCode::Mov {
+                    from: v,
+                    to: self.r1,
+                });
             }
 
             Expr::JSXElement(e) => {
@@ -2839,6 +2895,28 @@ impl FunctionBuilder {
             false
         };
 
+        // the private names are visible to the whole class body, including the members before them
+        self.ctx.new_context();
+
+        let mut private_names = std::collections::HashMap::new();
+        for i in &class.body {
+            let key = match i {
+                ClassMember::PrivateMethod(m) => &m.key,
+                ClassMember::PrivateProp(p) => &p.key,
+                _ => continue,
+            };
+            // a getter and a setter share one private name
+            private_names.entry(key.id.sym.clone()).or_insert_with(|| {
+                self.runtime.register_field_name(&format!("#{}", &key.id.sym))
+            });
+        }
+        for (sym, name) in &private_names {
+            self.ctx.declare_private_name(sym.clone(), *name);
+        }
+
+        // defines the private fields on every new instance
+        let mut initializer: Option<FunctionBuilder> = None;
+
         self.bytecode.push(OpCode::CreateClass {
             result: self.r3,
             class_id,
@@ -2854,6 +2932,19 @@ impl FunctionBuilder {
             DeclareKind::Var,
         ));
 
+        // the private names are made unique by the class, which is captured
+        // through a variable for each name
+        for sym in private_names.keys() {
+            self.bytecode.push(self.ctx.declare(
+                (
+                    swc_atoms::JsWord::from(format!("#{}", sym)),
+                    swc_common::SyntaxContext::empty(),
+                ),
+                self.r3,
+                DeclareKind::Var,
+            ));
+        }
+
         if sup {
             self.bytecode.push(OpCode::ClassBindSuper {
                 class: self.r3,
@@ -2865,6 +2956,11 @@ impl FunctionBuilder {
 
         for i in &class.body {
             match i {
+                // overload signatures and abstract members only declare types
+                ClassMember::Constructor(c) if c.body.is_none() => {}
+                ClassMember::Method(m) if m.is_abstract || m.function.body.is_none() => {}
+                ClassMember::ClassProp(p) if p.declare || p.is_abstract => {}
+
                 ClassMember::Constructor(c) => {
                     let mut builder = FunctionBuilder::new_with_context(
                         self.runtime.clone(),
@@ -2876,17 +2972,47 @@ impl FunctionBuilder {
 
                     if let Some(b) = &c.body {
                         let mut i = 0;
+                        // parameter properties, constructor(private x) {}
+                        let mut props = Vec::new();
                         for p in &c.params {
                             match p {
                                 ParamOrTsParamProp::Param(p) => {
+                                    if matches!(&p.pat, Pat::Ident(b) if &*b.id.sym == "this") {
+                                        continue;
+                                    }
                                     builder.translate_param(p, i)?;
                                 }
                                 ParamOrTsParamProp::TsParamProp(t) => {
-                                    todo!()
+                                    let (pat, id) = match &t.param {
+                                        TsParamPropParam::Ident(b) => (Pat::Ident(b.clone()), &b.id),
+                                        TsParamPropParam::Assign(a) => match a.left.as_ref() {
+                                            Pat::Ident(b) => (Pat::Assign(a.clone()), &b.id),
+                                            _ => {
+                                                return Err(Error::SyntaxError(
+                                                    "A parameter property may not be declared using a binding pattern."
+                                                        .to_string()
+                                                        .into(),
+                                                ))
+                                            }
+                                        },
+                                    };
+                                    builder.translate_param_pat(&pat, i)?;
+                                    props.push(id);
                                 }
                             };
                             i += 1;
                         }
+                        // this.x = x
+                        for id in props {
+                            builder.bytecode.push(OpCode::LoadThis { result: builder.r1 });
+                            let code = builder.ctx.get(&id.to_id(), builder.r2);
+                            builder.bytecode.push(code);
+                            builder.bytecode.push(OpCode::WriteFieldStatic {
+                                obj: builder.r1,
+                                value: builder.r2,
+                                field_id: self.runtime.register_field_name(&id.sym),
+                            });
+                        }
                         for i in &b.stmts {
                             builder.translate_statement(None, &i)?;
                         }
@@ -2947,27 +3073,18 @@ impl FunctionBuilder {
 
                     self.bytecode.extend(self.ctx.need_done());
 
-                    let name = format!("#{}", &m.key.id.sym);
-                    if m.is_static {
-                        if m.kind == MethodKind::Method {
-                            self.runtime
-                                .bind_class_static_method(class_id, &name, func_id);
-                        } else if m.kind == MethodKind::Getter {
-                            self.runtime
-                                .bind_class_static_getter(class_id, &name, func_id);
-                        } else if m.kind == MethodKind::Setter {
-                            self.runtime
-                                .bind_class_static_setter(class_id, &name, func_id);
-                        };
-                    } else {
-                        if m.kind == MethodKind::Method {
-                            self.runtime.bind_class_method(class_id, &name, func_id);
-                        } else if m.kind == MethodKind::Getter {
-                            self.runtime.bind_class_getter(class_id, &name, func_id);
-                        } else if m.kind == MethodKind::Setter {
-                            self.runtime.bind_class_setter(class_id, &name, func_id);
-                        };
-                    }
+                    let name = self.private_name(&m.key)?;
+                    match m.kind {
+                        MethodKind::Method => self
+                            .runtime
+                            .bind_class_private_method(class_id, name, func_id, m.is_static),
+                        MethodKind::Getter => self
+                            .runtime
+                            .bind_class_private_getter(class_id, name, func_id, m.is_static),
+                        MethodKind::Setter => self
+                            .runtime
+                            .bind_class_private_setter(class_id, name, func_id, m.is_static),
+                    };
                 }
 
                 ClassMember::ClassProp(p) => {
@@ -3007,41 +3124,52 @@ impl FunctionBuilder {
                     }
                 }
                 ClassMember::PrivateProp(p) => {
-                    let name = format!("#{}", &p.key.id.sym);
+                    // static fields are defined on the class once,
+                    // instance fields are defined by the initializer on every new instance
+                    let builder = if p.is_static {
+                        &mut *self
+                    } else {
+                        initializer.get_or_insert_with(|| {
+                            FunctionBuilder::new_with_context(
+                                self.runtime.clone(),
+                                self.ctx.clone(),
+                                false,
+                                false,
+                                0,
+                            )
+                        })
+                    };
 
-                    let field_id = if p.is_static {
-                        self.runtime.bind_class_static_prop(class_id, &name)
+                    let v = if let Some(e) = &p.value {
+                        builder.translate_expr(&e)?
                     } else {
-                        self.runtime.bind_class_prop(class_id, &name)
+                        builder
+                            .bytecode
+                            .push(OpCode::LoadUndefined { result: builder.r1 });
+                        builder.r1
                     };
 
-                    if let Some(e) = &p.value {
-                        let v = self.translate_expr(&e)?;
+                    if v != builder.r1 {
+                        builder.bytecode.push(OpCode::Mov {
+                            from: v,
+                            to: builder.r1,
+                        });
+                    }
 
-                        if v != self.r1 {
-                            self.bytecode.push(OpCode::Mov {
-                                from: v,
-                                to: self.r1,
-                            });
-                        }
-                        self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
+                    if p.is_static {
+                        builder.bytecode.push(OpCode::ReadTemp { value: builder.r3 });
+                    } else {
+                        builder.bytecode.push(OpCode::LoadThis { result: builder.r3 });
+                    }
 
-                        if !p.is_static {
-                            // read 'prototype' into self.r3
-                            self.bytecode.push(OpCode::ReadFieldStatic {
-                                obj: self.r3,
-                                result: self.r3,
-                                field_id: self.runtime.register_field_name("prototype"),
-                            });
-                        }
+                    let (class, name) = builder.load_private_name(&p.key, &[builder.r1, builder.r3])?;
 
-                        // write value into class.prototype.field
-                        self.bytecode.push(OpCode::WriteFieldStatic {
-                            obj: self.r3,
-                            value: self.r1,
-                            field_id,
-                        });
-                    }
+                    builder.bytecode.push(OpCode::DefinePrivateField {
+                        obj: builder.r3,
+                        value: builder.r1,
+                        class,
+                        name,
+                    });
                 }
                 ClassMember::Empty(_e) => {
                     // empty
@@ -3069,12 +3197,20 @@ impl FunctionBuilder {
                     // set 'this' to the old
                     self.bytecode.push(OpCode::SetThis { value: self.r3 });
                 }
-                ClassMember::TsIndexSignature(i) => {
-                    todo!()
-                }
+                // types are erased
+                ClassMember::TsIndexSignature(_) => {}
             }
         }
 
+        if let Some(mut builder) = initializer {
+            let func_id = builder.finish()?;
+            self.bytecode.extend(self.ctx.need_done());
+            self.runtime.bind_class_field_initializer(class_id, func_id);
+        }
+
+        self.bytecode.extend(self.ctx.need_done());
+        self.ctx.close_context();
+
         self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
         self.bytecode.push(OpCode::ReleaseTemp);
 
@@ -3095,7 +3231,7 @@ impl FunctionBuilder {
             VarDeclKind::Let => DeclareKind::Let,
             VarDeclKind::Var => DeclareKind::Var,
         };
-        self.translate_pat_assign(&d.name, value, kind);
+        self.translate_pat_assign(&d.name, value, kind)?;
 
         return Ok(());
     }
@@ -3140,57 +3276,88 @@ impl FunctionBuilder {
             }
             // default value if value is undefined
             Pat::Assign(a) => {
-                let v = self.translate_expr(&a.right)?;
-
-                self.bytecode.push(OpCode::Select {
-                    a: value,
-                    b: v,
-                    result: self.r1,
-                });
-
-                self.translate_pat_assign(&a.left, self.r1, declare)?;
+                let v = self.translate_default(value, &a.right)?;
+                self.translate_pat_assign(&a.left, v, declare)?;
 
-                Ok(self.r1)
+                Ok(v)
             }
 
             Pat::Expr(e) => {
                 self.bytecode.push(OpCode::StoreTemp { value: value });
 
-                match e.as_ref() {
+                // parenthesized targets, ([a.b]) = ..., and type assertions, [a!.b] = ...
+                let mut e = e.as_ref();
+                loop {
+                    e = match e {
+                        Expr::Paren(ParenExpr { expr, .. })
+                        | Expr::TsAs(TsAsExpr { expr, .. })
+                        | Expr::TsNonNull(TsNonNullExpr { expr, .. })
+                        | Expr::TsTypeAssertion(TsTypeAssertion { expr, .. })
+                        | Expr::TsSatisfaction(TsSatisfactionExpr { expr, .. }) => expr,
+                        _ => break,
+                    };
+                }
+
+                match e {
+                    Expr::Ident(i) => {
+                        self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
+
+                        let code = self.ctx.declare(i.to_id(), self.r1, declare);
+                        self.bytecode.push(code);
+                    }
                     Expr::Member(m) => {
                         let obj = self.translate_expr(&m.obj)?;
 
                         match &m.prop {
                             MemberProp::Ident(i) => {
+                                self.bytecode.push(OpCode::Mov {
+                                    from: obj,
+                                    to: self.r1,
+                                });
                                 self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
 
                                 let id = self.runtime.register_field_name(&i.sym);
 
                                 self.bytecode.push(OpCode::WriteFieldStatic {
-                                    obj: obj,
+                                    obj: self.r1,
                                     value: self.r3,
                                     field_id: id,
                                 });
                             }
                             MemberProp::PrivateName(p) => {
+                                self.bytecode.push(OpCode::Mov {
+                                    from: obj,
+                                    to: self.r1,
+                                });
                                 self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
 
-                                let id =
-                                    self.runtime.register_field_name(&format!("#{}", p.id.sym));
+                                let (class, name) =
+                                    self.load_private_name(p, &[self.r1, self.r3])?;
 
-                                self.bytecode.push(OpCode::WriteFieldStatic {
-                                    obj: obj,
+                                self.bytecode.push(OpCode::WritePrivateField {
+                                    obj: self.r1,
                                     value: self.r3,
-                                    field_id: id,
+                                    class,
+                                    name,
                                 });
                             }
                             MemberProp::Computed(c) => {
+                                // the field may be written into the register of obj
+                                self.bytecode.push(OpCode::StoreTemp { value: obj });
+
                                 let field = self.translate_expr(&c.expr)?;
+                                self.bytecode.push(OpCode::Mov {
+                                    from: field,
+                                    to: self.r2,
+                                });
+
+                                self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
+                                self.bytecode.push(OpCode::ReleaseTemp);
                                 self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
 
                                 self.bytecode.push(OpCode::WriteField {
-                                    obj: obj,
-                                    field,
+                                    obj: self.r1,
+                                    field: self.r2,
                                     value: self.r3,
                                     stack_offset: self.ctx.current_stack_offset(),
                                 });
@@ -3238,7 +3405,11 @@ impl FunctionBuilder {
                             }
                         }
                     }
-                    e => todo!("expression pattern assign {:#?}", e),
+                    _ => {
+                        return Err(Error::SyntaxError(
+                            "Invalid destructuring assignment target".to_string().into(),
+                        ))
+                    }
                 };
 
                 self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
@@ -3249,55 +3420,112 @@ impl FunctionBuilder {
             Pat::Invalid(_i) => Ok(self.r1),
 
             Pat::Array(a) => {
+                // the value is the result of the assignment
+                self.bytecode.push(OpCode::StoreTemp { value: value });
                 self.bytecode.push(OpCode::PrepareForOf { target: value });
 
-                let mut i = 0;
-                let mut re = value;
                 for p in &a.elems {
-                    let p = match p {
-                        Some(p) => p,
-                        None => continue,
-                    };
-
-                    let re = if p.is_rest() {
-                        let r = p.as_rest().unwrap();
-                        self.bytecode.push(OpCode::IterCollect {
-                            result: self.r1,
-                            stack_offset: self.ctx.current_stack_offset(),
-                        });
-                        self.translate_pat_assign(&r.arg, self.r1, declare)?
-                    } else {
-                        self.bytecode.push(OpCode::IterNext {
-                            result: self.r1,
-                            done: self.r2,
-                            hint: LoopHint::ForOf,
-                            stack_offset: self.ctx.current_stack_offset(),
-                        });
-                        self.translate_pat_assign(p, self.r1, declare)?
+                    match p {
+                        // an elision still steps the iterator
+                        None => {
+                            self.bytecode.push(OpCode::IterNext {
+                                result: self.r1,
+                                done: self.r2,
+                                hint: LoopHint::ForOf,
+                                stack_offset: self.ctx.current_stack_offset(),
+                            });
+                        }
+                        Some(Pat::Rest(r)) => {
+                            self.bytecode.push(OpCode::IterCollect {
+                                result: self.r1,
+                                stack_offset: self.ctx.current_stack_offset(),
+                            });
+                            self.translate_pat_assign(&r.arg, self.r1, declare)?;
+                        }
+                        Some(p) => {
+                            self.bytecode.push(OpCode::IterNext {
+                                result: self.r1,
+                                done: self.r2,
+                                hint: LoopHint::ForOf,
+                                stack_offset: self.ctx.current_stack_offset(),
+                            });
+                            self.translate_pat_assign(p, self.r1, declare)?;
+                        }
                     };
-                    i += 1;
                 }
+                // 7.4.8 IteratorClose if the pattern did not exhaust the iterator
                 self.bytecode.push(OpCode::IterDrop);
-                Ok(re)
+
+                self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
+                self.bytecode.push(OpCode::ReleaseTemp);
+                Ok(self.r1)
             }
 
             Pat::Object(o) => {
+                let has_rest = o.props.iter().any(|p| matches!(p, ObjectPatProp::Rest(_)));
+
+                // the source is kept in a temp slot, nested patterns may use all registers.
+                // the keys excluded from the rest element are collected in an object below the source
+                if has_rest {
+                    if value != self.r3 {
+                        self.bytecode.push(OpCode::Mov {
+                            from: value,
+                            to: self.r3,
+                        });
+                    }
+                    self.bytecode.push(OpCode::CreateObject { result: self.r1 });
+                    self.bytecode.push(OpCode::StoreTemp { value: self.r1 });
+                    self.bytecode.push(OpCode::StoreTemp { value: self.r3 });
+                } else {
+                    self.bytecode.push(OpCode::StoreTemp { value: value });
+                }
+
+                // the static keys excluded from the rest element
                 let mut names = vec![];
 
                 for p in &o.props {
                     match p {
                         ObjectPatProp::KeyValue(k) => {
-                            // prop = value.get(field);
-                            let field_id = self
-                                .runtime
-                                .register_field_name(&self.propname_to_str(&k.key));
-                            names.push(field_id);
+                            if let PropName::Computed(c) = &k.key {
+                                let key = self.translate_expr(&c.expr)?;
+                                self.bytecode.push(OpCode::Mov {
+                                    from: key,
+                                    to: self.r2,
+                                });
+                                self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
 
-                            self.bytecode.push(OpCode::ReadFieldStatic {
-                                obj: value,
-                                result: self.r1,
-                                field_id,
-                            });
+                                if has_rest {
+                                    self.bytecode.push(OpCode::ReleaseTemp);
+                                    self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
+                                    self.bytecode.push(OpCode::ExcludeKey {
+                                        keys: self.r1,
+                                        key: self.r2,
+                                        stack_offset: self.ctx.current_stack_offset(),
+                                    });
+                                    self.bytecode.push(OpCode::StoreTemp { value: self.r3 });
+                                }
+
+                                // prop = value[key];
+                                self.bytecode.push(OpCode::ReadField {
+                                    obj: self.r3,
+                                    field: self.r2,
+                                    result: self.r1,
+                                    stack_offset: self.ctx.current_stack_offset(),
+                                });
+                            } else {
+                                // prop = value.get(field);
+                                let field_id = self
+                                    .runtime
+                                    .register_field_name(&self.propname_to_str(&k.key));
+                                names.push(field_id);
+
+                                self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
+                                self.bytecode.push(OpCode::ReadFieldStatic {
+                                    obj: self.r3,
+                                    result: self.r1,
+                                    field_id,
+                                });
+                            }
 
                             self.translate_pat_assign(&k.value, self.r1, declare)?;
                         }
@@ -3305,48 +3533,97 @@ impl FunctionBuilder {
                             let field_id = self.runtime.register_field_name(&a.key.sym);
                             names.push(field_id);
 
-                            if let Some(v) = &a.value {
-                                let v = self.translate_expr(&v)?;
+                            self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
+                            self.bytecode.push(OpCode::ReadFieldStatic {
+                                obj: self.r3,
+                                result: self.r1,
+                                field_id,
+                            });
 
-                                self.bytecode.push(OpCode::Select {
-                                    a: value,
-                                    b: v,
-                                    result: self.r1,
-                                });
+                            let v = match &a.value {
+                                Some(v) => self.translate_default(self.r1, v)?,
+                                None => self.r1,
+                            };
 
-                                let code = self.ctx.declare(a.key.to_id(), self.r1, declare);
-                                self.bytecode.push(code);
-                            } else {
-                                let code = self.ctx.declare(a.key.to_id(), value, declare);
-                                self.bytecode.push(code);
-                            }
+                            let code = self.ctx.declare(a.key.to_id(), v, declare);
+                            self.bytecode.push(code);
                         }
                         ObjectPatProp::Rest(r) => {
-                            self.bytecode.push(OpCode::CloneObject {
-                                obj: value,
-                                result: self.r1,
-                            });
+                            self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
+                            self.bytecode.push(OpCode::ReleaseTemp);
+                            self.bytecode.push(OpCode::ReadTemp { value: self.r2 });
 
                             for i in &names {
-                                self.bytecode.push(OpCode::RemoveFieldStatic {
-                                    obj: self.r1,
+                                self.bytecode.push(OpCode::ExcludeKeyStatic {
+                                    keys: self.r2,
                                     field_id: *i,
                                 });
                             }
 
+                            // the excluded keys are never read from the source
+                            self.bytecode.push(OpCode::CopyDataProperties {
+                                from: self.r3,
+                                excluded: self.r2,
+                                result: self.r1,
+                            });
+                            self.bytecode.push(OpCode::StoreTemp { value: self.r3 });
+
                             self.translate_pat_assign(&r.arg, self.r1, declare)?;
                         }
                     }
                 }
+
+                self.bytecode.push(OpCode::ReadTemp { value: self.r1 });
+                self.bytecode.push(OpCode::ReleaseTemp);
+                if has_rest {
+                    self.bytecode.push(OpCode::ReleaseTemp);
+                }
                 Ok(self.r1)
             }
-            Pat::Rest(r) => {
-                /// a param rest pattern
-                unimplemented!("rest pattern assign")
+            Pat::Rest(_) => {
+                // rest elements are handled by the array pattern and the params
+                Err(Error::SyntaxError(
+                    "Unexpected rest element".to_string().into(),
+                ))
             }
         }
     }
 
+    /// evaluate the default only if value is undefined, the result is written into r1
+    fn translate_default(&mut self, value: Register, default: &Expr) -> Result<Register, Error> {
+        let default_block = self.create_block();
+        let exit = self.create_block();
+
+        self.bytecode.push(OpCode::Mov {
+            from: value,
+            to: self.r1,
+        });
+        self.bytecode
+            .push(OpCode::LoadUndefined { result: self.r2 });
+        self.bytecode.push(OpCode::EqEqEq {
+            result: self.r2,
+            left: self.r1,
+            right: self.r2,
+        });
+        self.bytecode.push(OpCode::JumpIfTrue {
+            value: self.r2,
+            to: default_block,
+            line: 0,
+        });
+        self.bytecode.push(OpCode::Jump { to: exit, line: 0 });
+
+        self.bytecode.push(OpCode::SwitchToBlock(default_block));
+        let v = self.translate_expr(default)?;
+        self.bytecode.push(OpCode::Mov {
+            from: v,
+            to: self.r1,
+        });
+        self.bytecode.push(OpCode::Jump { to: exit, line: 0 });
+
+        self.bytecode.push(OpCode::SwitchToBlock(exit));
+        Ok(self.r1)
+    }
+
     /// register arguments for a function call
     fn translate_args(&mut self, args: &[ExprOrSpread]) -> Result<(), Error> {
         self.bytecode.push(OpCode::CreateArg {
@@ -3442,6 +3719,239 @@ impl FunctionBuilder {
         Ok(())
     }
 
+    /// load a number into r1
+    fn load_number(&mut self, value: f64) {
+        // check if number can be contained in f32
+        if value as f32 as f64 == value {
+            self.bytecode.push(OpCode::LoadStaticFloat32 {
+                result: self.r1,
+                value: value as f32,
+            });
+        } else {
+            // register number to the runtime
+            let id = self
+                .runtime
+                .to_mut()
+                .unamed_constant(JValue::create_number(value));
+
+            self.bytecode.push(OpCode::LoadStaticFloat {
+                result: self.r1,
+                id,
+            });
+        }
+    }
+
+    /// translate a TypeScript enum into an object,
+    /// numeric members are reverse mapped, E[E["A"] = 0] = "A"
+    fn translate_enum(&mut self, e: &TsEnumDecl) -> Res {
+        self.bytecode.push(OpCode::CreateObject { result: self.r1 });
+        let code = self.ctx.declare(e.id.to_id(), self.r1, DeclareKind::Let);
+        self.bytecode.push(code);
+        self.bytecode.push(OpCode::StoreTemp { value: self.r1 });
+
+        // members known at compile time, later initializers may refer to them
+        let mut members = HashMap::new();
+        // the value of a member without initializer
+        let mut next = Some(0.0);
+
+        for m in &e.members {
+            let name = match &m.id {
+                TsEnumMemberId::Ident(i) => i.sym.clone(),
+                TsEnumMemberId::Str(s) => s.value.clone(),
+            };
+            let constant = match &m.init {
+                Some(init) => enum_constant(init, &members),
+                None => match next {
+                    Some(n) => Some(EnumValue::Number(n)),
+                    None => {
+                        return Err(Error::SyntaxError(
+                            "Enum member must have initializer.".to_string().into(),
+                        ))
+                    }
+                },
+            };
+
+            let is_string = match &constant {
+                Some(EnumValue::Number(n)) => {
+                    self.load_number(*n);
+                    next = Some(n + 1.0);
+                    false
+                }
+                Some(EnumValue::String(s)) => {
+                    let id = self.runtime.to_mut().register_string(s);
+                    self.bytecode
+                        .push(OpCode::LoadStaticString { result: self.r1, id });
+                    next = None;
+                    true
+                }
+                // computed at runtime
+                None => {
+                    let v = self.translate_expr(m.init.as_ref().unwrap())?;
+                    self.bytecode.push(OpCode::Mov {
+                        from: v,
+                        to: self.r1,
+                    });
+                    next = None;
+                    false
+                }
+            };
+            if let Some(c) = constant {
+                members.insert(name.clone(), c);
+            }
+
+            // E[name] = value
+            self.bytecode.push(OpCode::ReadTemp { value: self.r2 });
+            self.bytecode.push(OpCode::WriteFieldStatic {
+                obj: self.r2,
+                value: self.r1,
+                field_id: self.runtime.register_field_name(&name),
+            });
+
+            // E[value] = name
+            if !is_string {
+                let id = self.runtime.to_mut().register_string(&name);
+                self.bytecode
+                    .push(OpCode::LoadStaticString { result: self.r3, id });
+                self.bytecode.push(OpCode::WriteField {
+                    obj: self.r2,
+                    field: self.r1,
+                    value: self.r3,
+                    stack_offset: self.ctx.current_stack_offset(),
+                });
+            }
+        }
+
+        self.bytecode.push(OpCode::ReleaseTemp);
+        Ok(())
+    }
+
+    /// translate a TypeScript namespace into an object holding its exported members
+    fn translate_namespace(&mut self, m: &TsModuleDecl) -> Res {
+        // ambient declarations and namespaces of only types have no value
+        let (id, body) = match (&m.id, &m.body) {
+            (TsModuleName::Ident(id), Some(body))
+                if !m.declare && !m.global && is_instantiated(body) =>
+            {
+                (id, body)
+            }
+            _ => return Ok(()),
+        };
+
+        self.bytecode.push(OpCode::CreateObject { result: self.r1 });
+        let code = self.ctx.declare(id.to_id(), self.r1, DeclareKind::Let);
+        self.bytecode.push(code);
+        self.bytecode.push(OpCode::StoreTemp { value: self.r1 });
+
+        self.translate_namespace_body(body)?;
+
+        self.bytecode.push(OpCode::ReleaseTemp);
+        Ok(())
+    }
+
+    /// the namespace object is stored in the current temp slot
+    fn translate_namespace_body(&mut self, body: &TsNamespaceBody) -> Res {
+        self.ctx.new_context();
+        match body {
+            // namespace A.B {}
+            TsNamespaceBody::TsNamespaceDecl(d) => {
+                self.bytecode.push(OpCode::CreateObject { result: self.r1 });
+                let code = self.ctx.declare(d.id.to_id(), self.r1, DeclareKind::Let);
+                self.bytecode.push(code);
+                self.export_namespace_member(&d.id);
+                self.bytecode.push(OpCode::StoreTemp { value: self.r1 });
+
+                self.translate_namespace_body(&d.body)?;
+
+                self.bytecode.push(OpCode::ReleaseTemp);
+            }
+            TsNamespaceBody::TsModuleBlock(b) => {
+                for item in &b.body {
+                    match item {
+                        ModuleItem::Stmt(s) => {
+                            self.translate_statement(None, s)?;
+                        }
+                        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(e)) => {
+                            self.translate_statement(None, &Stmt::Decl(e.decl.clone()))?;
+                            for id in declared_idents(&e.decl) {
+                                self.export_namespace_member(id);
+                            }
+                        }
+                        ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(i)) => {
+                            self.translate_import_equals(i)?;
+                            if i.is_export && !i.is_type_only {
+                                self.export_namespace_member(&i.id);
+                            }
+                        }
+                        ModuleItem::ModuleDecl(_) => {
+                            return Err(Error::SyntaxError(
+                                "Import and export declarations are not permitted in a namespace."
+                                    .to_string()
+                                    .into(),
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        self.ctx.close_context();
+        Ok(())
+    }
+
+    /// write a declared variable onto the namespace object in the current temp slot
+    fn export_namespace_member(&mut self, id: &Ident) {
+        let code = self.ctx.get(&id.to_id(), self.r2);
+        self.bytecode.push(code);
+        self.bytecode.push(OpCode::ReadTemp { value: self.r3 });
+        self.bytecode.push(OpCode::WriteFieldStatic {
+            obj: self.r3,
+            value: self.r2,
+            field_id: self.runtime.register_field_name(&id.sym),
+        });
+    }
+
+    /// import A = N.B
+    pub fn translate_import_equals(&mut self, i: &TsImportEqualsDecl) -> Res {
+        if i.is_type_only {
+            return Ok(());
+        }
+        let mut name = match &i.module_ref {
+            TsModuleRef::TsEntityName(n) => n,
+            TsModuleRef::TsExternalModuleRef(_) => {
+                return Err(Error::SyntaxError(
+                    "Import assignment cannot be used when targeting ECMAScript modules."
+                        .to_string()
+                        .into(),
+                ))
+            }
+        };
+
+        // the qualified name is read from the leftmost identifier
+        let mut fields = Vec::new();
+        let root = loop {
+            match name {
+                TsEntityName::Ident(i) => break i,
+                TsEntityName::TsQualifiedName(q) => {
+                    fields.push(&q.right);
+                    name = &q.left;
+                }
+            }
+        };
+
+        let code = self.ctx.get(&root.to_id(), self.r1);
+        self.bytecode.push(code);
+        for f in fields.iter().rev() {
+            self.bytecode.push(OpCode::ReadFieldStatic {
+                obj: self.r1,
+                result: self.r1,
+                field_id: self.runtime.register_field_name(&f.sym),
+            });
+        }
+
+        let code = self.ctx.declare(i.id.to_id(), self.r1, DeclareKind::Const);
+        self.bytecode.push(code);
+        Ok(())
+    }
+
     fn propname_to_str(&self, propname: &PropName) -> String {
         match propname {
             PropName::BigInt(b) => b.value.to_string(),
@@ -3528,3 +4038,147 @@ impl FunctionBuilder {
         Ok(id)
     }
 }
+
+/// the value of an enum member known at compile time
+#[derive(Clone)]
+enum EnumValue {
+    Number(f64),
+    String(JsWord),
+}
+
+/// evaluate a constant enum expression, returns None if it is only known at runtime
+fn enum_constant(expr: &Expr, members: &HashMap<JsWord, EnumValue>) -> Option<EnumValue> {
+    match expr {
+        Expr::Lit(Lit::Num(n)) => Some(EnumValue::Number(n.value)),
+        Expr::Lit(Lit::Str(s)) => Some(EnumValue::String(s.value.clone())),
+        Expr::Paren(p) => enum_constant(&p.expr, members),
+        Expr::Ident(i) => members.get(&i.sym).cloned(),
+        Expr::Unary(u) => {
+            let v = match enum_constant(&u.arg, members)? {
+                EnumValue::Number(n) => n,
+                EnumValue::String(_) => return None,
+            };
+            let v = match u.op {
+                UnaryOp::Minus => -v,
+                UnaryOp::Plus => v,
+                UnaryOp::Tilde => !to_int32(v) as f64,
+                _ => return None,
+            };
+            Some(EnumValue::Number(v))
+        }
+        Expr::Bin(b) => {
+            let l = enum_constant(&b.left, members)?;
+            let r = enum_constant(&b.right, members)?;
+            let (l, r) = match (l, r) {
+                (EnumValue::Number(l), EnumValue::Number(r)) => (l, r),
+                (EnumValue::String(l), EnumValue::String(r)) if b.op == BinaryOp::Add => {
+                    return Some(EnumValue::String(format!("{}{}", l, r).into()))
+                }
+                _ => return None,
+            };
+            let shift = to_int32(r) as u32 & 31;
+            let v = match b.op {
+                BinaryOp::Add => l + r,
+                BinaryOp::Sub => l - r,
+                BinaryOp::Mul => l * r,
+                BinaryOp::Div => l / r,
+                BinaryOp::Mod => l % r,
+                BinaryOp::Exp => l.powf(r),
+                BinaryOp::BitOr => (to_int32(l) | to_int32(r)) as f64,
+                BinaryOp::BitAnd => (to_int32(l) & to_int32(r)) as f64,
+                BinaryOp::BitXor => (to_int32(l) ^ to_int32(r)) as f64,
+                BinaryOp::LShift => to_int32(l).wrapping_shl(shift) as f64,
+                BinaryOp::RShift => (to_int32(l) >> shift) as f64,
+                BinaryOp::ZeroFillRShift => (to_int32(l) as u32 >> shift) as f64,
+                _ => return None,
+            };
+            Some(EnumValue::Number(v))
+        }
+        _ => None,
+    }
+}
+
+/// 7.1.6 ToInt32
+fn to_int32(v: f64) -> i32 {
+    if !v.is_finite() {
+        return 0;
+    }
+    (v.trunc() % 4294967296.0) as i64 as u32 as i32
+}
+
+/// a namespace is instantiated if it contains anything other than types
+pub(crate) fn is_instantiated(body: &TsNamespaceBody) -> bool {
+    match body {
+        TsNamespaceBody::TsNamespaceDecl(d) => !d.declare && is_instantiated(&d.body),
+        TsNamespaceBody::TsModuleBlock(b) => b.body.iter().any(|item| match item {
+            ModuleItem::Stmt(Stmt::Decl(d)) => has_value(d),
+            ModuleItem::Stmt(_) => true,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(e)) => has_value(&e.decl),
+            ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(i)) => !i.is_type_only,
+            ModuleItem::ModuleDecl(_) => true,
+        }),
+    }
+}
+
+/// whether a declaration declares a value, not just a type
+fn has_value(decl: &Decl) -> bool {
+    match decl {
+        Decl::Class(c) => !c.declare,
+        Decl::Fn(f) => !f.declare && f.function.body.is_some(),
+        Decl::Var(v) => !v.declare,
+        Decl::TsEnum(e) => !e.declare,
+        Decl::TsModule(m) => match (&m.id, &m.body) {
+            (TsModuleName::Ident(_), Some(body)) => !m.declare && !m.global && is_instantiated(body),
+            _ => false,
+        },
+        Decl::TsInterface(_) | Decl::TsTypeAlias(_) => false,
+    }
+}
+
+/// the identifiers of values declared by a declaration
+pub(crate) fn declared_idents(decl: &Decl) -> Vec<&Ident> {
+    let mut idents = Vec::new();
+    if !has_value(decl) {
+        return idents;
+    }
+    match decl {
+        Decl::Class(c) => idents.push(&c.ident),
+        Decl::Fn(f) => idents.push(&f.ident),
+        Decl::Var(v) => {
+            for d in &v.decls {
+                binding_idents(&d.name, &mut idents);
+            }
+        }
+        Decl::TsEnum(e) => idents.push(&e.id),
+        Decl::TsModule(m) => {
+            if let TsModuleName::Ident(i) = &m.id {
+                idents.push(i);
+            }
+        }
+        Decl::TsInterface(_) | Decl::TsTypeAlias(_) => {}
+    }
+    idents
+}
+
+fn binding_idents<'a>(pat: &'a Pat, idents: &mut Vec<&'a Ident>) {
+    match pat {
+        Pat::Ident(i) => idents.push(&i.id),
+        Pat::Array(a) => {
+            for p in a.elems.iter().flatten() {
+                binding_idents(p, idents);
+            }
+        }
+        Pat::Object(o) => {
+            for p in &o.props {
+                match p {
+                    ObjectPatProp::KeyValue(k) => binding_idents(&k.value, idents),
+                    ObjectPatProp::Assign(a) => idents.push(&a.key),
+                    ObjectPatProp::Rest(r) => binding_idents(&r.arg, idents),
+                }
+            }
+        }
+        Pat::Rest(r) => binding_idents(&r.arg, idents),
+        Pat::Assign(a) => binding_idents(&a.left, idents),
+        Pat::Expr(_) | Pat::Invalid(_) => {}
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use super::{Block, DeclareKind, LoopHint, Register};
+use crate::runtime::{ClassID, ConstID, FuncID, RegexID, StringID, TemplateID};
+
+/// maps the ids of a runtime into indexes local to a cache entry
+#[derive(Default)]
+pub(crate) struct IdMap {
+    map: HashMap<u32, u32>,
+    /// the runtime ids in order of their local index
+    pub ids: Vec<u32>,
+}
+
+impl IdMap {
+    /// return the local index of an id, the id is recorded if not seen before
+    pub fn local(&mut self, id: u32) -> u32 {
+        if let Some(i) = self.map.get(&id) {
+            return *i;
+        }
+        let i = self.ids.len() as u32;
+        self.map.insert(id, i);
+        self.ids.push(id);
+        i
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+}
+
+/// writes bytecodes into a cache entry,
+/// every id encountered is recorded so its table can be written afterwards
+#[derive(Default)]
+pub(crate) struct Encoder {
+    pub buf: Vec<u8>,
+
+    pub field_names: IdMap,
+    pub var_names: IdMap,
+    pub strings: IdMap,
+    pub constants: IdMap,
+    pub regexs: IdMap,
+    pub templates: IdMap,
+    pub functions: IdMap,
+    pub classes: IdMap,
+}
+
+impl Encoder {
+    #[inline]
+    pub fn write_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    #[inline]
+    pub fn write_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    #[inline]
+    pub fn write_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    #[inline]
+    pub fn write_u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, v: &[u8]) {
+        self.write_u32(v.len() as u32);
+        self.buf.extend_from_slice(v);
+    }
+
+    pub fn write_str(&mut self, v: &str) {
+        self.write_bytes(v.as_bytes());
+    }
+
+    pub fn write_field_name(&mut self, id: u32) {
+        let i = self.field_names.local(id);
+        self.write_u32(i);
+    }
+
+    pub fn write_var_name(&mut self, id: u32) {
+        let i = self.var_names.local(id);
+        self.write_u32(i);
+    }
+}
+
+/// reads bytecodes from a cache entry,
+/// local indexes are mapped to the ids registered on the loading runtime
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+
+    pub field_names: Vec<u32>,
+    pub var_names: Vec<u32>,
+    pub strings: Vec<u32>,
+    pub constants: Vec<u32>,
+    pub regexs: Vec<u32>,
+    pub templates: Vec<u32>,
+    pub functions: Vec<u32>,
+    pub classes: Vec<u32>,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            field_names: Vec::new(),
+            var_names: Vec::new(),
+            strings: Vec::new(),
+            constants: Vec::new(),
+            regexs: Vec::new(),
+            templates: Vec::new(),
+            functions: Vec::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    /// create a decoder of another section that shares the tables of this one
+    pub fn with_tables<'b>(&self, bytes: &'b [u8]) -> Decoder<'b> {
+        Decoder {
+            bytes,
+            pos: 0,
+            field_names: self.field_names.clone(),
+            var_names: self.var_names.clone(),
+            strings: self.strings.clone(),
+            constants: self.constants.clone(),
+            regexs: self.regexs.clone(),
+            templates: self.templates.clone(),
+            functions: self.functions.clone(),
+            classes: self.classes.clone(),
+        }
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    /// the number of bytes left, lengths read from the input are bounded by it
+    pub fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.pos)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let b = self.bytes.get(self.pos..self.pos + N)?;
+        self.pos += N;
+        b.try_into().ok()
+    }
+
+    #[inline]
+    pub fn read_u8(&mut self) -> Option<u8> {
+        let b = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    #[inline]
+    pub fn read_u16(&mut self) -> Option<u16> {
+        self.read_array().map(u16::from_le_bytes)
+    }
+
+    #[inline]
+    pub fn read_u32(&mut self) -> Option<u32> {
+        self.read_array().map(u32::from_le_bytes)
+    }
+
+    #[inline]
+    pub fn read_u64(&mut self) -> Option<u64> {
+        self.read_array().map(u64::from_le_bytes)
+    }
+
+    pub fn read_bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.read_u32()? as usize;
+        let b = self.bytes.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(b)
+    }
+
+    pub fn read_str(&mut self) -> Option<&'a str> {
+        std::str::from_utf8(self.read_bytes()?).ok()
+    }
+
+    pub fn read_field_name(&mut self) -> Option<u32> {
+        let i = self.read_u32()?;
+        self.field_names.get(i as usize).copied()
+    }
+
+    pub fn read_var_name(&mut self) -> Option<u32> {
+        let i = self.read_u32()?;
+        self.var_names.get(i as usize).copied()
+    }
+}
+
+/// a value stored in an opcode
+pub(crate) trait CacheField: Sized {
+    fn encode(&self, enc: &mut Encoder);
+    fn decode(dec: &mut Decoder) -> Option<Self>;
+}
+
+impl CacheField for bool {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u8(*self as u8)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        Some(dec.read_u8()? != 0)
+    }
+}
+
+impl CacheField for u16 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u16(*self)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        dec.read_u16()
+    }
+}
+
+impl CacheField for u32 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u32(*self)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        dec.read_u32()
+    }
+}
+
+impl CacheField for i32 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u32(*self as u32)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        Some(dec.read_u32()? as i32)
+    }
+}
+
+impl CacheField for f32 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u32(self.to_bits())
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        Some(f32::from_bits(dec.read_u32()?))
+    }
+}
+
+impl CacheField for Register {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u8(self.0)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        Some(Register(dec.read_u8()?))
+    }
+}
+
+impl CacheField for Block {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u16(self.0)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        Some(Block(dec.read_u16()?))
+    }
+}
+
+impl CacheField for DeclareKind {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u8(*self as u8)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        match dec.read_u8()? {
+            0 => Some(Self::Var),
+            1 => Some(Self::Let),
+            2 => Some(Self::Const),
+            3 => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+impl CacheField for LoopHint {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u8(*self as u8)
+    }
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        match dec.read_u8()? {
+            0 => Some(Self::ForOf),
+            1 => Some(Self::ForIn),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! cache_id {
+    ($id:ident, $table:ident) => {
+        impl CacheField for $id {
+            fn encode(&self, enc: &mut Encoder) {
+                let i = enc.$table.local(self.0);
+                enc.write_u32(i)
+            }
+            fn decode(dec: &mut Decoder) -> Option<Self> {
+                let i = dec.read_u32()?;
+                dec.$table.get(i as usize).map(|id| $id(*id))
+            }
+        }
+    };
+}
+
+cache_id!(StringID, strings);
+cache_id!(ConstID, constants);
+cache_id!(RegexID, regexs);
+cache_id!(TemplateID, templates);
+cache_id!(FuncID, functions);
+cache_id!(ClassID, classes);
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use swc_atoms::JsWord;
 use swc_common::util::take::Take;
 use swc_ecmascript::ast::Id;
 
@@ -18,6 +19,9 @@ pub enum Variable {
     /// Captured variable will be stored into runtime context
     /// and will be read/write dynamically
     Captured(StackOffset),
+    /// a module variable stored by its dynamic variable id,
+    /// so that importers see the current value
+    Dynamic(u32),
 }
 
 #[derive(Clone)]
@@ -33,6 +37,8 @@ pub struct FunctionBuilderContextInner {
 
     pub is_try: bool,
     variables: HashMap<Id, Variable>,
+    /// the private names declared by a class body
+    private_names: HashMap<JsWord, u32>,
     pub stack_offset: StackOffset,
     capture_offset: Option<u16>,
 
@@ -50,6 +56,7 @@ impl FunctionBuilderContext {
                 //is_top_level: true,
                 is_try: false,
                 variables: Default::default(),
+                private_names: Default::default(),
                 stack_offset: 0,
                 capture_offset: None,
                 max_stack_offset: 0,
@@ -94,6 +101,7 @@ impl FunctionBuilderContext {
         let ctx = FunctionBuilderContextInner {
             parent: Some(self.inner.clone()),
             variables: Default::default(),
+            private_names: Default::default(),
 
             is_function_context: false,
             is_global_context: self.inner.is_global_context,
@@ -111,6 +119,7 @@ impl FunctionBuilderContext {
         let ctx = FunctionBuilderContextInner {
             parent: Some(self.inner.clone()),
             variables: Default::default(),
+            private_names: Default::default(),
 
             is_function_context: true,
             is_global_context: false,
@@ -160,10 +169,32 @@ impl FunctionBuilderContext {
         self.inner.to_mut().get(name, write_to)
     }
 
+    /// declare a variable that is read and written by its dynamic variable id
+    pub fn declare_dynamic(&mut self, name: Id, id: u32) {
+        self.inner.to_mut().variables.insert(name, Variable::Dynamic(id));
+    }
+
     pub fn set(&mut self, name: Id, value: Register) -> OpCode {
         self.inner.to_mut().set(name, value)
     }
 
+    /// declare a private name of the class body being built
+    pub fn declare_private_name(&mut self, name: JsWord, id: u32) {
+        self.inner.to_mut().private_names.insert(name, id);
+    }
+
+    /// resolve a private name by the innermost class body that declares it
+    pub fn get_private_name(&self, name: &JsWord) -> Option<u32> {
+        let mut ctx = Some(self.inner.clone());
+        while let Some(c) = ctx {
+            if let Some(id) = c.private_names.get(name) {
+                return Some(*id);
+            }
+            ctx = c.parent.clone();
+        }
+        None
+    }
+
     pub fn current_stack_offset(&self) -> StackOffset {
         self.inner.stack_offset
     }
@@ -225,13 +256,13 @@ impl FunctionBuilderContextInner {
     }
 
     pub fn get_next_stack_offset(&mut self) -> u16 {
-        let off = self.stack_offset;
-        self.stack_offset += 1;
-
         if self.is_global_context {
-            return off;
+            return self.get_next_global_offset();
         }
 
+        let off = self.stack_offset;
+        self.stack_offset += 1;
+
         if self.is_function_context {
             self.max_stack_offset = off.max(self.max_stack_offset);
             return off;
@@ -253,6 +284,29 @@ impl FunctionBuilderContextInner {
         return off;
     }
 
+    /// the global slots outlive the scope declaring them,
+    /// so every scope of the global context takes them from the root
+    fn get_next_global_offset(&mut self) -> u16 {
+        let mut root = self.parent.clone();
+        while let Some(p) = &root {
+            match &p.parent {
+                Some(pa) => root = Some(pa.clone()),
+                None => break,
+            }
+        }
+
+        let off = match &root {
+            Some(r) => {
+                let off = r.stack_offset.max(self.stack_offset);
+                r.to_mut().stack_offset = off + 1;
+                off
+            }
+            None => self.stack_offset,
+        };
+        self.stack_offset = off + 1;
+        off
+    }
+
     pub fn function_parent(&self) -> Arc<FunctionBuilderContextInner> {
         if self.is_function_context {
             panic!("basline: cannot get parent context of top level context.")
@@ -279,6 +333,12 @@ impl FunctionBuilderContextInner {
                         offset: *off,
                     }
                 }
+                Variable::Dynamic(id) => {
+                    return OpCode::ReadDynamicVar {
+                        result: write_to,
+                        id: *id,
+                    }
+                }
                 Variable::Const(off) => {
                     if !self.is_global_context {
                         return OpCode::ReadFromStack {
@@ -353,6 +413,12 @@ impl FunctionBuilderContextInner {
                                 offset: *off,
                             };
                         }
+                        Variable::Dynamic(id) => {
+                            return OpCode::ReadDynamicVar {
+                                result: write_to,
+                                id: *id,
+                            };
+                        }
                         Variable::Const(off) | Variable::Let(off) | Variable::Var(off) => {
                             if need_capture && !is_global {
                                 let offset = self.get_next_capture_offset();
@@ -418,6 +484,9 @@ impl FunctionBuilderContextInner {
                         offset: *off,
                     }
                 }
+                Variable::Dynamic(id) => {
+                    return OpCode::WriteDynamicVar { from: from, id: *id }
+                }
                 Variable::Const(off) => {
                     todo!("assign const offset: {}", off)
                 }
@@ -459,6 +528,9 @@ impl FunctionBuilderContextInner {
                                 offset: *off,
                             };
                         }
+                        Variable::Dynamic(id) => {
+                            return OpCode::WriteDynamicVar { from: from, id: *id };
+                        }
                         Variable::Const(off) => {
                             todo!("assign const offset:{}", off)
                         }
@@ -519,6 +591,11 @@ impl FunctionBuilderContextInner {
             return self.set(name.clone(), from);
         };
 
+        // the storage of a module variable is declared by the module
+        if let Some(Variable::Dynamic(id)) = self.variables.get(&name) {
+            return OpCode::WriteDynamicVar { from: from, id: *id };
+        }
+
         let off = self.get_next_stack_offset();
 
         if kind == DeclareKind::Let {
@@ -2,8 +2,11 @@ use crate::runtime::{ClassID, ConstID, FuncID, RegexID, StringID, TemplateID};
 
 pub mod function_builder_context;
 pub mod bytecode_builder;
+pub(crate) mod cache;
 pub mod optimize;
 
+use cache::{CacheField, Decoder, Encoder};
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
 pub struct Register(pub u8);
 
@@ -293,6 +296,7 @@ pub enum OpCode {
     WriteDynamicVar {
         #[r]
         from: Register,
+        #[var]
         id: u32,
     },
     /// write to a global variable,
@@ -308,6 +312,7 @@ pub enum OpCode {
     ReadDynamicVar {
         #[w]
         result: Register,
+        #[var]
         id: u32,
     },
 
@@ -394,10 +399,20 @@ pub enum OpCode {
         #[w]
         result: Register,
     },
-    /// get the metadata import.meta
+    /// get the metadata import.meta of the module with the url
     ImportMeta {
         #[w]
         result: Register,
+        url: StringID,
+    },
+    /// import(specifier, options), the result is a promise of the module namespace,
+    /// the specifier is resolved against the referrer
+    DynamicImport {
+        #[w]
+        result: Register,
+        stack_offset: u16,
+        args_len: u16,
+        referrer: StringID,
     },
 
     PrepareForIn{
@@ -423,7 +438,8 @@ pub enum OpCode {
         result: Register,
         stack_offset: u16,
     },
-    /// destroy the current iterator and restore the preveous one
+    /// destroy the current iterator and restore the preveous one,
+    /// the iterator is closed if it is not done
     IterDrop,
 
     IsNullish{
@@ -518,6 +534,14 @@ pub enum OpCode {
         #[r]
         arg: Register,
     },
+    /// yield* iterable, the generator is resumed after the iterator is done,
+    /// the result is the return value of the iterator
+    YieldDelegate {
+        #[w]
+        result: Register,
+        #[r]
+        iterable: Register,
+    },
 
     And {
         #[w]
@@ -921,11 +945,15 @@ pub enum OpCode {
         #[r]
         right: Register,
     },
+    /// #name in right, class is the class declaring the name
     PrivateIn {
         #[w]
         result: Register,
         #[r]
         right: Register,
+        #[r]
+        class: Register,
+        #[field]
         name: u32,
     },
     InstanceOf {
@@ -968,6 +996,7 @@ pub enum OpCode {
         obj: Register,
         #[r]
         value: Register,
+        #[field]
         field_id: u32,
     },
     /// read the field of an object with a static field name
@@ -976,19 +1005,55 @@ pub enum OpCode {
         obj: Register,
         #[w]
         result: Register,
+        #[field]
         field_id: u32,
     },
     /// remove a field from an object
     RemoveFieldStatic {
         #[r]
         obj: Register,
+        #[field]
         field_id: u32,
     },
+    /// read a private element of an object, 7.3.31 PrivateGet
+    ReadPrivateField {
+        #[r]
+        obj: Register,
+        #[w]
+        result: Register,
+        #[r]
+        class: Register,
+        #[field]
+        name: u32,
+    },
+    /// write a private element of an object, 7.3.32 PrivateSet
+    WritePrivateField {
+        #[r]
+        obj: Register,
+        #[r]
+        value: Register,
+        #[r]
+        class: Register,
+        #[field]
+        name: u32,
+    },
+    /// add a private field to an object, 7.3.29 PrivateFieldAdd
+    DefinePrivateField {
+        #[r]
+        obj: Register,
+        #[r]
+        value: Register,
+        #[r]
+        class: Register,
+        #[field]
+        name: u32,
+    },
 
     /// bind a setter into an object
     BindSetter {
         #[r]
         obj: Register,
+        #[field]
         field_id: u32,
         #[r]
         setter: Register,
@@ -997,6 +1062,7 @@ pub enum OpCode {
     BindGetter {
         #[r]
         obj: Register,
+        #[field]
         field_id: u32,
         #[r]
         getter: Register,
@@ -1023,6 +1089,7 @@ pub enum OpCode {
         result: Register,
         #[r]
         constructor: Register,
+        #[field]
         field_id: u32,
     },
     WriteSuperField {
@@ -1037,6 +1104,7 @@ pub enum OpCode {
         constructor: Register,
         #[r]
         value: Register, // 8bit
+        #[field]
         field: u32, // 32 bit
     },
 
@@ -1141,6 +1209,14 @@ pub enum OpCode {
         #[r]
         super_: Register,
     },
+    /// add the private elements of a class to an object,
+    /// a derived class does it once super() returns
+    InitializeInstanceElements {
+        #[r]
+        class: Register,
+        #[r]
+        this: Register,
+    },
 
     /// deep clone an object
     CloneObject {
@@ -1149,6 +1225,31 @@ pub enum OpCode {
         #[w]
         result: Register,
     },
+    /// add a key to the keys excluded from a rest element, the key is converted by ToPropertyKey
+    ExcludeKey {
+        #[r]
+        keys: Register,
+        #[r]
+        key: Register,
+        stack_offset: u16,
+    },
+    /// add a field name to the keys excluded from a rest element
+    ExcludeKeyStatic {
+        #[r]
+        keys: Register,
+        #[field]
+        field_id: u32,
+    },
+    /// copy the own enumerable properties into a new object, 7.3.25 CopyDataProperties,
+    /// the own keys of excluded are skipped
+    CopyDataProperties {
+        #[r]
+        from: Register,
+        #[r]
+        excluded: Register,
+        #[w]
+        result: Register,
+    },
 }
 
 #[test]
@@ -533,6 +533,11 @@ fn inline_static_function(mut codes: Vec<OpCode>) -> Vec<OpCode> {
                                                     + call_stack_offset
                                                     + arguments.len() as u16;
                                             }
+                                            OpCode::DynamicImport { stack_offset, .. } => {
+                                                *stack_offset = *stack_offset
+                                                    + call_stack_offset
+                                                    + arguments.len() as u16;
+                                            }
                                             _ => {}
                                         }
                                     }
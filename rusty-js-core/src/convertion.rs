@@ -57,6 +57,9 @@ impl From<JSString> for JValue {
 
 impl From<Error> for JValue {
     fn from(e: Error) -> Self {
-        JValue::UNDEFINED
+        if !crate::Runtime::is_attached() {
+            return JValue::UNDEFINED;
+        }
+        JObject::with_error(e).into()
     }
 }
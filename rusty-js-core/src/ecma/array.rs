@@ -44,11 +44,7 @@ fn constructor(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue,
         array.set_inner(crate::bultins::object::JObjectValue::Array(
             Default::default(),
         ));
-        array.insert_property(
-            NAMES["__proto__"],
-            ctx.runtime.prototypes.array.into(),
-            Default::default(),
-        );
+        array.set_prototype(ctx.runtime.prototypes.array.into());
         array.insert_property(NAMES["length"], 0.into(), PropFlag::WRITABLE);
         array
     } else {
@@ -125,18 +121,18 @@ fn from(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue
 
     if items.has_property(SYMBOLS["iterator"]) {
         let iter_method = items.get_property(SYMBOLS["iterator"], ctx)?;
-        let iter = iter_method.call(items.into(), &[], ctx)?;
+        let iter = iter_method.call_with_context(items.into(), &[], ctx)?;
 
         let mut values = Vec::new();
 
         loop {
             let next = iter.get_property("next", ctx)?;
-            let next_result = next.call(iter, &[], ctx)?;
+            let next_result = next.call_with_context(iter, &[], ctx)?;
             let done = next_result.get_property("done", ctx)?;
             let mut value = next_result.get_property("value", ctx)?;
 
             if let Some(f) = map_fn {
-                value = f.call(this_arg, &[value], ctx)?;
+                value = f.call_with_context(this_arg, &[value], ctx)?;
             }
 
             values.push((PropFlag::THREE, value));
@@ -159,7 +155,7 @@ fn from(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue
             let mut value = items.get_property(&i.to_string(), ctx)?;
 
             if let Some(f) = map_fn {
-                value = f.call(this_arg, &[value], ctx)?;
+                value = f.call_with_context(this_arg, &[value], ctx)?;
             }
 
             values.push((PropFlag::THREE, value));
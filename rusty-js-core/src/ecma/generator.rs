@@ -0,0 +1,96 @@
+use crate::bultins::generator::GeneratorResume;
+use crate::bultins::promise;
+use crate::error::Error;
+use crate::utils::string_interner::{NAMES, SYMBOLS};
+use crate::{value::JValue, JSContext, Runtime};
+
+macro_rules! builtin {
+    ($rt:ident, $obj:ident, $name:tt, $f:ident) => {
+        $obj.insert_property_builtin(NAMES[$name], $rt.create_native_function($f).into());
+    };
+}
+
+pub fn init(rt: &Runtime) {
+    // 27.5.1 %GeneratorPrototype%
+    let prototype = rt.prototypes.generator;
+
+    builtin!(rt, prototype, "next", next);
+    builtin!(rt, prototype, "return", return_);
+    builtin!(rt, prototype, "throw", throw);
+    prototype.insert_property_builtin(
+        SYMBOLS["iterator"],
+        rt.create_native_function(|_ctx, this, _args| Ok(this))
+            .into(),
+    );
+
+    // 27.6.1 %AsyncGeneratorPrototype%
+    let prototype = rt.prototypes.async_generator;
+
+    builtin!(rt, prototype, "next", async_next);
+    builtin!(rt, prototype, "return", async_return);
+    builtin!(rt, prototype, "throw", async_throw);
+    prototype.insert_property_builtin(
+        SYMBOLS["asyncIterator"],
+        rt.create_native_function(|_ctx, this, _args| Ok(this))
+            .into(),
+    );
+}
+
+/// 27.5.1.2 %GeneratorPrototype%.next ( value )
+fn next(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let value = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    resume(ctx, this, GeneratorResume::Next(value))
+}
+
+/// 27.5.1.3 %GeneratorPrototype%.return ( value )
+fn return_(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let value = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    resume(ctx, this, GeneratorResume::Return(value))
+}
+
+/// 27.5.1.4 %GeneratorPrototype%.throw ( exception )
+fn throw(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let value = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    resume(ctx, this, GeneratorResume::Throw(value))
+}
+
+/// 27.5.3.2 GeneratorValidate
+fn resume(ctx: JSContext, this: JValue, resume: GeneratorResume) -> Result<JValue, JValue> {
+    let obj = this.as_object();
+    match obj.as_ref().and_then(|o| o.as_generator()) {
+        Some(g) if !g.is_async => g.resume(ctx, resume),
+        _ => Err(Error::TypeError("not a generator object".to_string()).into()),
+    }
+}
+
+/// 27.6.1.2 %AsyncGeneratorPrototype%.next ( value )
+fn async_next(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let value = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    async_resume(ctx, this, GeneratorResume::Next(value))
+}
+
+/// 27.6.1.3 %AsyncGeneratorPrototype%.return ( value )
+fn async_return(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let value = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    async_resume(ctx, this, GeneratorResume::Return(value))
+}
+
+/// 27.6.1.4 %AsyncGeneratorPrototype%.throw ( exception )
+fn async_throw(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let value = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    async_resume(ctx, this, GeneratorResume::Throw(value))
+}
+
+/// 27.6.3.3 AsyncGeneratorValidate, the promise is rejected on failure
+fn async_resume(ctx: JSContext, this: JValue, resume: GeneratorResume) -> Result<JValue, JValue> {
+    let obj = this.as_object();
+    match obj.as_ref().and_then(|o| o.as_generator()) {
+        Some(g) if g.is_async => Ok(g.enqueue(ctx.runtime, resume).into()),
+        _ => {
+            let p = promise::new_promise(ctx.runtime);
+            let e = Error::TypeError("not an async generator object".to_string());
+            promise::reject_promise(ctx.runtime, p, e.into());
+            Ok(p.into())
+        }
+    }
+}
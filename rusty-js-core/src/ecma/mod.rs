@@ -1,31 +1,64 @@
 use crate::bultins::object_property::PropFlag;
 use crate::utils::string_interner::NAMES;
+use crate::runtime::Builtins;
 use crate::Runtime;
 
 mod array;
+mod generator;
 mod math;
 mod number;
 mod object;
+mod promise;
+mod timers;
 
-pub fn enable(runtime: &Runtime) {
-    let obj = object::ect(runtime);
+pub fn enable(runtime: &Runtime, builtins: Builtins) {
+    // generator objects are part of the language, not of a global
+    runtime.builtin_group("Generator", || generator::init(runtime));
 
-    runtime
-        .global_this
-        .insert_property(NAMES["Object"], obj.into(), PropFlag::BUILTIN);
+    if builtins.contains(Builtins::OBJECT) {
+        let obj = runtime.builtin_group("Object", || object::ect(runtime));
 
-    let obj = number::creat_object(runtime);
-    runtime
-        .global_this
-        .insert_property_builtin(NAMES["Number"], obj.into());
+        runtime
+            .global_this
+            .insert_property(NAMES["Object"], obj.into(), PropFlag::BUILTIN);
+    }
 
-    let obj = array::init(runtime);
-    runtime
-        .global_this
-        .insert_property_builtin(NAMES["Array"], obj.into());
+    if builtins.contains(Builtins::NUMBER) {
+        let obj = runtime.builtin_group("Number", || number::creat_object(runtime));
+        runtime
+            .global_this
+            .insert_property_builtin(NAMES["Number"], obj.into());
+    }
 
-    let obj = math::init(runtime);
-    runtime
-        .global_this
-        .insert_property_builtin(NAMES["Math"], obj.into());
+    if builtins.contains(Builtins::ARRAY) {
+        let obj = runtime.builtin_group("Array", || array::init(runtime));
+        runtime
+            .global_this
+            .insert_property_builtin(NAMES["Array"], obj.into());
+    }
+
+    if builtins.contains(Builtins::MATH) {
+        let obj = runtime.builtin_group("Math", || math::init(runtime));
+        runtime
+            .global_this
+            .insert_property_builtin(NAMES["Math"], obj.into());
+    }
+
+    if builtins.contains(Builtins::PROMISE) {
+        let obj = runtime.builtin_group("Promise", || promise::init(runtime));
+        runtime
+            .global_this
+            .insert_property_builtin(NAMES["Promise"], obj.into());
+
+        let queue = runtime.builtin_group("queueMicrotask", || {
+            runtime.create_native_function(promise::queue_microtask)
+        });
+        runtime
+            .global_this
+            .insert_property_builtin(NAMES["queueMicrotask"], queue.into());
+    }
+
+    if builtins.contains(Builtins::TIMERS) {
+        runtime.builtin_group("timers", || timers::init(runtime));
+    }
 }
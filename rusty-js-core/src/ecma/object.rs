@@ -90,7 +90,7 @@ pub fn create(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, J
     }
 
     let obj = JObject::new();
-    obj.insert_property(NAMES["__proto__"], args[0], Default::default());
+    obj.set_prototype(args[0]);
 
     if args.len() >= 2 {
         defineProperties(ctx, this, &[obj.into(), args[1]])?;
@@ -0,0 +1,153 @@
+use crate::bultins::object::JObjectValue;
+use crate::bultins::promise::{self, Promise, PromiseReaction};
+use crate::error::Error;
+use crate::runtime::Microtask;
+use crate::utils::string_interner::NAMES;
+use crate::{value::JValue, JObject, JSContext, Runtime};
+
+macro_rules! builtin {
+    ($rt:ident, $obj:ident, $name:tt, $f:ident) => {
+        $obj.insert_property_builtin(NAMES[$name], $rt.create_native_function($f).into());
+    };
+}
+
+pub fn init(rt: &Runtime) -> JObject {
+    let prototype = rt.prototypes.promise;
+
+    let obj = rt.create_constructor(constructor, "Promise", prototype);
+
+    builtin!(rt, obj, "resolve", resolve); // Promise.resolve
+    builtin!(rt, obj, "reject", reject); // Promise.reject
+
+    builtin!(rt, prototype, "then", then);
+    builtin!(rt, prototype, "catch", catch);
+
+    return obj;
+}
+
+/// 27.2.3.1 Promise ( executor )
+fn constructor(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let obj = match this.as_object() {
+        Some(obj) if obj.is_new_target() => obj,
+        _ => {
+            return Err(Error::TypeError(
+                "Promise constructor cannot be invoked without 'new'".to_string(),
+            )
+            .into())
+        }
+    };
+
+    let executor = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    if !executor.is_callable() {
+        return Err(Error::TypeError("Promise resolver is not a function".to_string()).into());
+    }
+
+    obj.set_inner(JObjectValue::Promise(Box::new(Promise::Unresolved {
+        reactions: Vec::new(),
+    })));
+
+    let (resolve, reject) = promise::create_resolving_functions(ctx.runtime, obj);
+    ctx.runtime.user_own_value(resolve.into());
+    ctx.runtime.user_own_value(reject.into());
+
+    let re = executor.call_with_context(JValue::UNDEFINED, &[resolve.into(), reject.into()], ctx);
+    let re = match re {
+        Err(e) => reject.call_with_context(JValue::UNDEFINED, &[e], ctx),
+        Ok(v) => Ok(v),
+    };
+
+    ctx.runtime.user_drop_value(resolve.into());
+    ctx.runtime.user_drop_value(reject.into());
+
+    re?;
+    Ok(obj.into())
+}
+
+/// 27.2.4.7 Promise.resolve ( x )
+fn resolve(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let x = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+
+    // a promise is returned as is
+    if let Some(obj) = x.as_object() {
+        if obj.is_promise() {
+            return Ok(x);
+        }
+    }
+
+    ctx.runtime.user_own_value(x);
+    let p = promise::new_promise(ctx.runtime);
+    ctx.runtime.user_drop_value(x);
+
+    promise::resolve_promise(ctx, p, x);
+    Ok(p.into())
+}
+
+/// 27.2.4.6 Promise.reject ( r )
+fn reject(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let r = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+
+    ctx.runtime.user_own_value(r);
+    let p = promise::new_promise(ctx.runtime);
+    ctx.runtime.user_drop_value(r);
+
+    promise::reject_promise(ctx.runtime, p, r);
+    Ok(p.into())
+}
+
+/// 27.2.5.4 Promise.prototype.then ( onFulfilled, onRejected )
+fn then(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let obj = match this.as_object() {
+        Some(obj) if obj.is_promise() => obj,
+        _ => {
+            return Err(Error::TypeError(
+                "Promise.prototype.then called on a non promise".to_string(),
+            )
+            .into())
+        }
+    };
+
+    let on_fulfilled = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    let on_rejected = args.get(1).copied().unwrap_or(JValue::UNDEFINED);
+
+    let derived = promise::new_promise(ctx.runtime);
+
+    promise::perform_then(
+        ctx.runtime,
+        obj,
+        PromiseReaction {
+            capability: Some(derived),
+            on_fulfilled,
+            on_rejected,
+        },
+    );
+    Ok(derived.into())
+}
+
+/// 27.2.5.1 Promise.prototype.catch ( onRejected )
+fn catch(ctx: JSContext, this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let on_rejected = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    let then = this.get_property(NAMES["then"], ctx)?;
+
+    if !then.is_callable() {
+        return Err(Error::TypeError("then is not a function".to_string()).into());
+    }
+    then.call_with_context(this, &[JValue::UNDEFINED, on_rejected], ctx)
+}
+
+/// queueMicrotask ( callback )
+pub(crate) fn queue_microtask(
+    ctx: JSContext,
+    _this: JValue,
+    args: &[JValue],
+) -> Result<JValue, JValue> {
+    let callback = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    if !callback.is_callable() {
+        return Err(Error::TypeError(
+            "queueMicrotask: the callback must be a function".to_string(),
+        )
+        .into());
+    }
+
+    ctx.runtime.enqueue_microtask(Microtask::Callback(callback));
+    Ok(JValue::UNDEFINED)
+}
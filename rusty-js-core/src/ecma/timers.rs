@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::utils::string_interner::NAMES;
+use crate::{value::JValue, JSContext, Runtime};
+
+macro_rules! builtin {
+    ($rt:ident, $obj:expr, $name:tt, $f:ident) => {
+        $obj.insert_property_builtin(NAMES[$name], $rt.create_native_function($f).into());
+    };
+}
+
+pub(crate) fn init(rt: &Runtime) {
+    builtin!(rt, rt.global_this, "setTimeout", set_timeout);
+    builtin!(rt, rt.global_this, "setInterval", set_interval);
+    builtin!(rt, rt.global_this, "clearTimeout", clear_timer);
+    builtin!(rt, rt.global_this, "clearInterval", clear_timer);
+}
+
+/// setTimeout ( handler, timeout, ...arguments )
+fn set_timeout(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    set_timer(ctx, args, false)
+}
+
+/// setInterval ( handler, timeout, ...arguments )
+fn set_interval(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    set_timer(ctx, args, true)
+}
+
+fn set_timer(ctx: JSContext, args: &[JValue], repeat: bool) -> Result<JValue, JValue> {
+    let handler = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+    if !handler.is_callable() {
+        return Err(Error::TypeError("the timer handler must be a function".to_string()).into());
+    }
+
+    let timeout = args
+        .get(1)
+        .copied()
+        .unwrap_or(JValue::ZERO)
+        .to_number(ctx)?;
+    // the timeout is clamped to the range of a 32 bit signed integer
+    let timeout = if timeout.is_nan() {
+        0.0
+    } else {
+        timeout.clamp(0.0, i32::MAX as f64)
+    };
+
+    // an interval repeats after at least 1ms, so it never stays due
+    let timeout = if repeat { timeout.max(1.0) } else { timeout };
+
+    let rest = args.get(2..).unwrap_or(&[]);
+    let id = ctx
+        .runtime
+        .set_timer(handler, rest, Duration::from_millis(timeout as u64), repeat);
+
+    Ok(JValue::create_number(id as f64))
+}
+
+/// clearTimeout ( id ), clearInterval ( id )
+fn clear_timer(ctx: JSContext, _this: JValue, args: &[JValue]) -> Result<JValue, JValue> {
+    let id = args
+        .get(0)
+        .copied()
+        .unwrap_or(JValue::UNDEFINED)
+        .to_number(ctx)?;
+    if id.is_finite() && id >= 1.0 && id <= u32::MAX as f64 {
+        ctx.runtime.clear_timer(id as u32);
+    }
+    Ok(JValue::UNDEFINED)
+}
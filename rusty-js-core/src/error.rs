@@ -20,9 +20,21 @@ pub enum Error {
     AwaitOnForeverPendingPromise,
     ImportError(String),
 
+    /// the code cache is corrupted or created by another engine version
+    InvalidCodeCache(String),
+    /// the heap snapshot is corrupted or cannot be restored by the runtime
+    InvalidSnapshot(String),
+
+    /// an async task is cancelled before it completes
+    AbortError(String),
+
+    /// the execution budget is exhausted or the runtime is interrupted,
+    /// this error cannot be caught by javascript
+    Terminated,
+
     RuntimeError(String),
     TypeError(String),
-    SyntaxError(String),
+    SyntaxError(SyntaxError),
     ReferenceError(String),
     RangeError(String),
     Value(JValue),
@@ -37,3 +49,126 @@ impl std::fmt::Display for Error {
         std::fmt::Debug::fmt(&self, f)
     }
 }
+
+impl Error {
+    /// the name of the javascript error constructor
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TypeError(_)
+            | Self::ExpectedFunction
+            | Self::CallOnNonFunction
+            | Self::ClassCannotBeInvokedWithoutNew
+            | Self::ClassExtendsNonCallable
+            | Self::InvalideIterator { .. } => "TypeError",
+            Self::SyntaxError(_)
+            | Self::LabelUndefined(_)
+            | Self::IllegalBreak
+            | Self::IllegalContinue
+            | Self::InvalidExpression { .. } => "SyntaxError",
+            Self::ReferenceError(_) => "ReferenceError",
+            Self::AbortError(_) => "AbortError",
+            Self::RangeError(_) | Self::FunctionCallArgumentsOverflow => "RangeError",
+            _ => "Error",
+        }
+    }
+
+    /// the message of the javascript error object
+    pub fn message(&self) -> String {
+        match self {
+            Self::SyntaxError(e) => e.message.clone(),
+            Self::TypeError(s)
+            | Self::ReferenceError(s)
+            | Self::RangeError(s)
+            | Self::RuntimeError(s)
+            | Self::AbortError(s)
+            | Self::ImportError(s)
+            | Self::InvalidCodeCache(s)
+            | Self::InvalidSnapshot(s) => s.clone(),
+            Self::InvalideIterator { msg } => msg.to_string(),
+            Self::Value(v) => v.to_string(),
+            _ => format!("{:?}", self),
+        }
+    }
+}
+
+/// a syntax error, either reported by the parser or raised at runtime
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    pub message: String,
+    /// one diagnostic for each error reported by the parser,
+    /// empty if the error is raised at runtime
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl From<String> for SyntaxError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+impl SyntaxError {
+    pub(crate) fn from_diagnostics(diagnostics: Vec<Diagnostic>) -> Self {
+        let mut message = match diagnostics.first() {
+            Some(d) => d.to_string(),
+            None => String::from("invalid syntax"),
+        };
+        if diagnostics.len() > 1 {
+            message.push_str(&format!(" (and {} more errors)", diagnostics.len() - 1));
+        }
+        Self {
+            message,
+            diagnostics,
+        }
+    }
+}
+
+/// the location and message of an error reported by the parser
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub filename: String,
+    /// line number, starts from 1
+    pub line: usize,
+    /// column in characters, starts from 1
+    pub column: usize,
+    /// byte range in the source text
+    pub span: Range<u32>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// locate a byte range in the source text
+    pub(crate) fn new(filename: &str, source: &str, span: Range<u32>, message: String) -> Self {
+        let start = (span.start as usize).min(source.len());
+        let before = source.get(..start).unwrap_or_default();
+
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+
+        Self {
+            filename: filename.to_string(),
+            line,
+            column,
+            span,
+            message,
+        }
+    }
+
+    /// the text of the line the error starts on, without the line break
+    pub fn source_line<'a>(&self, source: &'a str) -> &'a str {
+        source.lines().nth(self.line - 1).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.filename, self.line, self.column, self.message
+        )
+    }
+}
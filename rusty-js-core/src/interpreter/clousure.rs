@@ -1,15 +1,19 @@
+use std::sync::Arc;
+
 use likely_stable::likely;
 
 use crate::bultins::object_property::PropFlag;
+use crate::bultins::class::PrivateName;
 use crate::bultins::function::CaptureStack;
+use crate::bultins::promise::PromiseFuture;
 use crate::bultins::object::JObject;
 use crate::bytecodes::{Block, OpCode, Register};
 use crate::error::Error;
-use crate::runtime::Runtime;
+use crate::runtime::{ModuleId, Runtime, TaskRoots};
 use crate::value::JValue;
 use crate::utils::iterator::JSIterator;
 use crate::utils::string_interner::NAMES;
-use crate::{operations, JSContext, PropKey, Promise};
+use crate::{operations, JSContext, PropKey};
 
 use super::Registers;
 use super::Res;
@@ -48,6 +52,17 @@ struct ClousureState<'a> {
     temps: Vec<JValue>,
 }
 
+impl<'a> ClousureState<'a> {
+    /// the values held outside of the stack, marked while the code is suspended
+    fn roots(&self, regs: &Registers) -> Vec<JValue> {
+        let mut roots = regs.0.to_vec();
+        roots.push(self.accumulator);
+        roots.extend_from_slice(&self.temps);
+        roots.extend(self.iterators.iter().map(|i| i.object()));
+        roots
+    }
+}
+
 pub struct Clousure {
     codes: Vec<
         Box<
@@ -121,6 +136,10 @@ impl Clousure {
 
             match re {
                 Err(e) => {
+                    // termination cannot be caught
+                    if state.runtime.is_terminating() {
+                        return Err(e);
+                    }
                     if let Some((_catch_block, line)) = state.catch_block.pop() {
                         regs[Register(0)] = e;
 
@@ -190,6 +209,10 @@ impl Clousure {
 
             match re {
                 Err(e) => {
+                    // termination cannot be caught
+                    if state.runtime.is_terminating() {
+                        return Err(e);
+                    }
                     if let Some((_catch_block, line)) = state.catch_block.pop() {
                         regs[Register(0)] = e;
 
@@ -207,45 +230,40 @@ impl Clousure {
                         regs[r] = v;
                     },
                     Res::Await(v, r) => {
-                        if let Some(o) = v.as_object(){
-                            if let Some(p) = o.as_promise(){
-                                match p{
-                                    Promise::ForeverPending => {
-                                        return Err(Error::AwaitOnForeverPendingPromise.into());
-                                    },
-                                    Promise::Fulfilled(f) => {
-                                        regs[r] = *f;
-                                    },
-                                    Promise::Rejected(e) => {
-                                        if let Some((_catch_block, line)) = state.catch_block.pop() {
-                                            regs[Register(0)] = *e;
-                    
-                                            i = line as usize;
-                                        } else {
-                                            return Err(*e);
-                                        }
-                                    }
-                                    Promise::Pending { id } => {
-                                        let re = runtime.to_mut().get_future(*id).await;
-                                        match re{
-                                            Ok(v) => {
-                                                regs[r] = v
-                                            }
-                                            Err(e) => {
-                                                if let Some((_catch_block, line)) = state.catch_block.pop() {
-                                                    regs[Register(0)] = e;
-                            
-                                                    i = line as usize;
-                                                } else {
-                                                    return Err(e);
-                                                }
-                                            }
-                                        }
-                                    }
+                        // the values of the state are owned while suspended
+                        let roots = TaskRoots::new(runtime, this, &state.roots(&regs));
+
+                        // await always suspends, even on a settled value,
+                        // the caller keeps running until the next job
+                        let mut suspended = false;
+                        std::future::poll_fn(|cx| {
+                            if std::mem::replace(&mut suspended, true) {
+                                return std::task::Poll::Ready(());
+                            }
+                            cx.waker().wake_by_ref();
+                            std::task::Poll::Pending
+                        })
+                        .await;
+
+                        let re = PromiseFuture::new(runtime.arc(), v).await;
+                        drop(roots);
+
+                        match re {
+                            Ok(v) => regs[r] = v,
+                            Err(e) => {
+                                let e = Runtime::value_from_error(e);
+                                // termination cannot be caught
+                                if runtime.is_terminating() {
+                                    return Err(e);
+                                }
+                                if let Some((_catch_block, line)) = state.catch_block.pop() {
+                                    regs[Register(0)] = e;
+
+                                    i = line as usize;
+                                } else {
+                                    return Err(e);
                                 }
                             }
-                        } else{
-                            regs[r] = v;
                         }
                     }
                     _ => {}
@@ -257,6 +275,101 @@ impl Clousure {
         Ok(JValue::UNDEFINED)
     }
 
+    /// run the global code of a module with top level await,
+    /// the code is suspended until the awaited promise is settled
+    pub(crate) async fn run_module_async(
+        &mut self,
+        runtime: &Arc<Runtime>,
+        module: ModuleId,
+        stack: &mut [JValue],
+        op_stack: &mut [JValue],
+    ) -> Result<JValue, JValue> {
+        let mut i = 0;
+
+        let mut state = ClousureState {
+            runtime: runtime.as_ref(),
+            op_stack: op_stack.as_mut_ptr(),
+            accumulator: JValue::UNDEFINED,
+
+            arg_offset_counter: 0,
+            arg_len: 0,
+            cap: None,
+            capture_stack: None,
+            catch_block: Default::default(),
+            is_global: true,
+            iterators: Vec::new(),
+            temps: Default::default(),
+        };
+
+        let mut regs = Registers([JValue::UNDEFINED; 3]);
+        let ctx = JSContext {
+            stack: state.op_stack,
+            runtime: state.runtime,
+        };
+        // this is undefined at the top level of a module
+        let mut this = JValue::UNDEFINED;
+
+        loop {
+            if i == self.codes.len() {
+                break;
+            }
+            let code = unsafe { self.codes.get_unchecked_mut(i) };
+
+            let re = (code)(&mut state, ctx, &mut regs, &mut this, &[], stack, &mut i);
+
+            let e = match re {
+                Err(e) => e,
+                Ok(Res::Return(r)) => return Ok(r),
+                Ok(Res::Await(v, r)) => {
+                    // the module marks the values of the code while it is suspended
+                    runtime.set_module_roots(module, state.roots(&regs));
+
+                    // await always suspends, even on a settled value,
+                    // the importers keep running while the module is suspended
+                    let mut suspended = false;
+                    std::future::poll_fn(|cx| {
+                        if std::mem::replace(&mut suspended, true) {
+                            return std::task::Poll::Ready(());
+                        }
+                        cx.waker().wake_by_ref();
+                        std::task::Poll::Pending
+                    })
+                    .await;
+
+                    let re = PromiseFuture::new(runtime.clone(), v).await;
+                    runtime.set_module_roots(module, Vec::new());
+
+                    match re {
+                        Ok(v) => {
+                            regs[r] = v;
+                            i += 1;
+                            continue;
+                        }
+                        Err(e) => Runtime::value_from_error(e),
+                    }
+                }
+                Ok(_) => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            // termination cannot be caught
+            if runtime.is_terminating() {
+                return Err(e);
+            }
+            if let Some((_catch_block, line)) = state.catch_block.pop() {
+                regs[Register(0)] = e;
+                i = line as usize;
+            } else {
+                return Err(e);
+            }
+
+            i += 1;
+        }
+        Ok(JValue::UNDEFINED)
+    }
+
     fn create_code<'a>(
         code: OpCode,
     ) -> Box<
@@ -1693,6 +1806,17 @@ impl Clousure {
                     Ok(Res::Ok)
                 },
             ),
+            OpCode::YieldDelegate { result, iterable } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    return Ok(Res::YieldDelegate(regs[iterable], result));
+                },
+            ),
             OpCode::In {
                 result,
                 left,
@@ -1724,8 +1848,9 @@ impl Clousure {
             ),
             OpCode::PrivateIn {
                 result,
-                name,
                 right,
+                class,
+                name,
             } => Box::new(
                 move |state: &mut ClousureState,
                       ctx: JSContext,
@@ -1735,19 +1860,8 @@ impl Clousure {
                       stack: &mut [JValue],
                       index: &mut usize| {
                     let rhs = regs[right];
-
-                    let key = state.runtime.get_field_name(name);
-
-                    if let Some(obj) = rhs.as_object() {
-                        regs[result] = obj.has_property(PropKey(name)).into();
-                    } else {
-                        return Err(Error::TypeError(format!(
-                            "Cannot use 'in' operator to search for '#{}' in {}",
-                            key,
-                            rhs.to_string()
-                        ))
-                        .into());
-                    };
+                    let name = PrivateName::of(regs[class], name);
+                    regs[result] = rhs.has_private_element(state.runtime, name)?.into();
                     Ok(Res::Ok)
                 },
             ),
@@ -1897,7 +2011,9 @@ impl Clousure {
                       args: &[JValue],
                       stack: &mut [JValue],
                       index: &mut usize| {
-                    state.iterators.pop();
+                    if let Some(mut iter) = state.iterators.pop() {
+                        iter.close()?;
+                    }
                     Ok(Res::Ok)
                 },
             ),
@@ -1918,7 +2034,10 @@ impl Clousure {
                     let re = iter.next();
                     match re {
                         Some(v) => match v {
-                            Ok(v) => regs[result] = v,
+                            Ok(v) => {
+                                regs[result] = v;
+                                regs[done] = JValue::FALSE;
+                            }
                             Err(e) => return Err(e),
                         },
                         None => {
@@ -2364,7 +2483,7 @@ impl Clousure {
                     Ok(Res::Ok)
                 },
             ),
-            OpCode::ImportMeta { result } => Box::new(
+            OpCode::ImportMeta { result, url } => Box::new(
                 move |state: &mut ClousureState,
                       ctx: JSContext,
                       regs: &mut Registers,
@@ -2372,7 +2491,30 @@ impl Clousure {
                       args: &[JValue],
                       stack: &mut [JValue],
                       index: &mut usize| {
-                    regs[result] = operations::import_meta(&state.runtime);
+                    regs[result] = operations::import_meta(&state.runtime, url.0);
+                    Ok(Res::Ok)
+                },
+            ),
+            OpCode::DynamicImport {
+                result,
+                stack_offset,
+                args_len,
+                referrer,
+            } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    let args = &mut stack[stack_offset as usize..];
+                    regs[result] = operations::dynamic_import(
+                        &state.runtime,
+                        args.as_mut_ptr(),
+                        args_len as u32,
+                        referrer.0,
+                    );
                     Ok(Res::Ok)
                 },
             ),
@@ -2400,6 +2542,10 @@ impl Clousure {
                       args: &[JValue],
                       stack: &mut [JValue],
                       index: &mut usize| {
+                    // loop back edge
+                    if line as usize <= *index {
+                        state.runtime.consume_fuel()?;
+                    }
                     *index = line as usize;
                     return Ok(Res::Ok);
                     Ok(Res::Ok)
@@ -2414,6 +2560,10 @@ impl Clousure {
                       stack: &mut [JValue],
                       index: &mut usize| {
                     if !regs[value].to_bool() {
+                        // loop back edge
+                        if line as usize <= *index {
+                            state.runtime.consume_fuel()?;
+                        }
                         *index = line as usize;
                         return Ok(Res::Ok);
                     };
@@ -2429,6 +2579,10 @@ impl Clousure {
                       stack: &mut [JValue],
                       index: &mut usize| {
                     if regs[value].to_bool() {
+                        // loop back edge
+                        if line as usize <= *index {
+                            state.runtime.consume_fuel()?;
+                        }
                         *index = line as usize;
                         return Ok(Res::Ok);
                     };
@@ -2602,12 +2756,14 @@ impl Clousure {
                       stack: &mut [JValue],
                       index: &mut usize| {
                     let obj = regs[obj];
-                    let field = regs[field].to_string();
-                    let id = state.runtime.register_field_name(&field);
                     let stack = &mut stack[stack_offset as usize..];
+                    let key = regs[field].to_property_key(JSContext {
+                        stack: stack.as_mut_ptr(),
+                        runtime: state.runtime,
+                    })?;
 
                     regs[result] = obj.get_property(
-                        PropKey(id),
+                        key,
                         JSContext {
                             stack: stack.as_mut_ptr(),
                             runtime: state.runtime,
@@ -2648,12 +2804,14 @@ impl Clousure {
                       stack: &mut [JValue],
                       index: &mut usize| {
                     let obj = regs[obj];
-                    let field = regs[field].to_string();
-                    let id = state.runtime.register_field_name(&field);
                     let stack = &mut stack[stack_offset as usize..];
+                    let key = regs[field].to_property_key(JSContext {
+                        stack: stack.as_mut_ptr(),
+                        runtime: state.runtime,
+                    })?;
 
                     obj.set_property(
-                        PropKey(id),
+                        key,
                         regs[value],
                         JSContext {
                             stack: stack.as_mut_ptr(),
@@ -2701,6 +2859,100 @@ impl Clousure {
                     Ok(Res::Ok)
                 },
             ),
+            OpCode::ExcludeKey {
+                keys,
+                key,
+                stack_offset,
+            } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    let stack = &mut stack[stack_offset as usize..];
+                    let key = regs[key].to_property_key(JSContext {
+                        stack: stack.as_mut_ptr(),
+                        runtime: state.runtime,
+                    })?;
+                    if let Some(keys) = regs[keys].as_object() {
+                        keys.insert_property(key, JValue::UNDEFINED, Default::default());
+                    };
+                    Ok(Res::Ok)
+                },
+            ),
+            OpCode::ExcludeKeyStatic { keys, field_id } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    if let Some(keys) = regs[keys].as_object() {
+                        keys.insert_property(PropKey(field_id), JValue::UNDEFINED, Default::default());
+                    };
+                    Ok(Res::Ok)
+                },
+            ),
+            OpCode::ReadPrivateField {
+                obj,
+                result,
+                class,
+                name,
+            } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    let obj = regs[obj];
+                    let name = PrivateName::of(regs[class], name);
+                    regs[result] = obj.private_get(name, ctx)?;
+                    Ok(Res::Ok)
+                },
+            ),
+            OpCode::WritePrivateField {
+                obj,
+                value,
+                class,
+                name,
+            } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    let obj = regs[obj];
+                    let name = PrivateName::of(regs[class], name);
+                    obj.private_set(name, regs[value], ctx)?;
+                    Ok(Res::Ok)
+                },
+            ),
+            OpCode::DefinePrivateField {
+                obj,
+                value,
+                class,
+                name,
+            } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    let obj = regs[obj];
+                    let name = PrivateName::of(regs[class], name);
+                    obj.private_field_add(state.runtime, name, regs[value])?;
+                    Ok(Res::Ok)
+                },
+            ),
             OpCode::ReadSuperField {
                 constructor,
                 result,
@@ -2882,6 +3134,29 @@ impl Clousure {
                     Ok(Res::Ok)
                 },
             ),
+            OpCode::CopyDataProperties {
+                from,
+                excluded,
+                result,
+            } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    regs[result] = operations::copy_data_properties(
+                        regs[from],
+                        regs[excluded],
+                        JSContext {
+                            stack: ctx.stack,
+                            runtime: state.runtime,
+                        },
+                    )?;
+                    Ok(Res::Ok)
+                },
+            ),
             OpCode::ExtendObject { obj, from } => Box::new(
                 move |state: &mut ClousureState,
                       ctx: JSContext,
@@ -3083,6 +3358,23 @@ impl Clousure {
                     Ok(Res::Ok)
                 },
             ),
+            OpCode::InitializeInstanceElements { class, this } => Box::new(
+                move |state: &mut ClousureState,
+                      ctx: JSContext,
+                      regs: &mut Registers,
+                      this: &mut JValue,
+                      args: &[JValue],
+                      stack: &mut [JValue],
+                      index: &mut usize| {
+                    let class = regs[class].as_object();
+                    let this = regs[this].as_object();
+
+                    if let (Some(c), Some(this)) = (class.as_ref().and_then(|c| c.as_class()), this) {
+                        c.initialize_instance_elements(state.runtime, this, ctx.stack)?;
+                    }
+                    Ok(Res::Ok)
+                },
+            ),
         }
     }
 }
@@ -1,19 +1,24 @@
+use std::rc::Rc;
 use std::sync::Arc;
+use std::task::Poll;
 
 use futures::Future;
 use fxhash::FxHashMap;
 use likely_stable::likely;
 
 use crate::bultins::object_property::PropFlag;
+use crate::bultins::class::PrivateName;
 use crate::bultins::function::CaptureStack;
+use crate::bultins::generator::{GeneratorChannel, Resumed};
 use crate::bultins::object::JObject;
+use crate::bultins::promise::PromiseFuture;
 use crate::bytecodes::{Block, OpCode, Register};
 use crate::error::Error;
-use crate::runtime::Runtime;
+use crate::runtime::{Runtime, TaskRoots};
 use crate::value::JValue;
 use crate::utils::iterator::JSIterator;
 use crate::utils::string_interner::{NAMES, SYMBOLS};
-use crate::{operations, JSContext, Promise, PropKey, ToProperyKey};
+use crate::{operations, JSContext, PropKey, ToProperyKey};
 
 //use self::block_compiler::CompiledBlock;
 
@@ -60,6 +65,8 @@ pub enum Res {
     Ok,
     Await(JValue, Register),
     Yield(JValue, Register),
+    /// yield* to the iterable
+    YieldDelegate(JValue, Register),
 
     Return(JValue),
 
@@ -213,6 +220,10 @@ impl<'a> Interpreter<'a> {
 
             match re {
                 Err(e) => {
+                    // termination cannot be caught
+                    if self.runtime.is_terminating() {
+                        return Err(e);
+                    }
                     if let Some((_catch_block, line)) = self.catch_block.pop() {
                         self.r[Register(0)] = e;
 
@@ -225,8 +236,12 @@ impl<'a> Interpreter<'a> {
                     match re {
                         Res::Await(_, _) => {}
                         Res::Yield(_, _) => {}
+                        Res::YieldDelegate(_, _) => {}
                         Res::Ok => {}
                         Res::Err(e) => {
+                            if self.runtime.is_terminating() {
+                                return Err(e);
+                            }
                             if let Some((_catch_block, line)) = self.catch_block.pop() {
                                 self.r[Register(0)] = e;
 
@@ -269,6 +284,10 @@ impl<'a> Interpreter<'a> {
 
                 match re {
                     Err(e) => {
+                        // termination cannot be caught
+                        if intpr.runtime.is_terminating() {
+                            return Err(e);
+                        }
                         if let Some((catch_block, line)) = intpr.catch_block.pop() {
                             intpr.r[Register(0)] = e;
 
@@ -280,37 +299,46 @@ impl<'a> Interpreter<'a> {
                     Ok(r) => {
                         match r {
                             Res::Await(f, result) => {
-                                if let Some(obj) = f.as_object() {
-                                    if let Some(p) = obj.as_promise() {
-                                        match p {
-                                            Promise::ForeverPending => {
-                                                return Err(
-                                                    Error::AwaitOnForeverPendingPromise.into()
-                                                )
-                                            }
-                                            Promise::Fulfilled(f) => {
-                                                intpr.r[result] = *f;
-                                            }
-                                            Promise::Pending { id } => {
-                                                let re =
-                                                    intpr.runtime.to_mut().get_future(*id).await;
-                                                match re {
-                                                    Ok(v) => {
-                                                        intpr.r[result] = v;
-                                                        *p = Promise::Fulfilled(v);
-                                                    }
-                                                    Err(e) => {
-                                                        *p = Promise::Rejected(e);
-                                                        return Err(e);
-                                                    }
-                                                };
-                                            }
-                                            Promise::Rejected(e) => return Err(*e),
+                                // the values of the interpreter are owned while suspended
+                                let roots = TaskRoots::new(
+                                    intpr.runtime,
+                                    this,
+                                    &intpr.suspended_roots(this),
+                                );
+
+                                // await always suspends, even on a settled value,
+                                // the caller keeps running until the next job
+                                let mut suspended = false;
+                                std::future::poll_fn(|cx| {
+                                    if std::mem::replace(&mut suspended, true) {
+                                        return Poll::Ready(());
+                                    }
+                                    cx.waker().wake_by_ref();
+                                    Poll::Pending
+                                })
+                                .await;
+
+                                let re = PromiseFuture::new(intpr.runtime.arc(), f).await;
+                                drop(roots);
+
+                                match re {
+                                    Ok(v) => intpr.r[result] = v,
+                                    Err(e) => {
+                                        let e = Runtime::value_from_error(e);
+                                        // termination cannot be caught
+                                        if intpr.runtime.is_terminating() {
+                                            return Err(e);
+                                        }
+                                        if let Some((_catch_block, line)) = intpr.catch_block.pop()
+                                        {
+                                            intpr.r[Register(0)] = e;
+
+                                            i = line as usize;
+                                        } else {
+                                            return Err(e);
                                         }
-                                    };
-                                } else {
-                                    intpr.r[result] = f;
-                                };
+                                    }
+                                }
                             }
                             Res::Yield(_, _) => {}
                             // the outermost context should not be break
@@ -327,46 +355,91 @@ impl<'a> Interpreter<'a> {
         }
     }
 
-    pub fn run_async_generator(
-        mut self,
+    /// consume self and create the body of a generator,
+    /// the body is suspended at each yield until the generator is resumed
+    pub(crate) fn run_generator(
+        self,
         mut this: JValue,
         args: &[JValue],
-        codes: &[OpCode],
-    ) -> Result<JValue, JValue> {
-        let mut i = 0;
+        codes: Arc<Vec<OpCode>>,
+        generator: Rc<GeneratorChannel>,
+    ) -> impl Future<Output = Result<JValue, JValue>> + 'static {
+        let args = args.to_vec();
+        let mut intpr: Interpreter<'static> = unsafe { std::mem::transmute(self) };
 
-        loop {
-            if i == codes.len() {
-                break;
-            }
-            let code = codes[i];
+        async move {
+            let mut i = 0;
 
-            //debug::debug!("run code {:#?}", code);
+            loop {
+                if i == codes.len() {
+                    break;
+                }
+                let code = codes[i];
 
-            let re = self.run_code(&mut this, args, code, codes, &mut i);
+                let re = intpr.run_code(&mut this, &args, code, &codes, &mut i);
 
-            match re {
-                Err(e) => {
-                    if let Some((catch_block, line)) = self.catch_block.pop() {
-                        self.r[Register(0)] = e;
+                // the values of the interpreter are marked by the generator while suspended
+                if let Ok(Res::Await(..) | Res::Yield(..) | Res::YieldDelegate(..)) = re {
+                    generator.set_roots(intpr.suspended_roots(this));
+                }
 
-                        i = line as usize;
-                    } else {
-                        return Err(e);
+                let resumed = match re {
+                    Err(e) | Ok(Res::Err(e)) => Err(e),
+                    Ok(Res::Return(r)) if generator.is_async => {
+                        return generator.await_value(r).await
                     }
-                }
-                Ok(re) => {
-                    match re {
-                        Res::Ok => {}
-                        Res::Return(r) => return Ok(r),
-                        _ => {}
+                    Ok(Res::Return(r)) => return Ok(r),
+                    Ok(Res::Await(f, result)) => generator
+                        .await_value(f)
+                        .await
+                        .map(|v| (Resumed::Normal(v), result)),
+                    Ok(Res::Yield(v, result)) => {
+                        generator.yield_value(v).await.map(|r| (r, result))
+                    }
+                    Ok(Res::YieldDelegate(iterable, result)) => {
+                        let stack = intpr.stack[intpr.op_stack_offset..].as_mut_ptr();
+                        generator
+                            .delegate(stack, iterable)
+                            .await
+                            .map(|r| (r, result))
+                    }
+                    Ok(Res::Ok) => {
+                        i += 1;
+                        continue;
+                    }
+                };
+
+                match resumed {
+                    Ok((Resumed::Normal(v), result)) => intpr.r[result] = v,
+                    Ok((Resumed::Return(v), _)) => return Ok(v),
+                    Err(e) => {
+                        // termination cannot be caught
+                        if intpr.runtime.is_terminating() {
+                            return Err(e);
+                        }
+                        if let Some((_catch_block, line)) = intpr.catch_block.pop() {
+                            intpr.r[Register(0)] = e;
+
+                            i = line as usize;
+                        } else {
+                            return Err(e);
+                        }
                     }
                 }
-            }
 
-            i += 1;
+                i += 1;
+            }
+            Ok(JValue::UNDEFINED)
         }
-        Ok(JValue::UNDEFINED)
+    }
+
+    /// the values held by the interpreter outside of the stack
+    fn suspended_roots(&self, this: JValue) -> Vec<JValue> {
+        let mut roots = self.r.0.to_vec();
+        roots.push(this);
+        roots.extend_from_slice(&self.temps);
+        roots.extend(self.iterators.iter().map(|i| i.object()));
+        roots
     }
 
     #[inline]
@@ -1186,6 +1259,9 @@ impl<'a> Interpreter<'a> {
 
             OpCode::Await { result, future } => return Ok(Res::Await(self.r[future], result)),
             OpCode::Yield { result, arg } => return Ok(Res::Yield(self.r[arg], result)),
+            OpCode::YieldDelegate { result, iterable } => {
+                return Ok(Res::YieldDelegate(self.r[iterable], result))
+            }
             OpCode::In {
                 result,
                 left,
@@ -1208,23 +1284,13 @@ impl<'a> Interpreter<'a> {
             }
             OpCode::PrivateIn {
                 result,
-                name,
                 right,
+                class,
+                name,
             } => {
                 let rhs = self.r[right];
-
-                let key = self.runtime.get_field_name(name);
-
-                if let Some(obj) = rhs.as_object() {
-                    self.r[result] = obj.has_property(PropKey(name)).into();
-                } else {
-                    return Err(Error::TypeError(format!(
-                        "Cannot use 'in' operator to search for '#{}' in {}",
-                        key,
-                        rhs.to_string()
-                    ))
-                    .into());
-                }
+                let name = PrivateName::of(self.r[class], name);
+                self.r[result] = rhs.has_private_element(self.runtime, name)?.into();
             }
             OpCode::InstanceOf {
                 result,
@@ -1285,7 +1351,9 @@ impl<'a> Interpreter<'a> {
 
             }
             OpCode::IterDrop => {
-                self.iterators.pop();
+                if let Some(mut iter) = self.iterators.pop() {
+                    iter.close()?;
+                }
             }
             OpCode::IterNext {
                 result,
@@ -1297,7 +1365,10 @@ impl<'a> Interpreter<'a> {
                 let re = iter.next();
                 match re {
                     Some(v) => match v {
-                        Ok(v) => self.r[result] = v,
+                        Ok(v) => {
+                            self.r[result] = v;
+                            self.r[done] = JValue::FALSE;
+                        }
                         Err(e) => return Err(e),
                     },
                     None => {
@@ -1474,7 +1545,7 @@ impl<'a> Interpreter<'a> {
                     &mut self.stack[stack_offset as usize..args_len as usize + stack_offset as usize];
                 let stack = unsafe { args.as_mut_ptr().add(args_len as usize) };
 
-                let v = callee.call(
+                let v = callee.call_with_context(
                     this,
                     args,
                     JSContext {
@@ -1513,8 +1584,22 @@ impl<'a> Interpreter<'a> {
             OpCode::NewTarget { result } => {
                 self.r[result] = operations::new_target(&self.runtime);
             }
-            OpCode::ImportMeta { result } => {
-                self.r[result] = operations::import_meta(&self.runtime);
+            OpCode::ImportMeta { result, url } => {
+                self.r[result] = operations::import_meta(&self.runtime, url.0);
+            }
+            OpCode::DynamicImport {
+                result,
+                stack_offset,
+                args_len,
+                referrer,
+            } => {
+                let args = &mut self.stack[stack_offset as usize..];
+                self.r[result] = operations::dynamic_import(
+                    &self.runtime,
+                    args.as_mut_ptr(),
+                    args_len as u32,
+                    referrer.0,
+                );
             }
 
             ////////////////////////////////////////////////////////////////////////
@@ -1527,17 +1612,27 @@ impl<'a> Interpreter<'a> {
                 //self.insert_block(b, *index)
             }
             OpCode::Jump { to:_, line } => {
+                // loop back edge
+                if line as usize <= *index {
+                    self.runtime.consume_fuel()?;
+                }
                 *index = line as usize;
                 return Ok(Res::Ok);
             }
             OpCode::JumpIfFalse { value, to:_, line } => {
                 if !self.r[value].to_bool() {
+                    if line as usize <= *index {
+                        self.runtime.consume_fuel()?;
+                    }
                     *index = line as usize;
                     return Ok(Res::Ok);
                 }
             }
             OpCode::JumpIfTrue { value, to:_, line } => {
                 if self.r[value].to_bool() {
+                    if line as usize <= *index {
+                        self.runtime.consume_fuel()?;
+                    }
                     *index = line as usize;
                     return Ok(Res::Ok);
                 }
@@ -1594,12 +1689,14 @@ impl<'a> Interpreter<'a> {
                 stack_offset,
             } => {
                 let obj = self.r[obj];
-                let field = self.r[field].to_string();
-                let id = self.runtime.register_field_name(&field);
                 let stack = &mut self.stack[stack_offset as usize..];
+                let key = self.r[field].to_property_key(JSContext {
+                    stack: stack.as_mut_ptr(),
+                    runtime: self.runtime,
+                })?;
 
                 self.r[result] = obj.get_property(
-                    PropKey(id),
+                    key,
                     JSContext {
                         stack: stack.as_mut_ptr(),
                         runtime: self.runtime,
@@ -1623,12 +1720,14 @@ impl<'a> Interpreter<'a> {
                 stack_offset,
             } => {
                 let obj = self.r[obj];
-                let field = self.r[field].to_string();
-                let id = self.runtime.register_field_name(&field);
                 let stack = &mut self.stack[stack_offset as usize..];
+                let key = self.r[field].to_property_key(JSContext {
+                    stack: stack.as_mut_ptr(),
+                    runtime: self.runtime,
+                })?;
 
                 obj.set_property(
-                    PropKey(id),
+                    key,
                     self.r[value],
                     JSContext {
                         stack: stack.as_mut_ptr(),
@@ -1657,6 +1756,58 @@ impl<'a> Interpreter<'a> {
                     obj.remove_property(PropKey(field_id));
                 }
             }
+            OpCode::ExcludeKey {
+                keys,
+                key,
+                stack_offset,
+            } => {
+                let stack = &mut self.stack[stack_offset as usize..];
+                let key = self.r[key].to_property_key(JSContext {
+                    stack: stack.as_mut_ptr(),
+                    runtime: self.runtime,
+                })?;
+                if let Some(keys) = self.r[keys].as_object() {
+                    keys.insert_property(key, JValue::UNDEFINED, Default::default());
+                }
+            }
+            OpCode::ExcludeKeyStatic { keys, field_id } => {
+                if let Some(keys) = self.r[keys].as_object() {
+                    keys.insert_property(PropKey(field_id), JValue::UNDEFINED, Default::default());
+                }
+            }
+
+            OpCode::ReadPrivateField {
+                obj,
+                result,
+                class,
+                name,
+            } => {
+                let obj = self.r[obj];
+                let name = PrivateName::of(self.r[class], name);
+                self.r[result] = obj.private_get(name, ctx)?;
+            }
+
+            OpCode::WritePrivateField {
+                obj,
+                value,
+                class,
+                name,
+            } => {
+                let obj = self.r[obj];
+                let name = PrivateName::of(self.r[class], name);
+                obj.private_set(name, self.r[value], ctx)?;
+            }
+
+            OpCode::DefinePrivateField {
+                obj,
+                value,
+                class,
+                name,
+            } => {
+                let obj = self.r[obj];
+                let name = PrivateName::of(self.r[class], name);
+                obj.private_field_add(self.runtime, name, self.r[value])?;
+            }
 
             OpCode::ReadSuperField {
                 constructor,
@@ -1775,6 +1926,14 @@ impl<'a> Interpreter<'a> {
                     self.r[result] = JValue::create_object(obj.deep_clone());
                 }
             }
+            OpCode::CopyDataProperties {
+                from,
+                excluded,
+                result,
+            } => {
+                self.r[result] =
+                    operations::copy_data_properties(self.r[from], self.r[excluded], ctx)?;
+            }
             OpCode::ExtendObject { obj, from } => {
                 unsafe { operations::extend_object(self.r[obj], self.r[from], self.runtime) };
             }
@@ -1884,6 +2043,14 @@ impl<'a> Interpreter<'a> {
                     }
                 }
             },
+            OpCode::InitializeInstanceElements { class, this } => {
+                let class = self.r[class].as_object();
+                let this = self.r[this].as_object();
+
+                if let (Some(c), Some(this)) = (class.as_ref().and_then(|c| c.as_class()), this) {
+                    c.initialize_instance_elements(self.runtime, this, ctx.stack)?;
+                }
+            }
         };
         Ok(Res::Ok)
     }
@@ -17,7 +17,14 @@ mod debug;
 
 mod type_script;
 
-pub use runtime::{ClassID, ConstID, FuncID, RegexID, Runtime, StringID, TemplateID};
+#[cfg(feature = "serde")]
+mod serde_value;
+
+pub use runtime::{
+    Builtins, ClassID, ConstID, ExceptionHandler, FsResolver, FuncID, ImportAssertion, ImportMap,
+    ImportMetaHook, ImportResolver, InterruptHandle, RegexID, RejectionHandler, RejectionOperation,
+    Runtime, RuntimeBuilder, RuntimeOptions, Script, StringID, TemplateID,
+};
 
 pub use bultins::{
     bigint::JSBigInt, function::JSContext, object::CustomObject, object::JObject, object::PropKey,
@@ -25,4 +32,8 @@ pub use bultins::{
     strings::JSString, symbol::JSymbol, typed_array::TypedArray,
 };
 
+pub use error::{Diagnostic, Error, SyntaxError};
 pub use value::JValue;
+
+#[cfg(feature = "serde")]
+pub use serde_value::{from_value, to_value};
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use crate::bultins::function::CaptureStack;
 use crate::bultins::object::JObject;
 use crate::error::Error;
-use crate::runtime::{FuncID, Runtime, TemplateID};
+use crate::runtime::{FuncID, Runtime, StringID, TemplateID};
 use crate::value::JValue;
 use crate::utils::iterator::JSIterator;
 use crate::utils::string_interner::NAMES;
@@ -219,7 +219,7 @@ pub extern "C" fn invoke_new(
             Ok(v) => v,
             Err(e) => { *result = Result(e, true); return;},
         };
-        this.insert_property(NAMES["__proto__"], proto, Default::default());
+        this.set_prototype(proto);
 
         // get the old target
         let old_target = runtime.new_target;
@@ -227,7 +227,7 @@ pub extern "C" fn invoke_new(
 
         let re = unsafe {
             let args = std::slice::from_raw_parts(stack, argc);
-            constructor.call(
+            constructor.call_with_context(
                 this.into(),
                 args,
                 JSContext {
@@ -264,14 +264,42 @@ pub extern "C" fn invoke_new(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn consume_fuel(runtime: &Runtime, re: &mut Result) {
+    *re = match runtime.consume_fuel() {
+        Ok(()) => Result(JValue::UNDEFINED, false),
+        Err(e) => Result(e, true),
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn is_terminating(runtime: &Runtime) -> bool {
+    runtime.is_terminating()
+}
+
 #[no_mangle]
 pub extern "C" fn new_target(runtime: &Runtime) -> JValue {
     return runtime.new_target;
 }
 
 #[no_mangle]
-pub extern "C" fn import_meta(runtime: &Runtime) -> JValue {
-    return runtime.import_meta;
+pub extern "C" fn import_meta(runtime: &Runtime, url: u32) -> JValue {
+    runtime.import_meta(runtime.get_string(StringID(url)))
+}
+
+#[no_mangle]
+pub extern "C" fn dynamic_import(
+    runtime: &Runtime,
+    args: *mut JValue,
+    argc: u32,
+    referrer: u32,
+) -> JValue {
+    let args = unsafe { std::slice::from_raw_parts(args, argc as usize) };
+    // the module runs on the stack above the arguments
+    let stack = unsafe { args.as_ptr().add(argc as usize) as *mut JValue };
+    let referrer = runtime.get_string(StringID(referrer));
+    let referrer = if referrer.is_empty() { None } else { Some(referrer) };
+    runtime.dynamic_import(args, stack, referrer)
 }
 
 pub unsafe fn spread(
@@ -324,6 +352,69 @@ pub unsafe fn extend_object(obj: JValue, target: JValue, runtime: &Runtime) {
     };
 }
 
+/// 7.3.25 CopyDataProperties into a new object,
+/// the own keys of excluded are not copied
+pub fn copy_data_properties(
+    from: JValue,
+    excluded: JValue,
+    ctx: JSContext,
+) -> std::result::Result<JValue, JValue> {
+    if from.is_undefined() || from.is_null() {
+        return Err(Error::TypeError(format!(
+            "Cannot destructure '{}' as it is {}.",
+            from.to_string(),
+            from.to_string()
+        ))
+        .into());
+    }
+
+    let obj = JObject::new();
+    let is_excluded = |key: PropKey| match excluded.as_object() {
+        Some(e) => e.has_owned_property(key),
+        None => false,
+    };
+
+    if let Some(s) = from.as_string() {
+        for (i, c) in s.as_str().chars().enumerate() {
+            let key = i.to_string().to_key(ctx.runtime);
+            if !is_excluded(key) {
+                obj.insert_property(
+                    key,
+                    JValue::create_string(c.to_string().into()),
+                    Default::default(),
+                );
+            }
+        }
+    } else if let Some(from) = from.as_object() {
+        if let Some(array) = from.as_array() {
+            let elements = array.clone();
+            for (i, (flag, value)) in elements.into_iter().enumerate() {
+                let key = i.to_string().to_key(ctx.runtime);
+                if flag.is_enumerable() && !is_excluded(key) {
+                    obj.insert_property(key, value, Default::default());
+                }
+            }
+        }
+
+        // getters may change the object while copying
+        let keys = from
+            .inner
+            .values
+            .iter()
+            .filter(|(key, cell)| cell.flag.is_enumerable() && !is_excluded(**key))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            // an own "__proto__" is copied as data, not as the prototype
+            let value = from.get_own_property(key, ctx)?;
+            obj.insert_own_property(key, value, Default::default());
+        }
+    }
+
+    Ok(obj.into())
+}
+
 pub unsafe fn create_template(id: u32, args: *mut JValue, argc: u32, tagged: bool) -> JValue {
     let args = std::slice::from_raw_parts_mut(args, argc as usize);
     let runtime = Runtime::current();
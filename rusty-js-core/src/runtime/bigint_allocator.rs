@@ -28,7 +28,7 @@ impl BigIntAllocator {
     pub fn alloc(&mut self) -> &'static mut JSBigInt {
         unsafe {
             if self.next.is_null() {
-                self.add_pages(self.pages.len());
+                self.add_pages(self.pages.len().max(1));
             }
 
             let next = self.next;
@@ -41,6 +41,17 @@ impl BigIntAllocator {
         }
     }
 
+    /// return true if the allocator has to grow on the next allocation
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.next.is_null()
+    }
+
+    /// the number of bytes acquired when the allocator grows
+    pub fn growth_size(&self) -> usize {
+        self.pages.len().max(1) * 128 * std::mem::size_of::<JSBigInt>()
+    }
+
     unsafe fn add_pages(&mut self, num: usize) {
         let mut ptr = std::alloc::alloc(Layout::array::<JSBigInt>(128 * num).unwrap())
             as *mut [JSBigInt; 128];
@@ -0,0 +1,189 @@
+use std::alloc::Layout;
+use std::ops;
+use std::sync::Arc;
+
+use super::{Runtime, DEFAULT_STACK_SIZE};
+use crate::error::Error;
+use crate::value::JValue;
+
+/// the ECMAScript globals installed on a new runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtins(u32);
+
+impl Builtins {
+    pub const NONE: Builtins = Builtins(0);
+    pub const OBJECT: Builtins = Builtins(0b00000001);
+    pub const NUMBER: Builtins = Builtins(0b00000010);
+    pub const ARRAY: Builtins = Builtins(0b00000100);
+    pub const MATH: Builtins = Builtins(0b00001000);
+    /// `Promise` and `queueMicrotask`
+    pub const PROMISE: Builtins = Builtins(0b00010000);
+    /// `setTimeout`, `setInterval` and their clear functions
+    pub const TIMERS: Builtins = Builtins(0b00100000);
+
+    pub const ALL: Builtins = Builtins(
+        Self::OBJECT.0
+            | Self::NUMBER.0
+            | Self::ARRAY.0
+            | Self::MATH.0
+            | Self::PROMISE.0
+            | Self::TIMERS.0,
+    );
+
+    pub fn contains(self, other: Builtins) -> bool {
+        return (self & other).0 == other.0;
+    }
+}
+
+impl Default for Builtins {
+    fn default() -> Self {
+        Builtins::ALL
+    }
+}
+
+impl ops::BitOr for Builtins {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        return Builtins(self.0 | rhs.0);
+    }
+}
+
+impl ops::BitAnd for Builtins {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        return Builtins(self.0 & rhs.0);
+    }
+}
+
+impl ops::Not for Builtins {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        return Builtins(!self.0 & Self::ALL.0);
+    }
+}
+
+/// options used to create a runtime
+#[derive(Debug, Clone)]
+pub struct RuntimeOptions {
+    /// number of values the stack can hold
+    pub stack_size: usize,
+    /// number of values the operational stack can hold
+    pub operational_stack_size: usize,
+    /// number of worker threads used for garbage collection, async tasks and jit compiling,
+    /// tasks are run on the calling thread if zero
+    pub worker_threads: usize,
+    /// execute global code in strict mode
+    pub strict_mode: bool,
+    /// compile hot functions with the llvm baseline tier
+    pub baseline_jit: bool,
+    /// the ECMAScript globals to install
+    pub builtins: Builtins,
+    /// timers use a clock that is only advanced by the event loop
+    /// or `Runtime::advance_clock`, no time passes while sleeping
+    pub virtual_clock: bool,
+}
+
+impl RuntimeOptions {
+    /// the stacks must hold at least one value and fit in memory
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        for (name, size) in [
+            ("stack size", self.stack_size),
+            ("operational stack size", self.operational_stack_size),
+        ] {
+            if size == 0 {
+                return Err(Error::RangeError(format!(
+                    "the {} must be greater than zero",
+                    name
+                )));
+            }
+            if Layout::array::<JValue>(size).is_err() {
+                return Err(Error::RangeError(format!("the {} is too large", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for RuntimeOptions {
+    fn default() -> Self {
+        Self {
+            stack_size: DEFAULT_STACK_SIZE,
+            operational_stack_size: DEFAULT_STACK_SIZE,
+            worker_threads: 3,
+            strict_mode: false,
+            baseline_jit: true,
+            builtins: Builtins::ALL,
+            virtual_clock: false,
+        }
+    }
+}
+
+/// builder for a runtime with custom options
+///
+/// ```ignore
+/// let runtime = RuntimeBuilder::new()
+///     .worker_threads(0)
+///     .baseline_jit(false)
+///     .builtins(Builtins::OBJECT | Builtins::MATH)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeBuilder {
+    options: RuntimeOptions,
+}
+
+impl RuntimeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: RuntimeOptions) -> Self {
+        Self { options }
+    }
+
+    /// the size is checked by `build`
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.options.stack_size = size;
+        self
+    }
+
+    /// the size is checked by `build`
+    pub fn operational_stack_size(mut self, size: usize) -> Self {
+        self.options.operational_stack_size = size;
+        self
+    }
+
+    pub fn worker_threads(mut self, num: usize) -> Self {
+        self.options.worker_threads = num;
+        self
+    }
+
+    pub fn strict_mode(mut self, strict: bool) -> Self {
+        self.options.strict_mode = strict;
+        self
+    }
+
+    pub fn baseline_jit(mut self, enable: bool) -> Self {
+        self.options.baseline_jit = enable;
+        self
+    }
+
+    pub fn builtins(mut self, builtins: Builtins) -> Self {
+        self.options.builtins = builtins;
+        self
+    }
+
+    pub fn virtual_clock(mut self, enable: bool) -> Self {
+        self.options.virtual_clock = enable;
+        self
+    }
+
+    pub fn options(&self) -> &RuntimeOptions {
+        &self.options
+    }
+
+    /// return a RangeError if a stack size is zero or too large
+    pub fn build(self) -> Result<Arc<Runtime>, Error> {
+        Runtime::with_options(self.options)
+    }
+}
@@ -0,0 +1,703 @@
+// the binary code cache of compiled scripts.
+//
+// layout of a cache entry:
+//
+// - magic, format version, engine version and source hash
+// - tables of field names, variable names, strings, constants, regexs and templates
+// - number of functions and classes
+// - the script header and its bytecodes
+// - one record for each function and class referenced by the script
+//
+// ids stored in bytecodes are indexes into the tables of the entry,
+// they are remapped to the ids of the loading runtime.
+//
+// modules are not cached: their bytecodes refer to the bindings of the modules
+// they import, which are only known once the module graph is linked by the loading runtime.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use num_traits::ToPrimitive;
+
+use super::{Runtime, Script};
+use crate::bultins::class::JSClass;
+use crate::bultins::function::JSFunction;
+use crate::bultins::regex::RegExp;
+use crate::bultins::strings::Template;
+use crate::bytecodes::cache::{Decoder, Encoder};
+use crate::bytecodes::OpCode;
+use crate::error::Error;
+use crate::runtime::{ClassID, ConstID, FuncID, RegexID, StringID, TemplateID};
+use crate::value::JValue;
+
+const MAGIC: &[u8; 8] = b"RJSCACHE";
+
+/// bump when the layout of the cache or the opcodes changes
+const FORMAT_VERSION: u32 = 5;
+
+pub(super) const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const RECORD_FUNCTION: u8 = 0;
+const RECORD_CLASS: u8 = 1;
+
+const CONST_NUMBER: u8 = 0;
+const CONST_BIGINT: u8 = 1;
+
+/// a constant of an entry, bigints are allocated once the entry is registered
+enum Constant {
+    Number(f64),
+    BigInt(i128),
+}
+
+/// the tables and records of an entry, registered on the runtime once the whole entry
+/// is decoded so that a corrupted entry leaves nothing behind.
+///
+/// names and strings are interned while decoding, the interners ignore duplicates.
+#[derive(Default)]
+pub(super) struct Staged {
+    constants: Vec<Constant>,
+    regexs: Vec<RegExp>,
+    templates: Vec<Template>,
+    functions: Vec<Option<JSFunction>>,
+    classes: Vec<Option<ClassRecord>>,
+}
+
+impl Runtime {
+    /// serialize a compiled script into the code cache format.
+    ///
+    /// the cache can be loaded by any runtime of the same engine version
+    /// with `load_cached_script`, skipping the parser and bytecode builder.
+    pub fn serialize_script(&self, script: &Script) -> Result<Vec<u8>, Error> {
+        let mut enc = Encoder::default();
+
+        enc.write_str(&script.filename);
+        enc.write_u8(script.strict as u8);
+        enc.write_u32(script.op_stack as u32);
+        enc.write_u32(script.globals.len() as u32);
+        for g in &script.globals {
+            enc.write_str(g);
+        }
+        encode_codes(&mut enc, &script.bytecodes);
+
+        self.encode_records(&mut enc);
+
+        // the tables are complete once every record is encoded
+        let mut out = Encoder::default();
+        out.buf.extend_from_slice(MAGIC);
+        out.write_u32(FORMAT_VERSION);
+        out.write_str(ENGINE_VERSION);
+        out.write_u64(script.source_hash);
+
+        self.write_tables(&mut out, &enc)?;
+
+        out.buf.extend_from_slice(&enc.buf);
+        Ok(out.buf)
+    }
+
+    /// map the registered functions to their ids
+    pub(super) fn function_ids(&self) -> HashMap<*const JSFunction, u32> {
+        let mut function_ids = HashMap::new();
+        for (i, f) in self.functions.iter().enumerate() {
+            if let Some(f) = f {
+                function_ids.insert(Arc::as_ptr(f), i as u32);
+            }
+        }
+        function_ids
+    }
+
+    /// write a record for every function and class recorded by the encoder
+    pub(super) fn encode_records(&self, enc: &mut Encoder) {
+        // functions bound to classes are stored by reference
+        let function_ids = self.function_ids();
+
+        // encoding a function or class may discover new ones
+        let mut next_function = 0;
+        let mut next_class = 0;
+        while next_function < enc.functions.len() || next_class < enc.classes.len() {
+            if next_function < enc.functions.len() {
+                let id = enc.functions.ids[next_function];
+                let func = self.get_function(FuncID(id)).unwrap();
+
+                enc.write_u8(RECORD_FUNCTION);
+                enc.write_u32(next_function as u32);
+                encode_function(enc, &func);
+                next_function += 1;
+            } else {
+                let id = enc.classes.ids[next_class];
+                let class = self.get_class(ClassID(id));
+
+                enc.write_u8(RECORD_CLASS);
+                enc.write_u32(next_class as u32);
+                encode_class(enc, &class, &function_ids);
+                next_class += 1;
+            }
+        }
+    }
+
+    /// write the tables of the ids recorded by the encoder
+    pub(super) fn write_tables(&self, out: &mut Encoder, enc: &Encoder) -> Result<(), Error> {
+        out.write_u32(enc.field_names.len() as u32);
+        for id in &enc.field_names.ids {
+            out.write_str(self.get_field_name(*id));
+        }
+
+        out.write_u32(enc.var_names.len() as u32);
+        for id in &enc.var_names.ids {
+            out.write_str(self.get_dynamic_var_name(*id));
+        }
+
+        out.write_u32(enc.strings.len() as u32);
+        for id in &enc.strings.ids {
+            out.write_str(self.get_string(StringID(*id)));
+        }
+
+        out.write_u32(enc.constants.len() as u32);
+        for id in &enc.constants.ids {
+            let c = self.get_unamed_constant(ConstID(*id));
+            if let Some(b) = c.as_bigint() {
+                let v = b.value.to_i128().ok_or_else(|| {
+                    Error::RuntimeError("cannot serialize bigint larger then i128".to_string())
+                })?;
+                out.write_u8(CONST_BIGINT);
+                out.buf.extend_from_slice(&v.to_le_bytes());
+            } else {
+                out.write_u8(CONST_NUMBER);
+                out.write_u64(c.as_number().unwrap_or(f64::NAN).to_bits());
+            }
+        }
+
+        out.write_u32(enc.regexs.len() as u32);
+        for id in &enc.regexs.ids {
+            let r = self.get_regex(RegexID(*id));
+            out.write_str(&r.source);
+            out.write_str(&r.flags.to_flags_string());
+        }
+
+        out.write_u32(enc.templates.len() as u32);
+        for id in &enc.templates.ids {
+            let t = self.get_template(TemplateID(*id));
+            out.write_u8(t.tagged as u8);
+            out.write_u32(t.strings.len() as u32);
+            for s in &t.strings {
+                out.write_str(s);
+            }
+        }
+
+        out.write_u32(enc.functions.len() as u32);
+        out.write_u32(enc.classes.len() as u32);
+        Ok(())
+    }
+
+    /// load a script from the code cache.
+    ///
+    /// the cache is rejected with `Error::InvalidCodeCache` if it is created
+    /// by another engine version or from a different source text.
+    pub fn load_cached_script(
+        self: &Arc<Self>,
+        source: &str,
+        cache: &[u8],
+    ) -> Result<Script, Error> {
+        let invalid = || Error::InvalidCodeCache("the code cache is corrupted".to_string());
+
+        if cache.len() < MAGIC.len() || &cache[..MAGIC.len()] != MAGIC {
+            return Err(Error::InvalidCodeCache("not a code cache".to_string()));
+        }
+
+        let mut dec = Decoder::new(&cache[MAGIC.len()..]);
+
+        let format = dec.read_u32().ok_or_else(invalid)?;
+        let engine = dec.read_str().ok_or_else(invalid)?;
+        if format != FORMAT_VERSION || engine != ENGINE_VERSION {
+            return Err(Error::InvalidCodeCache(format!(
+                "the code cache is created by engine version {}, expected {}",
+                engine, ENGINE_VERSION
+            )));
+        }
+
+        let hash = dec.read_u64().ok_or_else(invalid)?;
+        if hash != Script::hash_source(source) {
+            return Err(Error::InvalidCodeCache(
+                "the code cache is created from a different source".to_string(),
+            ));
+        }
+
+        let mut staged = self.load_tables(&mut dec).ok_or_else(invalid)?;
+
+        let filename = dec.read_str().ok_or_else(invalid)?.to_string();
+        let strict = dec.read_u8().ok_or_else(invalid)? != 0;
+        let op_stack = dec.read_u32().ok_or_else(invalid)? as usize;
+
+        let globals_len = dec.read_u32().ok_or_else(invalid)?;
+        let mut globals = Vec::new();
+        for _ in 0..globals_len {
+            globals.push(dec.read_str().ok_or_else(invalid)?.to_string());
+        }
+
+        let bytecodes = decode_codes(&mut dec).ok_or_else(invalid)?;
+
+        self.load_records(&mut dec, &mut staged)
+            .ok_or_else(invalid)?;
+        self.register_staged(staged, &dec);
+
+        Ok(Script::new(
+            self, &filename, hash, bytecodes, op_stack, strict, globals,
+        ))
+    }
+
+    /// read the tables of a cache entry and record the ids they are registered under
+    pub(super) fn load_tables(&self, dec: &mut Decoder) -> Option<Staged> {
+        let mut staged = Staged::default();
+
+        for _ in 0..dec.read_u32()? {
+            let name = dec.read_str()?;
+            dec.field_names.push(self.register_field_name(name));
+        }
+
+        for _ in 0..dec.read_u32()? {
+            let name = dec.read_str()?;
+            let id = self.dynamic_var_names.write().get_or_intern(name) as u32;
+            dec.var_names.push(id);
+        }
+
+        for _ in 0..dec.read_u32()? {
+            let s = dec.read_str()?;
+            dec.strings.push(self.to_mut().register_string(s).0);
+        }
+
+        // the other tables are appended to, the ids follow the current ones
+        for _ in 0..dec.read_u32()? {
+            let c = match dec.read_u8()? {
+                CONST_NUMBER => Constant::Number(f64::from_bits(dec.read_u64()?)),
+                CONST_BIGINT => {
+                    let lo = dec.read_u64()? as u128;
+                    let hi = dec.read_u64()? as u128;
+                    Constant::BigInt(((hi << 64) | lo) as i128)
+                }
+                _ => return None,
+            };
+            dec.constants
+                .push((self.constants.len() + staged.constants.len()) as u32);
+            staged.constants.push(c);
+        }
+
+        for _ in 0..dec.read_u32()? {
+            let source = dec.read_str()?;
+            let flags = dec.read_str()?;
+            dec.regexs
+                .push((self.regexs.len() + staged.regexs.len()) as u32);
+            staged.regexs.push(RegExp::with_flags(source, flags).ok()?);
+        }
+
+        for _ in 0..dec.read_u32()? {
+            let tagged = dec.read_u8()? != 0;
+            let mut strings = Vec::new();
+            for _ in 0..dec.read_u32()? {
+                strings.push(dec.read_str()?.to_string());
+            }
+            dec.templates
+                .push((self.templates.len() + staged.templates.len()) as u32);
+            staged.templates.push(Template { strings, tagged });
+        }
+
+        // the ids are reserved so that bytecodes can refer to them before they are loaded,
+        // every record takes more than a byte
+        let functions = dec.read_u32()? as usize;
+        if functions > dec.remaining() {
+            return None;
+        }
+        for i in 0..functions {
+            dec.functions.push((self.functions.len() + i) as u32);
+            staged.functions.push(None);
+        }
+
+        let classes = dec.read_u32()? as usize;
+        if classes > dec.remaining() {
+            return None;
+        }
+        for i in 0..classes {
+            dec.classes.push((self.classes.len() + i) as u32);
+            staged.classes.push(None);
+        }
+
+        Some(staged)
+    }
+
+    /// read the function and class records, every reserved id must be given a record
+    pub(super) fn load_records(&self, dec: &mut Decoder, staged: &mut Staged) -> Option<()> {
+        while !dec.is_end() {
+            match dec.read_u8()? {
+                RECORD_FUNCTION => {
+                    let idx = dec.read_u32()? as usize;
+                    let func = decode_function(dec)?;
+                    *staged.functions.get_mut(idx)? = Some(func);
+                }
+                RECORD_CLASS => {
+                    let idx = dec.read_u32()? as usize;
+                    let record = ClassRecord::decode(dec)?;
+                    *staged.classes.get_mut(idx)? = Some(record);
+                }
+                _ => return None,
+            }
+        }
+
+        if staged.functions.iter().any(|f| f.is_none()) {
+            return None;
+        }
+        // classes refer to the functions of the entry
+        for class in &staged.classes {
+            let class = class.as_ref()?;
+            if class
+                .functions()
+                .any(|i| i as usize >= staged.functions.len())
+            {
+                return None;
+            }
+        }
+
+        Some(())
+    }
+
+    /// register the tables and records of a decoded entry under the ids recorded by the decoder
+    pub(super) fn register_staged(&self, staged: Staged, dec: &Decoder) {
+        let rt = self.to_mut();
+
+        for c in staged.constants {
+            let value = match c {
+                Constant::Number(n) => JValue::create_number(n),
+                Constant::BigInt(v) => {
+                    let b = self.allocate_bigint();
+                    b.set_value(v);
+                    JValue::create_bigint_allocated(b)
+                }
+            };
+            rt.unamed_constant(value);
+        }
+
+        for r in staged.regexs {
+            rt.regexs.push(Box::new(r));
+        }
+
+        for t in staged.templates {
+            self.register_template(t);
+        }
+
+        for f in staged.functions.into_iter().flatten() {
+            rt.functions.push(Some(Arc::new(f)));
+        }
+
+        // the classes are built once every function is registered
+        for record in staged.classes.into_iter().flatten() {
+            let class = record.build(self, dec);
+            rt.classes.push(Some(Arc::new(class)));
+        }
+    }
+}
+
+fn encode_codes(enc: &mut Encoder, codes: &[OpCode]) {
+    enc.write_u32(codes.len() as u32);
+    for c in codes {
+        c.encode(enc);
+    }
+}
+
+fn decode_codes(dec: &mut Decoder) -> Option<Vec<OpCode>> {
+    let len = dec.read_u32()?;
+    // every opcode takes at least a byte
+    let mut codes = Vec::with_capacity((len as usize).min(dec.remaining()));
+    for _ in 0..len {
+        codes.push(OpCode::decode(dec)?);
+    }
+
+    // the code after the target of a jump is run next, it must be inside the codes
+    for c in &codes {
+        match c {
+            OpCode::Jump { line, .. }
+            | OpCode::JumpIfTrue { line, .. }
+            | OpCode::JumpIfFalse { line, .. }
+            | OpCode::EnterTry { line, .. } => {
+                if *line as usize >= codes.len() {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(codes)
+}
+
+fn encode_function(enc: &mut Encoder, func: &JSFunction) {
+    enc.write_u8(func.is_async as u8);
+    enc.write_u8(func.is_generator as u8);
+    enc.write_u16(func.var_count);
+    enc.write_u16(func.args_len);
+    enc.write_u32(func.largest_stack_offset);
+    match func.capture_stack_size {
+        Some(n) => {
+            enc.write_u8(1);
+            enc.write_u16(n);
+        }
+        None => enc.write_u8(0),
+    }
+    encode_codes(enc, &func.bytecodes);
+}
+
+fn decode_function(dec: &mut Decoder) -> Option<JSFunction> {
+    let is_async = dec.read_u8()? != 0;
+    let is_generator = dec.read_u8()? != 0;
+    let var_count = dec.read_u16()?;
+    let args_len = dec.read_u16()?;
+    let largest_stack_offset = dec.read_u32()?;
+    let capture_stack_size = match dec.read_u8()? {
+        0 => None,
+        _ => Some(dec.read_u16()?),
+    };
+    let codes = decode_codes(dec)?;
+
+    // same as the bytecode builder
+    let clousure = if !is_async && !is_generator {
+        Some(crate::interpreter::clousure::Clousure::create(&codes))
+    } else {
+        None
+    };
+
+    Some(JSFunction {
+        is_async,
+        is_generator,
+        var_count,
+        args_len,
+        largest_stack_offset,
+        call_count: 0,
+        capture_stack_size,
+        bytecodes: Arc::new(codes),
+        baseline_clousure: clousure,
+        baseline_jit: None,
+    })
+}
+
+fn encode_class(
+    enc: &mut Encoder,
+    class: &JSClass,
+    function_ids: &HashMap<*const JSFunction, u32>,
+) {
+    let func = |enc: &mut Encoder, f: &Option<Arc<JSFunction>>| match f {
+        Some(f) => {
+            enc.write_u8(1);
+            let i = enc.functions.local(function_ids[&Arc::as_ptr(f)]);
+            enc.write_u32(i);
+        }
+        None => enc.write_u8(0),
+    };
+
+    enc.write_str(&class.name);
+    func(enc, &class.constructor);
+
+    for methods in [&class.methods, &class.static_methods] {
+        enc.write_u32(methods.len() as u32);
+        for (name, f) in methods.iter() {
+            enc.write_field_name(*name);
+            func(enc, &Some(f.clone()));
+        }
+    }
+
+    for get_setters in [&class.get_setters, &class.static_get_setters] {
+        enc.write_u32(get_setters.len() as u32);
+        for (name, (getter, setter)) in get_setters.iter() {
+            enc.write_field_name(*name);
+            func(enc, getter);
+            func(enc, setter);
+        }
+    }
+
+    for props in [&class.props, &class.static_props] {
+        enc.write_u32(props.len() as u32);
+        for name in props {
+            enc.write_field_name(*name);
+        }
+    }
+
+    for methods in [&class.private_methods, &class.static_private_methods] {
+        enc.write_u32(methods.len() as u32);
+        for (name, f) in methods.iter() {
+            enc.write_field_name(*name);
+            func(enc, &Some(f.clone()));
+        }
+    }
+
+    for get_setters in [
+        &class.private_get_setters,
+        &class.static_private_get_setters,
+    ] {
+        enc.write_u32(get_setters.len() as u32);
+        for (name, (getter, setter)) in get_setters.iter() {
+            enc.write_field_name(*name);
+            func(enc, getter);
+            func(enc, setter);
+        }
+    }
+
+    func(enc, &class.field_initializer);
+}
+
+/// a class with local function indexes,
+/// built after every function is loaded
+struct ClassRecord {
+    name: String,
+    constructor: Option<u32>,
+    methods: [Vec<(u32, u32)>; 2],
+    get_setters: [Vec<(u32, Option<u32>, Option<u32>)>; 2],
+    props: [Vec<u32>; 2],
+    private_methods: [Vec<(u32, u32)>; 2],
+    private_get_setters: [Vec<(u32, Option<u32>, Option<u32>)>; 2],
+    field_initializer: Option<u32>,
+}
+
+impl ClassRecord {
+    fn decode(dec: &mut Decoder) -> Option<Self> {
+        fn func(dec: &mut Decoder) -> Option<Option<u32>> {
+            match dec.read_u8()? {
+                0 => Some(None),
+                _ => Some(Some(dec.read_u32()?)),
+            }
+        }
+
+        let name = dec.read_str()?.to_string();
+        let constructor = func(dec)?;
+
+        let mut methods = [Vec::new(), Vec::new()];
+        for m in &mut methods {
+            for _ in 0..dec.read_u32()? {
+                let name = dec.read_field_name()?;
+                m.push((name, func(dec)??));
+            }
+        }
+
+        let mut get_setters = [Vec::new(), Vec::new()];
+        for g in &mut get_setters {
+            for _ in 0..dec.read_u32()? {
+                let name = dec.read_field_name()?;
+                g.push((name, func(dec)?, func(dec)?));
+            }
+        }
+
+        let mut props = [Vec::new(), Vec::new()];
+        for p in &mut props {
+            for _ in 0..dec.read_u32()? {
+                p.push(dec.read_field_name()?);
+            }
+        }
+
+        let mut private_methods = [Vec::new(), Vec::new()];
+        for m in &mut private_methods {
+            for _ in 0..dec.read_u32()? {
+                let name = dec.read_field_name()?;
+                m.push((name, func(dec)??));
+            }
+        }
+
+        let mut private_get_setters = [Vec::new(), Vec::new()];
+        for g in &mut private_get_setters {
+            for _ in 0..dec.read_u32()? {
+                let name = dec.read_field_name()?;
+                g.push((name, func(dec)?, func(dec)?));
+            }
+        }
+
+        let field_initializer = func(dec)?;
+
+        Some(Self {
+            name,
+            constructor,
+            methods,
+            get_setters,
+            props,
+            private_methods,
+            private_get_setters,
+            field_initializer,
+        })
+    }
+
+    /// the indexes of the functions the class refers to
+    fn functions(&self) -> Vec<u32> {
+        let mut functions: Vec<u32> = self
+            .constructor
+            .into_iter()
+            .chain(self.field_initializer)
+            .collect();
+        for methods in self.methods.iter().chain(&self.private_methods) {
+            functions.extend(methods.iter().map(|(_, f)| *f));
+        }
+        for get_setters in self.get_setters.iter().chain(&self.private_get_setters) {
+            for (_, getter, setter) in get_setters {
+                functions.extend(getter.iter().chain(setter));
+            }
+        }
+        functions
+    }
+
+    /// the functions are registered and checked by `load_records`
+    fn build(self, runtime: &Runtime, dec: &Decoder) -> JSClass {
+        let func = |idx: u32| -> Arc<JSFunction> {
+            let id = dec.functions[idx as usize];
+            runtime.get_function(FuncID(id)).unwrap()
+        };
+
+        let mut class = JSClass::new(self.name);
+
+        if let Some(c) = self.constructor {
+            class.constructor = Some(func(c));
+        }
+
+        let [methods, static_methods] = self.methods;
+        for (name, f) in methods {
+            class.methods.insert(name, func(f));
+        }
+        for (name, f) in static_methods {
+            class.static_methods.insert(name, func(f));
+        }
+
+        let [get_setters, static_get_setters] = self.get_setters;
+        for (name, getter, setter) in get_setters {
+            let getter = getter.map(func);
+            let setter = setter.map(func);
+            class.get_setters.insert(name, (getter, setter));
+        }
+        for (name, getter, setter) in static_get_setters {
+            let getter = getter.map(func);
+            let setter = setter.map(func);
+            class.static_get_setters.insert(name, (getter, setter));
+        }
+
+        let [props, static_props] = self.props;
+        class.props = props;
+        class.static_props = static_props;
+
+        let [private_methods, static_private_methods] = self.private_methods;
+        for (name, f) in private_methods {
+            class.private_methods.insert(name, func(f));
+        }
+        for (name, f) in static_private_methods {
+            class.static_private_methods.insert(name, func(f));
+        }
+
+        let [private_get_setters, static_private_get_setters] = self.private_get_setters;
+        for (get_setters, records) in [
+            (&mut class.private_get_setters, private_get_setters),
+            (
+                &mut class.static_private_get_setters,
+                static_private_get_setters,
+            ),
+        ] {
+            for (name, getter, setter) in records {
+                let getter = getter.map(func);
+                let setter = setter.map(func);
+                get_setters.insert(name, (getter, setter));
+            }
+        }
+
+        if let Some(f) = self.field_initializer {
+            class.field_initializer = Some(func(f));
+        }
+
+        class
+    }
+}
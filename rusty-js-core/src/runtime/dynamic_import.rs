@@ -0,0 +1,82 @@
+// import() called from running code,
+// the module graph is fetched by a task and the module is evaluated by a microtask
+
+use super::{AsyncId, ImportAssertion, Microtask, Runtime};
+use crate::bultins::object::JObject;
+use crate::bultins::promise;
+use crate::value::JValue;
+use crate::JSContext;
+
+/// an import() waiting for its module graph
+pub(crate) struct ModuleFetch {
+    pub(crate) task: AsyncId,
+    pub(crate) promise: JObject,
+    pub(crate) specifier: String,
+    pub(crate) asserts: ImportAssertion,
+}
+
+impl Runtime {
+    /// 13.3.10.1 EvaluateImportCall,
+    /// the stack points above the arguments of the call
+    pub(crate) fn dynamic_import(
+        &self,
+        args: &[JValue],
+        stack: *mut JValue,
+        referrer: Option<&str>,
+    ) -> JValue {
+        let ctx = JSContext {
+            stack,
+            runtime: self,
+        };
+        let specifier = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+        let options = args.get(1).copied().unwrap_or(JValue::UNDEFINED);
+
+        let promise = promise::new_promise(self);
+
+        // the options may run getters
+        self.user_own_value(promise.into());
+        let asserts = ImportAssertion::from_options(options, ctx);
+        self.user_drop_value(promise.into());
+
+        let asserts = match asserts {
+            Ok(asserts) => asserts,
+            Err(e) => {
+                promise::reject_promise(self, promise, e);
+                return promise.into();
+            }
+        };
+        let specifier = match self.resolve_specifier(&specifier.to_string(), referrer) {
+            Ok(specifier) => specifier,
+            Err(e) => {
+                promise::reject_promise(self, promise, Runtime::value_from_error(e));
+                return promise.into();
+            }
+        };
+
+        let key = asserts.cache_key(&specifier);
+        if self.module_map.contains_key(&key) || self.module_sources.contains_key(&key) {
+            self.enqueue_microtask(Microtask::EvaluateModule {
+                promise,
+                specifier,
+                asserts,
+            });
+            return promise.into();
+        }
+
+        let fetch = self
+            .arc()
+            .fetch_module_graph(vec![(specifier.clone(), asserts.clone())]);
+        let task = self.spawn_task(async move {
+            fetch.await.map_err(Runtime::value_from_error)?;
+            Ok(JValue::UNDEFINED)
+        });
+        self.to_mut().module_fetches.push(ModuleFetch {
+            task,
+            promise,
+            specifier,
+            asserts,
+        });
+
+        promise.into()
+    }
+}
@@ -0,0 +1,258 @@
+// timers and the host event loop,
+// the loop runs until there are no timers, tasks or microtasks left
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use super::Runtime;
+use crate::error::Error;
+use crate::value::JValue;
+
+/// the time source of the timers
+pub(crate) enum Clock {
+    Real(Instant),
+    /// only advanced by the event loop or `Runtime::advance_clock`
+    Virtual(Duration),
+}
+
+impl Clock {
+    fn now(&self) -> Duration {
+        match self {
+            Clock::Real(start) => start.elapsed(),
+            Clock::Virtual(now) => *now,
+        }
+    }
+}
+
+struct Timer {
+    id: u32,
+    deadline: Duration,
+    interval: Option<Duration>,
+    callback: JValue,
+    args: Vec<JValue>,
+}
+
+#[derive(Default)]
+pub(crate) struct TimerQueue {
+    timers: Vec<Timer>,
+    next_id: u32,
+    /// the interval being run and whether it was cleared by its callback
+    running: Option<(u32, bool)>,
+}
+
+impl TimerQueue {
+    /// the timer that fires next, timers with the same deadline fire in order of creation
+    fn next(&self) -> Option<usize> {
+        let mut next: Option<usize> = None;
+        for (i, t) in self.timers.iter().enumerate() {
+            match next {
+                Some(n) if self.timers[n].deadline <= t.deadline => {}
+                _ => next = Some(i),
+            }
+        }
+        next
+    }
+
+    fn next_deadline(&self) -> Option<Duration> {
+        self.next().map(|i| self.timers[i].deadline)
+    }
+
+    pub(crate) unsafe fn trace(&self) {
+        for t in &self.timers {
+            t.callback.trace();
+            t.args.iter().for_each(|v| v.trace());
+        }
+    }
+}
+
+impl Runtime {
+    /// the time elapsed on the clock of the runtime
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// register a timer, return the timer id
+    pub(crate) fn set_timer(
+        &self,
+        callback: JValue,
+        args: &[JValue],
+        delay: Duration,
+        repeat: bool,
+    ) -> u32 {
+        let queue = &mut self.to_mut().timers;
+        queue.next_id += 1;
+
+        let id = queue.next_id;
+        queue.timers.push(Timer {
+            id,
+            deadline: self.now() + delay,
+            interval: if repeat { Some(delay) } else { None },
+            callback,
+            args: args.to_vec(),
+        });
+        id
+    }
+
+    pub(crate) fn clear_timer(&self, id: u32) {
+        let queue = &mut self.to_mut().timers;
+        queue.timers.retain(|t| t.id != id);
+
+        if let Some((running, cleared)) = &mut queue.running {
+            if *running == id {
+                *cleared = true;
+            }
+        }
+    }
+
+    /// fire the timers whose deadline has passed,
+    /// a microtask checkpoint is performed after each callback.
+    ///
+    /// only the timers due on entry are fired, an interval queued again
+    /// by this call waits for the next turn of the event loop
+    fn run_due_timers(&self) -> Result<(), Error> {
+        let now = self.now();
+        // timers with the same deadline fire in order of creation
+        let mut due: Vec<(Duration, usize, u32)> = self
+            .timers
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.deadline <= now)
+            .map(|(i, t)| (t.deadline, i, t.id))
+            .collect();
+        due.sort();
+
+        for (_, _, id) in due {
+            // the timer may be cleared by an earlier callback
+            let i = match self.timers.timers.iter().position(|t| t.id == id) {
+                Some(i) => i,
+                None => continue,
+            };
+
+            let timer = self.to_mut().timers.timers.remove(i);
+
+            // keep the callback alive while it runs
+            self.user_own_value(timer.callback);
+            timer.args.iter().for_each(|v| self.user_own_value(*v));
+            self.to_mut().timers.running = Some((timer.id, false));
+
+            let re = self.call_from_host(|ctx| {
+                timer
+                    .callback
+                    .call_with_context(JValue::UNDEFINED, &timer.args, ctx)
+            });
+
+            let cleared = match self.to_mut().timers.running.take() {
+                Some((_, cleared)) => cleared,
+                None => false,
+            };
+            self.user_drop_value(timer.callback);
+            timer.args.iter().for_each(|v| self.user_drop_value(*v));
+
+            if let Some(interval) = timer.interval {
+                if !cleared {
+                    self.to_mut().timers.timers.push(Timer {
+                        deadline: self.now() + interval,
+                        ..timer
+                    });
+                }
+            }
+
+            re?;
+        }
+        Ok(())
+    }
+
+    /// move the virtual clock forward, firing the timers on the way
+    pub fn advance_clock(&self, by: Duration) -> Result<(), Error> {
+        let target = match self.clock {
+            Clock::Virtual(now) => now + by,
+            Clock::Real(_) => {
+                return Err(Error::RuntimeError(
+                    "the runtime does not use a virtual clock".to_string(),
+                ))
+            }
+        };
+
+        loop {
+            self.run_microtasks()?;
+
+            match self.timers.next_deadline() {
+                Some(deadline) if deadline <= target => {
+                    if deadline > self.now() {
+                        self.to_mut().clock = Clock::Virtual(deadline);
+                    }
+                    self.run_due_timers()?;
+                }
+                _ => break,
+            }
+        }
+
+        self.to_mut().clock = Clock::Virtual(target);
+        Ok(())
+    }
+
+    /// run the timers, tasks and microtasks until there is nothing left to do.
+    ///
+    /// the loop can be awaited inside an existing tokio runtime,
+    /// without one the thread sleeps until the next timer is due.
+    /// with a virtual clock the time jumps to the next timer instead of sleeping.
+    pub async fn run_event_loop(&self) -> Result<(), Error> {
+        let mut sleep = None;
+        std::future::poll_fn(|cx| self.with_attached(|| self.poll_event_loop(cx, &mut sleep))).await
+    }
+
+    fn poll_event_loop(
+        &self,
+        cx: &mut Context,
+        sleep: &mut Option<Pin<Box<tokio::time::Sleep>>>,
+    ) -> Poll<Result<(), Error>> {
+        loop {
+            if let Err(e) = self.run_microtasks() {
+                return Poll::Ready(Err(e));
+            }
+
+            let (completed, running) = self.poll_tasks(cx);
+
+            // settle the promises waiting for the completed tasks
+            if completed > 0 || self.has_pending_microtasks() {
+                continue;
+            }
+
+            let deadline = match self.timers.next_deadline() {
+                Some(d) => d,
+                None if running > 0 => return Poll::Pending,
+                None => {
+                    // every rejection left is reported as unhandled by now
+                    self.to_mut().unhandled_rejections.clear();
+                    return Poll::Ready(Ok(()));
+                }
+            };
+
+            let now = self.now();
+            if deadline > now {
+                match self.clock {
+                    Clock::Virtual(_) => self.to_mut().clock = Clock::Virtual(deadline),
+                    Clock::Real(start) => {
+                        if tokio::runtime::Handle::try_current().is_ok() {
+                            let s = sleep.insert(Box::pin(tokio::time::sleep_until(
+                                (start + deadline).into(),
+                            )));
+                            if s.as_mut().poll(cx).is_pending() {
+                                return Poll::Pending;
+                            }
+                        } else {
+                            std::thread::sleep(deadline - now);
+                        }
+                    }
+                }
+            }
+
+            if let Err(e) = self.run_due_timers() {
+                return Poll::Ready(Err(e));
+            }
+        }
+    }
+}
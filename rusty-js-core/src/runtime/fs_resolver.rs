@@ -0,0 +1,277 @@
+// a resolver loading modules from the filesystem,
+// specifiers are resolved the way node resolves ES modules
+
+use std::path::{Path, PathBuf};
+
+use super::{ImportAssertion, ImportResolver};
+use crate::utils::json::{parse_json, Json};
+
+/// the extensions probed when a path is imported without one
+const EXTENSIONS: &[&str] = &["js", "mjs", "json", "ts", "mts"];
+
+/// resolves relative, absolute and bare specifiers on the filesystem,
+/// bare specifiers are looked up in the `node_modules` directories
+pub struct FsResolver {
+    root: PathBuf,
+    conditions: Vec<String>,
+}
+
+impl FsResolver {
+    /// specifiers imported without a referrer are resolved against the root
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            conditions: vec!["import".to_string(), "node".to_string()],
+        }
+    }
+
+    /// the conditions matched against the `exports` of a package,
+    /// `default` is always matched
+    pub fn with_conditions(mut self, conditions: &[&str]) -> Self {
+        self.conditions = conditions.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    fn resolve_path(&self, specifier: &str, referrer: Option<&str>) -> Result<PathBuf, String> {
+        let base = match referrer {
+            Some(referrer) => {
+                let referrer = self.root.join(url_to_path(referrer));
+                referrer
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|| self.root.clone())
+            }
+            None => self.root.clone(),
+        };
+
+        if specifier.starts_with("file://") {
+            return load_file_or_directory(Path::new(url_to_path(specifier)));
+        }
+        if specifier.starts_with('/')
+            || specifier.starts_with("./")
+            || specifier.starts_with("../")
+            || specifier == "."
+            || specifier == ".."
+        {
+            return load_file_or_directory(&base.join(specifier));
+        }
+        self.load_package(specifier, &base)
+    }
+
+    /// look for the package in the node_modules of every ancestor directory
+    fn load_package(&self, specifier: &str, base: &Path) -> Result<PathBuf, String> {
+        let (name, subpath) = split_package_name(specifier)
+            .filter(|(_, subpath)| !subpath.split('/').any(|s| s == "." || s == ".."))
+            .ok_or_else(|| format!("invalid module specifier {}", specifier))?;
+
+        for dir in base.ancestors() {
+            let package = dir.join("node_modules").join(name);
+            if !package.is_dir() {
+                continue;
+            }
+            let manifest = read_manifest(&package)?;
+
+            if let Some(exports) = manifest.as_ref().and_then(|m| m.get("exports")) {
+                let target = self.resolve_exports(exports, &format!(".{}", subpath));
+                return match target.map(|target| package.join(target)) {
+                    Some(path) if path.is_file() => Ok(path),
+                    Some(path) => Err(format!(
+                        "cannot find module {} exported from package {}",
+                        path.display(),
+                        name
+                    )),
+                    None => Err(format!(
+                        "package path .{} is not exported from package {}",
+                        subpath, name
+                    )),
+                };
+            }
+
+            if !subpath.is_empty() {
+                return load_file_or_directory(&package.join(&subpath[1..]));
+            }
+            if let Some(manifest) = &manifest {
+                for field in ["module", "main"] {
+                    if let Some(Json::Str(main)) = manifest.get(field) {
+                        if let Ok(path) = load_file_or_directory(&package.join(main)) {
+                            return Ok(path);
+                        }
+                    }
+                }
+            }
+            return load_index(&package)
+                .ok_or_else(|| format!("cannot find the entry of package {}", name));
+        }
+        Err(format!("cannot find package {}", name))
+    }
+
+    /// PACKAGE_EXPORTS_RESOLVE, returns the path relative to the package
+    fn resolve_exports(&self, exports: &Json, subpath: &str) -> Option<PathBuf> {
+        let is_subpath_map = match exports {
+            Json::Object(entries) => entries.iter().any(|(k, _)| k.starts_with('.')),
+            _ => false,
+        };
+        if !is_subpath_map {
+            if subpath != "." {
+                return None;
+            }
+            return self.resolve_target(exports, "");
+        }
+        let entries = match exports {
+            Json::Object(entries) => entries,
+            _ => unreachable!(),
+        };
+
+        if let Some((_, target)) = entries.iter().find(|(k, _)| k == subpath) {
+            return self.resolve_target(target, "");
+        }
+
+        // the pattern with the longest prefix wins
+        let mut best: Option<(&str, &Json, &str)> = None;
+        for (key, target) in entries {
+            let (prefix, suffix) = match key.split_once('*') {
+                Some(v) => v,
+                None => continue,
+            };
+            if subpath.len() < prefix.len() + suffix.len()
+                || !subpath.starts_with(prefix)
+                || !subpath.ends_with(suffix)
+            {
+                continue;
+            }
+            if best.map(|(p, _, _)| prefix.len() > p.len()).unwrap_or(true) {
+                let matched = &subpath[prefix.len()..subpath.len() - suffix.len()];
+                best = Some((prefix, target, matched));
+            }
+        }
+        let (_, target, matched) = best?;
+        self.resolve_target(target, matched)
+    }
+
+    /// PACKAGE_TARGET_RESOLVE,
+    /// a target or pattern match leaving the package directory is invalid
+    fn resolve_target(&self, target: &Json, matched: &str) -> Option<PathBuf> {
+        match target {
+            Json::Str(target) => {
+                let target = target.strip_prefix("./")?.replace('*', matched);
+                let invalid = target.split('/').any(|s| {
+                    s.is_empty() || s == "." || s == ".." || s.eq_ignore_ascii_case("node_modules")
+                });
+                if invalid {
+                    return None;
+                }
+                Some(PathBuf::from(target))
+            }
+            Json::Array(targets) => targets
+                .iter()
+                .find_map(|target| self.resolve_target(target, matched)),
+            Json::Object(conditions) => conditions
+                .iter()
+                .filter(|(k, _)| k == "default" || self.conditions.contains(k))
+                .find_map(|(_, target)| self.resolve_target(target, matched)),
+            _ => None,
+        }
+    }
+}
+
+impl ImportResolver for FsResolver {
+    /// the module is named by its `file://` url
+    fn resolve(&mut self, specifier: &str, referrer: Option<&str>) -> Result<String, String> {
+        let path = self.resolve_path(specifier, referrer)?;
+        let path = path
+            .canonicalize()
+            .map_err(|e| format!("cannot resolve {}: {}", path.display(), e))?;
+        Ok(path_to_url(&path))
+    }
+
+    fn import(&mut self, name: &str, _asserts: ImportAssertion) -> Result<String, String> {
+        std::fs::read_to_string(url_to_path(name))
+            .map_err(|e| format!("cannot read {}: {}", name, e))
+    }
+}
+
+/// the `file://` url of an absolute path
+fn path_to_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        // a windows path starts with the drive letter
+        format!("file:///{}", path)
+    }
+}
+
+/// the path of a `file://` url, other names are paths already
+fn url_to_path(url: &str) -> &str {
+    match url.strip_prefix("file://") {
+        // a windows path starts with the drive letter
+        Some(path) if path.as_bytes().get(2) == Some(&b':') => &path[1..],
+        Some(path) => path,
+        None => url,
+    }
+}
+
+/// LOAD_AS_FILE then LOAD_AS_DIRECTORY
+fn load_file_or_directory(path: &Path) -> Result<PathBuf, String> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    for ext in EXTENSIONS {
+        let mut file = path.as_os_str().to_owned();
+        file.push(".");
+        file.push(ext);
+        let file = PathBuf::from(file);
+        if file.is_file() {
+            return Ok(file);
+        }
+    }
+    if path.is_dir() {
+        if let Some(Json::Str(main)) = read_manifest(path)?.as_ref().and_then(|m| m.get("main")) {
+            if let Ok(path) = load_file_or_directory(&path.join(main)) {
+                return Ok(path);
+            }
+        }
+        if let Some(index) = load_index(path) {
+            return Ok(index);
+        }
+    }
+    Err(format!("cannot find module {}", path.display()))
+}
+
+fn load_index(dir: &Path) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("index.{}", ext)))
+        .find(|index| index.is_file())
+}
+
+/// split a bare specifier into the package name and the subpath,
+/// the subpath is empty or starts with a slash
+fn split_package_name(specifier: &str) -> Option<(&str, &str)> {
+    let end = if specifier.starts_with('@') {
+        let scope = specifier.find('/')?;
+        specifier[scope + 1..]
+            .find('/')
+            .map(|i| scope + 1 + i)
+            .unwrap_or(specifier.len())
+    } else {
+        specifier.find('/').unwrap_or(specifier.len())
+    };
+    if end == 0 || specifier.starts_with('.') {
+        return None;
+    }
+    Some(specifier.split_at(end))
+}
+
+/// read the package.json in the directory
+fn read_manifest(dir: &Path) -> Result<Option<Json>, String> {
+    let path = dir.join("package.json");
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return Ok(None),
+    };
+
+    parse_json(&path.to_string_lossy(), &text)
+        .map(Some)
+        .map_err(|_| format!("invalid package.json {}", path.display()))
+}
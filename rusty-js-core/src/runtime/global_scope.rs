@@ -0,0 +1,176 @@
+use swc_atoms::JsWord;
+use swc_common::DUMMY_SP;
+use swc_ecmascript::ast::*;
+use swc_ecmascript::visit::{VisitMut, VisitMutWith};
+
+/// return true if the statements start with a "use strict" directive
+pub(crate) fn has_use_strict(body: &[Stmt]) -> bool {
+    for stmt in body {
+        if let Stmt::Expr(e) = stmt {
+            if let Expr::Lit(Lit::Str(s)) = e.expr.as_ref() {
+                if s.value.as_ref() == "use strict" {
+                    return true;
+                }
+                continue;
+            }
+        }
+        break;
+    }
+    false
+}
+
+/// rewrite the `var` and function declarations of a classic script
+/// into assignments, so that they become properties of the global object.
+///
+/// function declarations are moved to the start of the script,
+/// returns the names that should be declared on the global object before running.
+pub(crate) fn hoist_global_declarations(body: &mut Vec<Stmt>) -> Vec<JsWord> {
+    let mut hoister = GlobalHoister { names: Vec::new() };
+    let mut functions = Vec::new();
+
+    for stmt in body.iter_mut() {
+        if let Stmt::Decl(Decl::Fn(f)) = stmt {
+            hoister.names.push(f.ident.sym.clone());
+
+            // name = function name(){}
+            let f = std::mem::replace(stmt, Stmt::Empty(EmptyStmt { span: DUMMY_SP }));
+            let f = match f {
+                Stmt::Decl(Decl::Fn(f)) => f,
+                _ => unreachable!(),
+            };
+            functions.push(assign_stmt(
+                Pat::Ident(BindingIdent {
+                    id: f.ident.clone(),
+                    type_ann: None,
+                }),
+                Box::new(Expr::Fn(FnExpr {
+                    ident: Some(f.ident),
+                    function: f.function,
+                })),
+            ));
+        } else {
+            stmt.visit_mut_with(&mut hoister);
+        }
+    }
+
+    body.splice(0..0, functions);
+    hoister.names
+}
+
+fn assign_stmt(pat: Pat, value: Box<Expr>) -> Stmt {
+    Stmt::Expr(ExprStmt {
+        span: DUMMY_SP,
+        expr: Box::new(assign_expr(pat, value)),
+    })
+}
+
+fn assign_expr(pat: Pat, value: Box<Expr>) -> Expr {
+    Expr::Assign(AssignExpr {
+        span: DUMMY_SP,
+        op: AssignOp::Assign,
+        left: PatOrExpr::Pat(Box::new(pat)),
+        right: value,
+    })
+}
+
+struct GlobalHoister {
+    names: Vec<JsWord>,
+}
+
+impl GlobalHoister {
+    fn collect_names(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(i) => self.names.push(i.id.sym.clone()),
+            Pat::Array(a) => {
+                for p in a.elems.iter().flatten() {
+                    self.collect_names(p);
+                }
+            }
+            Pat::Object(o) => {
+                for p in &o.props {
+                    match p {
+                        ObjectPatProp::KeyValue(kv) => self.collect_names(&kv.value),
+                        ObjectPatProp::Assign(a) => self.names.push(a.key.sym.clone()),
+                        ObjectPatProp::Rest(r) => self.collect_names(&r.arg),
+                    }
+                }
+            }
+            Pat::Rest(r) => self.collect_names(&r.arg),
+            Pat::Assign(a) => self.collect_names(&a.left),
+            Pat::Expr(_) | Pat::Invalid(_) => {}
+        }
+    }
+
+    /// turn the declarators into assignments, declarators without initializer are dropped
+    fn declarators_to_expr(&mut self, v: &mut VarDecl) -> Option<Box<Expr>> {
+        let mut exprs = Vec::new();
+        for d in v.decls.drain(..) {
+            self.collect_names(&d.name);
+            if let Some(init) = d.init {
+                exprs.push(Box::new(assign_expr(d.name, init)));
+            }
+        }
+
+        match exprs.len() {
+            0 => None,
+            1 => exprs.pop(),
+            _ => Some(Box::new(Expr::Seq(SeqExpr {
+                span: DUMMY_SP,
+                exprs,
+            }))),
+        }
+    }
+}
+
+impl VisitMut for GlobalHoister {
+    // declarations inside functions and classes are not global
+    fn visit_mut_function(&mut self, _n: &mut Function) {}
+    fn visit_mut_arrow_expr(&mut self, _n: &mut ArrowExpr) {}
+    fn visit_mut_class(&mut self, _n: &mut Class) {}
+
+    fn visit_mut_stmt(&mut self, n: &mut Stmt) {
+        n.visit_mut_children_with(self);
+
+        if let Stmt::Decl(Decl::Var(v)) = n {
+            if v.kind != VarDeclKind::Var {
+                return;
+            }
+            let span = v.span;
+            *n = match self.declarators_to_expr(v) {
+                Some(expr) => Stmt::Expr(ExprStmt { span, expr }),
+                None => Stmt::Empty(EmptyStmt { span }),
+            };
+        }
+    }
+
+    fn visit_mut_var_decl_or_expr(&mut self, n: &mut VarDeclOrExpr) {
+        n.visit_mut_children_with(self);
+
+        if let VarDeclOrExpr::VarDecl(v) = n {
+            if v.kind != VarDeclKind::Var {
+                return;
+            }
+            *n = match self.declarators_to_expr(v) {
+                Some(expr) => VarDeclOrExpr::Expr(expr),
+                // an empty initializer
+                None => VarDeclOrExpr::Expr(Box::new(Expr::Ident(Ident::new(
+                    JsWord::from("undefined"),
+                    DUMMY_SP,
+                )))),
+            };
+        }
+    }
+
+    fn visit_mut_var_decl_or_pat(&mut self, n: &mut VarDeclOrPat) {
+        n.visit_mut_children_with(self);
+
+        if let VarDeclOrPat::VarDecl(v) = n {
+            if v.kind != VarDeclKind::Var || v.decls.len() != 1 {
+                return;
+            }
+            let d = v.decls.pop().unwrap();
+            self.collect_names(&d.name);
+            *n = VarDeclOrPat::Pat(Box::new(d.name));
+        }
+    }
+}
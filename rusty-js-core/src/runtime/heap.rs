@@ -0,0 +1,82 @@
+/// a callback invoked when the heap reaches its limit and a collection cannot free enough space.
+///
+/// receives the current limit and the heap size required in bytes,
+/// return a new limit to continue allocating or `None` to refuse.
+pub type HeapLimitCallback = Box<dyn FnMut(usize, usize) -> Option<usize> + Send + Sync>;
+
+/// bookkeeping of the memory acquired by the object, string and bigint allocators
+#[derive(Default)]
+pub(crate) struct HeapQuota {
+    /// the limit in bytes, None if unlimited
+    limit: Option<usize>,
+    /// bytes acquired by the allocators
+    used: usize,
+    callback: Option<HeapLimitCallback>,
+    /// the heap exceeded the limit, a RangeError is pending
+    exhausted: bool,
+}
+
+impl HeapQuota {
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn set_callback(&mut self, callback: Option<HeapLimitCallback>) {
+        self.callback = callback;
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// return true if size bytes can be acquired without exceeding the limit
+    #[inline]
+    pub fn fits(&self, size: usize) -> bool {
+        match self.limit {
+            Some(limit) => self.used + size <= limit,
+            None => true,
+        }
+    }
+
+    #[inline]
+    pub fn acquire(&mut self, size: usize) {
+        self.used += size;
+    }
+
+    /// ask the host to raise the limit,
+    ///
+    /// return true if the new limit can hold size more bytes
+    pub fn raise(&mut self, size: usize) -> bool {
+        let limit = match self.limit {
+            Some(l) => l,
+            None => return true,
+        };
+
+        let required = self.used + size;
+
+        if let Some(callback) = &mut self.callback {
+            if let Some(new_limit) = (callback)(limit, required) {
+                self.limit = Some(new_limit);
+                return new_limit >= required;
+            }
+        }
+        false
+    }
+
+    /// the allocation exceeded the limit,
+    /// memory is still acquired as allocators cannot fail,
+    /// the error is raised at the next safe point.
+    ///
+    /// allocations made before the safe point are not refused,
+    /// the heap may exceed the limit by their size.
+    pub fn exhaust(&mut self, size: usize) {
+        self.used += size;
+        self.exhausted = true;
+    }
+
+    /// return true once if the heap exceeded the limit
+    #[inline]
+    pub fn take_exhausted(&mut self) -> bool {
+        std::mem::replace(&mut self.exhausted, false)
+    }
+}
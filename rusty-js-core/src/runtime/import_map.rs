@@ -0,0 +1,213 @@
+// WICG import maps,
+// specifiers are remapped before they are handed to the import resolver
+
+use crate::utils::json::{parse_json, Json};
+
+/// a specifier map, the keys are sorted so that longer prefixes match first,
+/// a null address blocks the specifier
+type SpecifierMap = Vec<(String, Option<String>)>;
+
+/// the `imports` and `scopes` of an import map
+#[derive(Debug, Clone, Default)]
+pub struct ImportMap {
+    imports: SpecifierMap,
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+impl ImportMap {
+    /// parse the import map, the addresses are resolved against the url of the map
+    pub fn parse(json: &str, base_url: &str) -> Result<Self, String> {
+        let json = parse_json(base_url, json)?;
+        if !matches!(json, Json::Object(_)) {
+            return Err("the import map must be a JSON object".to_string());
+        }
+
+        let imports = match json.get("imports") {
+            Some(imports @ Json::Object(_)) => parse_specifier_map(imports, base_url),
+            Some(_) => return Err("the imports of an import map must be an object".to_string()),
+            None => Vec::new(),
+        };
+
+        let mut scopes = Vec::new();
+        match json.get("scopes") {
+            Some(Json::Object(entries)) => {
+                for (prefix, map) in entries {
+                    if !matches!(map, Json::Object(_)) {
+                        return Err(format!("the scope {} must be an object", prefix));
+                    }
+                    scopes.push((
+                        join_url(base_url, prefix),
+                        parse_specifier_map(map, base_url),
+                    ));
+                }
+            }
+            Some(_) => return Err("the scopes of an import map must be an object".to_string()),
+            None => {}
+        }
+        scopes.sort_by(|a, b| b.0.cmp(&a.0));
+
+        Ok(Self { imports, scopes })
+    }
+
+    /// resolve a module specifier,
+    /// returns None if the specifier is not mapped
+    pub fn resolve(
+        &self,
+        specifier: &str,
+        referrer: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        let as_url = match referrer {
+            Some(referrer) if is_relative(specifier) => Some(join_url(referrer, specifier)),
+            _ if is_url(specifier) => Some(specifier.to_string()),
+            _ => None,
+        };
+        let normalized = as_url.as_deref().unwrap_or(specifier);
+
+        if let Some(referrer) = referrer {
+            for (prefix, map) in &self.scopes {
+                if referrer == prefix
+                    || (prefix.ends_with('/') && referrer.starts_with(prefix.as_str()))
+                {
+                    if let Some(address) = resolve_imports_match(normalized, map)? {
+                        return Ok(Some(address));
+                    }
+                }
+            }
+        }
+        resolve_imports_match(normalized, &self.imports)
+    }
+}
+
+fn parse_specifier_map(map: &Json, base_url: &str) -> SpecifierMap {
+    let entries = match map {
+        Json::Object(entries) => entries,
+        _ => return Vec::new(),
+    };
+
+    let mut map: SpecifierMap = entries
+        .iter()
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, address)| {
+            let key = if is_relative(key) || is_url(key) {
+                join_url(base_url, key)
+            } else {
+                key.clone()
+            };
+            // invalid addresses block the specifier
+            let address = match address {
+                Json::Str(address) if !key.ends_with('/') || address.ends_with('/') => {
+                    Some(join_url(base_url, address))
+                }
+                _ => None,
+            };
+            (key, address)
+        })
+        .collect();
+
+    map.sort_by(|a, b| b.0.cmp(&a.0));
+    map
+}
+
+/// resolve an imports match, an exact key or the longest prefix ending with a slash
+fn resolve_imports_match(specifier: &str, map: &SpecifierMap) -> Result<Option<String>, String> {
+    for (key, address) in map {
+        let after_prefix = if key == specifier {
+            ""
+        } else if key.ends_with('/') && specifier.starts_with(key.as_str()) {
+            &specifier[key.len()..]
+        } else {
+            continue;
+        };
+
+        return match address {
+            Some(address) => Ok(Some(format!("{}{}", address, after_prefix))),
+            None => Err(format!("{} is blocked by the import map", specifier)),
+        };
+    }
+    Ok(None)
+}
+
+fn is_relative(specifier: &str) -> bool {
+    specifier.starts_with('/') || specifier.starts_with("./") || specifier.starts_with("../")
+}
+
+/// absolute urls start with a scheme, drive letters are not schemes
+fn is_url(specifier: &str) -> bool {
+    match specifier.find(':') {
+        Some(i) if i > 1 => specifier[..i]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'),
+        _ => false,
+    }
+}
+
+/// resolve a url against the base url, the base is either a url or a path
+fn join_url(base: &str, url: &str) -> String {
+    if is_url(url) {
+        return url.to_string();
+    }
+
+    let (origin, base_path) = match base.find("://") {
+        Some(i) => {
+            let path_start = base[i + 3..]
+                .find('/')
+                .map(|p| i + 3 + p)
+                .unwrap_or(base.len());
+            base.split_at(path_start)
+        }
+        None => ("", base),
+    };
+
+    let path = if url.starts_with('/') {
+        url.to_string()
+    } else {
+        let dir = match base_path.rfind('/') {
+            Some(i) => &base_path[..=i],
+            None => "",
+        };
+        format!("{}{}", dir, url)
+    };
+
+    format!("{}{}", origin, normalize(&path))
+}
+
+/// remove the `.` and `..` segments of a path
+fn normalize(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let segments: Vec<&str> = path.split('/').collect();
+    let last = segments.len() - 1;
+
+    let mut out: Vec<&str> = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        match *segment {
+            "" if i != last => {}
+            "." => {
+                if i == last {
+                    out.push("");
+                }
+            }
+            ".." => {
+                match out.last() {
+                    Some(&s) if s != ".." => {
+                        out.pop();
+                    }
+                    _ if absolute => {}
+                    _ => out.push(".."),
+                }
+                if i == last {
+                    out.push("");
+                }
+            }
+            s => out.push(s),
+        }
+    }
+
+    let path = out.join("/");
+    if absolute {
+        format!("/{}", path)
+    } else if path.starts_with("..") {
+        path
+    } else {
+        format!("./{}", path)
+    }
+}
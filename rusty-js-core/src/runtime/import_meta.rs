@@ -0,0 +1,81 @@
+// import.meta, created for each module the first time it is read
+
+use super::Runtime;
+use crate::bultins::object::JObject;
+use crate::bultins::object_property::{PropFlag, ToProperyKey};
+use crate::error::Error;
+use crate::utils::string_interner::NAMES;
+use crate::value::JValue;
+
+/// 16.2.1.12 HostGetImportMetaProperties and HostFinalizeImportMeta,
+/// called with the runtime, the url of the module and its import.meta object.
+///
+/// the object is rooted, values are kept alive once they are set on it.
+pub type ImportMetaHook = Box<dyn FnMut(&Runtime, &str, JObject)>;
+
+impl Runtime {
+    /// set the hook that adds host defined properties to import.meta,
+    /// such as `import.meta.env`
+    pub fn set_import_meta_hook<F>(&self, hook: F)
+    where
+        F: FnMut(&Runtime, &str, JObject) + 'static,
+    {
+        self.to_mut().import_meta_hook = Some(Box::new(hook));
+    }
+
+    /// 13.3.12.1 the import.meta of the module with the url,
+    /// the object has `url` and `resolve()` and a null prototype
+    pub(crate) fn import_meta(&self, url: &str) -> JValue {
+        if let Some(meta) = self.import_metas.get(url) {
+            return (*meta).into();
+        }
+
+        let meta: JObject = self.allocate_obj().into();
+        // rooted before the properties are allocated
+        self.to_mut().import_metas.insert(url.to_string(), meta);
+
+        meta.insert_property(
+            "url".to_key(self),
+            JValue::create_string(self.allocate_string(url)),
+            PropFlag::THREE,
+        );
+
+        let referrer = url.to_string();
+        let resolve = self.create_native_function(move |ctx, _this, args| {
+            let specifier = args.get(0).copied().unwrap_or(JValue::UNDEFINED);
+            let referrer = if referrer.is_empty() {
+                None
+            } else {
+                Some(referrer.as_str())
+            };
+            match ctx
+                .runtime
+                .resolve_specifier(&specifier.to_string(), referrer)
+            {
+                Ok(url) => Ok(JValue::create_string(ctx.runtime.allocate_string(&url))),
+                Err(e) => Err(Error::TypeError(e.message()).into()),
+            }
+        });
+        resolve.insert_property(
+            NAMES["name"],
+            JValue::create_static_string("resolve"),
+            PropFlag::CONFIGURABLE,
+        );
+        resolve.insert_property(
+            NAMES["length"],
+            JValue::create_number(1.0),
+            PropFlag::CONFIGURABLE,
+        );
+        meta.insert_property("resolve".to_key(self), resolve.into(), PropFlag::THREE);
+
+        // the hook is taken out while running as it may replace itself
+        if let Some(mut hook) = self.to_mut().import_meta_hook.take() {
+            hook(self, url, meta);
+            if self.import_meta_hook.is_none() {
+                self.to_mut().import_meta_hook = Some(hook);
+            }
+        }
+
+        meta.into()
+    }
+}
@@ -1,14 +1,116 @@
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 
+use crate::bultins::function::JSContext;
+use crate::error::Error;
+use crate::utils::string_interner::NAMES;
+use crate::value::JValue;
+
+#[derive(Debug, Clone, Default)]
 pub struct ImportAssertion {
     pub assertions: HashMap<String, String>,
 }
 
+impl ImportAssertion {
+    /// the key of a module request, modules imported with different assertions are fetched apart
+    pub(crate) fn cache_key(&self, name: &str) -> String {
+        if self.assertions.is_empty() {
+            return name.to_string();
+        }
+        let mut assertions: Vec<_> = self.assertions.iter().collect();
+        assertions.sort();
+
+        let mut key = name.to_string();
+        for (k, v) in assertions {
+            key.push_str(&format!(" {}={}", k, v));
+        }
+        key
+    }
+}
+
 pub trait ImportResolver {
+    /// resolve the specifier against the url of the importing module,
+    /// the resolved name is passed to `import` and identifies the module
+    fn resolve(&mut self, specifier: &str, _referrer: Option<&str>) -> Result<String, String> {
+        Ok(specifier.to_string())
+    }
+
     /// return the script in raw string
     fn import(&mut self, name: &str, asserts: ImportAssertion) -> Result<String, String>;
 }
 
+/// the language of a module source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    JavaScript,
+    Json,
+    TypeScript,
+    Wasm,
+}
+
+impl ModuleKind {
+    /// guess the kind from the `type` assertion or the extension of the url
+    pub fn guess(url: &str, asserts: &ImportAssertion) -> Self {
+        if let Some(ty) = asserts.assertions.get("type") {
+            if ty == "json" {
+                return ModuleKind::Json;
+            }
+        }
+
+        let path = url.split(|c| c == '?' || c == '#').next().unwrap_or(url);
+        match path.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("json") => ModuleKind::Json,
+            Some("ts") | Some("mts") | Some("cts") => ModuleKind::TypeScript,
+            Some("wasm") => ModuleKind::Wasm,
+            _ => ModuleKind::JavaScript,
+        }
+    }
+}
+
+/// a module loaded by a resolver
+#[derive(Debug, Clone)]
+pub struct ModuleSource {
+    /// the source text
+    pub source: String,
+    /// the canonical url or path of the module
+    pub url: String,
+    pub kind: ModuleKind,
+}
+
+pub type ModuleFuture = Pin<Box<dyn Future<Output = Result<ModuleSource, String>>>>;
+
+/// a resolver that loads modules without blocking the runtime,
+/// the futures are polled on the thread of the runtime
+pub trait AsyncImportResolver {
+    /// resolve the specifier against the url of the importing module,
+    /// the resolved name is passed to `import` and identifies the module
+    fn resolve(&mut self, specifier: &str, _referrer: Option<&str>) -> Result<String, String> {
+        Ok(specifier.to_string())
+    }
+
+    fn import(&mut self, name: &str, asserts: ImportAssertion) -> ModuleFuture;
+}
+
+/// runs a synchronous resolver as an async one
+pub(crate) struct SyncResolver<R>(pub R);
+
+impl<R: ImportResolver> AsyncImportResolver for SyncResolver<R> {
+    fn resolve(&mut self, specifier: &str, referrer: Option<&str>) -> Result<String, String> {
+        self.0.resolve(specifier, referrer)
+    }
+
+    fn import(&mut self, name: &str, asserts: ImportAssertion) -> ModuleFuture {
+        let kind = ModuleKind::guess(name, &asserts);
+        let re = self.0.import(name, asserts).map(|source| ModuleSource {
+            source,
+            url: name.to_string(),
+            kind,
+        });
+        Box::pin(std::future::ready(re))
+    }
+}
+
 impl From<Option<&swc_ecmascript::ast::ObjectLit>> for ImportAssertion {
     fn from(asserts: Option<&swc_ecmascript::ast::ObjectLit>) -> Self {
         if let Some(o) = asserts {
@@ -56,3 +158,56 @@ impl From<Option<&swc_ecmascript::ast::ObjectLit>> for ImportAssertion {
         }
     }
 }
+
+impl ImportAssertion {
+    /// read the assertions from the options of import(),
+    /// the `with` key is looked up before the older `assert` key
+    pub(crate) fn from_options(options: JValue, ctx: JSContext) -> Result<Self, JValue> {
+        let mut assertions = HashMap::default();
+
+        if options.is_undefined() {
+            return Ok(Self { assertions });
+        }
+        if !options.is_object() {
+            return Err(
+                Error::TypeError("the options of import() must be an object".to_string()).into(),
+            );
+        }
+
+        let mut attributes = options.get_property(NAMES["with"], ctx)?;
+        if attributes.is_undefined() {
+            attributes = options.get_property(NAMES["assert"], ctx)?;
+        }
+        if attributes.is_undefined() {
+            return Ok(Self { assertions });
+        }
+
+        let obj = match attributes.as_object() {
+            Some(obj) => obj,
+            None => {
+                return Err(
+                    Error::TypeError("the import assertions must be an object".to_string()).into(),
+                )
+            }
+        };
+
+        for (key, cell) in obj.inner.values.iter() {
+            if !cell.flag.is_enumerable() {
+                continue;
+            }
+            let value = obj.get_property(*key, ctx)?;
+            let value = match value.as_string() {
+                Some(s) => s.to_string(),
+                None => {
+                    return Err(Error::TypeError(
+                        "the value of an import assertion must be a string".to_string(),
+                    )
+                    .into())
+                }
+            };
+            assertions.insert(ctx.runtime.get_field_name(key.0).to_string(), value);
+        }
+
+        Ok(Self { assertions })
+    }
+}
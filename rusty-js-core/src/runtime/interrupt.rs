@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// the shared state between a runtime and its interrupt handles
+pub(crate) struct InterruptState {
+    /// set by an interrupt handle, checked by the interpreter
+    interrupted: AtomicBool,
+    /// the execution is being terminated, errors can no longer be caught
+    terminating: AtomicBool,
+    /// the budget set by the user, u64::MAX if unlimited
+    budget: AtomicU64,
+    /// the remaining fuel of the current execution
+    fuel: AtomicU64,
+    /// number of nested executions, modules are executed inside their importer
+    depth: AtomicUsize,
+}
+
+impl Default for InterruptState {
+    fn default() -> Self {
+        Self {
+            interrupted: AtomicBool::new(false),
+            terminating: AtomicBool::new(false),
+            budget: AtomicU64::new(u64::MAX),
+            fuel: AtomicU64::new(u64::MAX),
+            depth: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl InterruptState {
+    pub fn set_budget(&self, budget: Option<u64>) {
+        let budget = budget.unwrap_or(u64::MAX);
+        self.budget.store(budget, Ordering::Relaxed);
+        self.fuel.store(budget, Ordering::Relaxed);
+    }
+
+    /// called before an execution starts,
+    /// the fuel is refilled and stale interrupts are dropped if this is the outermost execution
+    pub fn enter(&self) {
+        if self.depth.fetch_add(1, Ordering::Relaxed) == 0 {
+            self.interrupted.store(false, Ordering::Relaxed);
+            self.terminating.store(false, Ordering::Relaxed);
+            self.fuel
+                .store(self.budget.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+
+    /// called after an execution finishes,
+    /// the termination flags are cleared if this is the outermost execution
+    pub fn exit(&self) {
+        if self.depth.fetch_sub(1, Ordering::Relaxed) == 1 {
+            self.interrupted.store(false, Ordering::Relaxed);
+            self.terminating.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// consume one unit of fuel,
+    ///
+    /// return false if the execution should terminate
+    #[inline]
+    pub fn consume(&self) -> bool {
+        if self.interrupted.load(Ordering::Relaxed) {
+            self.terminating.store(true, Ordering::Relaxed);
+            return false;
+        }
+
+        let fuel = self.fuel.load(Ordering::Relaxed);
+
+        // unlimited budget
+        if fuel == u64::MAX {
+            return true;
+        }
+
+        if fuel == 0 {
+            self.terminating.store(true, Ordering::Relaxed);
+            return false;
+        }
+
+        self.fuel.store(fuel - 1, Ordering::Relaxed);
+        true
+    }
+
+    /// return true if an execution is running
+    pub fn is_running(&self) -> bool {
+        self.depth.load(Ordering::Relaxed) > 0
+    }
+
+    #[inline]
+    pub fn is_terminating(&self) -> bool {
+        self.terminating.load(Ordering::Relaxed)
+    }
+
+    pub fn remaining(&self) -> Option<u64> {
+        let fuel = self.fuel.load(Ordering::Relaxed);
+        if fuel == u64::MAX {
+            None
+        } else {
+            Some(fuel)
+        }
+    }
+}
+
+/// a handle used to terminate the execution of a runtime from another thread.
+///
+/// the handle is cheap to clone and can be sent to any thread.
+#[derive(Clone)]
+pub struct InterruptHandle {
+    pub(crate) state: Arc<InterruptState>,
+}
+
+impl InterruptHandle {
+    /// request the runtime to terminate the current execution.
+    ///
+    /// the interpreter checks the request at loop back edges and function calls,
+    /// the execution then returns `Error::Terminated`, which cannot be caught by javascript.
+    ///
+    /// a request made while nothing is running is dropped when the next execution starts.
+    pub fn interrupt(&self) {
+        self.state.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    /// return true if an interrupt is requested and not yet handled
+    pub fn is_interrupted(&self) -> bool {
+        self.state.interrupted.load(Ordering::Relaxed)
+    }
+}
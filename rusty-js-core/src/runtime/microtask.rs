@@ -0,0 +1,336 @@
+// the job queue of promise reactions and queueMicrotask callbacks,
+// drained by a checkpoint after each top level execution
+
+use std::task::{Context, Poll};
+
+use super::{AsyncId, ImportAssertion, ModuleId, Runtime};
+use crate::bultins::object::JObject;
+use crate::bultins::promise::{self, Promise, PromiseReaction};
+use crate::error::Error;
+use crate::value::JValue;
+use crate::JSContext;
+
+/// the operation reported to the rejection handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionOperation {
+    /// a promise is rejected without a handler
+    Reject,
+    /// a handler is added to a promise reported by `Reject`
+    Handle,
+}
+
+/// called with the promise, the rejection reason and the operation
+pub type RejectionHandler = Box<dyn FnMut(JValue, JValue, RejectionOperation)>;
+
+/// called with the exception thrown by a microtask job
+pub type ExceptionHandler = Box<dyn FnMut(JValue)>;
+
+#[derive(Clone)]
+pub(crate) enum Microtask {
+    /// 27.2.2.1 NewPromiseReactionJob
+    Reaction {
+        reaction: PromiseReaction,
+        argument: JValue,
+        rejected: bool,
+    },
+    /// 27.2.2.2 NewPromiseResolveThenableJob
+    ResolveThenable {
+        promise: JObject,
+        thenable: JValue,
+        then: JValue,
+    },
+    /// a callback queued by queueMicrotask
+    Callback(JValue),
+    /// evaluate a module fetched by import() and settle its promise
+    EvaluateModule {
+        promise: JObject,
+        specifier: String,
+        asserts: ImportAssertion,
+    },
+    /// 16.2.1.5.3.4 AsyncModuleExecutionFulfilled or AsyncModuleExecutionRejected
+    AsyncModuleSettled {
+        module: ModuleId,
+        result: Result<(), JValue>,
+    },
+}
+
+impl Microtask {
+    fn run(self, ctx: JSContext) -> Result<(), JValue> {
+        match self {
+            Microtask::Reaction {
+                reaction,
+                argument,
+                rejected,
+            } => {
+                let handler = if rejected {
+                    reaction.on_rejected
+                } else {
+                    reaction.on_fulfilled
+                };
+
+                let re = if handler.is_callable() {
+                    handler.call_with_context(JValue::UNDEFINED, &[argument], ctx)
+                } else if rejected {
+                    Err(argument)
+                } else {
+                    Ok(argument)
+                };
+
+                if let Some(p) = reaction.capability {
+                    match re {
+                        Ok(v) => promise::resolve_promise(ctx, p, v),
+                        Err(e) => promise::reject_promise(ctx.runtime, p, e),
+                    }
+                }
+                Ok(())
+            }
+            Microtask::ResolveThenable {
+                promise,
+                thenable,
+                then,
+            } => {
+                let (resolve, reject) = promise::create_resolving_functions(ctx.runtime, promise);
+                ctx.runtime.user_own_value(resolve.into());
+                ctx.runtime.user_own_value(reject.into());
+
+                let re = then.call_with_context(thenable, &[resolve.into(), reject.into()], ctx);
+                let re = match re {
+                    Err(e) => reject.call_with_context(JValue::UNDEFINED, &[e], ctx),
+                    Ok(v) => Ok(v),
+                };
+
+                ctx.runtime.user_drop_value(resolve.into());
+                ctx.runtime.user_drop_value(reject.into());
+                re.map(|_| ())
+            }
+            Microtask::Callback(callback) => {
+                callback.call_with_context(JValue::UNDEFINED, &[], ctx)?;
+                Ok(())
+            }
+            Microtask::EvaluateModule {
+                promise,
+                specifier,
+                asserts,
+            } => {
+                ctx.runtime
+                    .arc()
+                    .evaluate_dynamic_import(promise, &specifier, asserts, ctx.stack);
+                Ok(())
+            }
+            Microtask::AsyncModuleSettled { module, result } => {
+                ctx.runtime
+                    .arc()
+                    .async_module_settled(module, result, ctx.stack);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) unsafe fn trace(&self) {
+        match self {
+            Microtask::Reaction {
+                reaction, argument, ..
+            } => {
+                reaction.trace();
+                argument.trace();
+            }
+            Microtask::ResolveThenable {
+                promise,
+                thenable,
+                then,
+            } => {
+                promise.trace();
+                thenable.trace();
+                then.trace();
+            }
+            Microtask::Callback(callback) => callback.trace(),
+            Microtask::EvaluateModule { promise, .. } => promise.trace(),
+            Microtask::AsyncModuleSettled { result, .. } => {
+                if let Err(e) = result {
+                    e.trace();
+                }
+            }
+        }
+    }
+}
+
+impl Runtime {
+    /// 9.5.4 HostEnqueuePromiseJob
+    pub(crate) fn enqueue_microtask(&self, job: Microtask) {
+        self.to_mut().microtasks.push_back(job);
+    }
+
+    /// set the callback of HostPromiseRejectionTracker,
+    /// a promise still unhandled after the event loop finishes is an unhandled rejection
+    pub fn set_rejection_handler<F>(&self, handler: F)
+    where
+        F: FnMut(JValue, JValue, RejectionOperation) + 'static,
+    {
+        self.to_mut().rejection_handler = Some(Box::new(handler));
+    }
+
+    /// set the callback reporting the exceptions thrown by microtask jobs,
+    /// the checkpoint keeps running the remaining jobs
+    pub fn set_exception_handler<F>(&self, handler: F)
+    where
+        F: FnMut(JValue) + 'static,
+    {
+        self.to_mut().exception_handler = Some(Box::new(handler));
+    }
+
+    /// 27.2.1.9 HostPromiseRejectionTracker ( promise, "reject" )
+    pub(crate) fn track_rejection(&self, promise: JObject, reason: JValue) {
+        self.to_mut().unhandled_rejections.insert(promise.into());
+        self.notify_rejection(promise, reason, RejectionOperation::Reject);
+    }
+
+    /// 27.2.1.9 HostPromiseRejectionTracker ( promise, "handle" ),
+    /// called whenever a handler is added to a rejected promise
+    pub(crate) fn promise_handled(&self, promise: JObject) {
+        if !self.to_mut().unhandled_rejections.remove(&promise.into()) {
+            return;
+        }
+
+        if let Some(Promise::Rejected(reason)) = promise.as_promise() {
+            self.notify_rejection(promise, *reason, RejectionOperation::Handle);
+        }
+    }
+
+    /// take the rejected promises that have no handler yet, in no particular order,
+    /// a handler added to them later is not reported.
+    ///
+    /// the list is dropped when the event loop finishes,
+    /// a host running scripts without the event loop should take it after each execution.
+    pub fn take_unhandled_rejections(&self) -> Vec<JValue> {
+        self.to_mut().unhandled_rejections.drain().collect()
+    }
+
+    fn notify_rejection(&self, promise: JObject, reason: JValue, op: RejectionOperation) {
+        // the handler is taken out while running as it may replace itself
+        if let Some(mut handler) = self.to_mut().rejection_handler.take() {
+            handler(promise.into(), reason, op);
+            if self.rejection_handler.is_none() {
+                self.to_mut().rejection_handler = Some(handler);
+            }
+        }
+    }
+
+    /// report the exception of a job to the handler,
+    /// without a handler the first exception is kept until the checkpoint ends
+    fn report_exception(&self, exception: JValue) {
+        if let Some(mut handler) = self.to_mut().exception_handler.take() {
+            handler(exception);
+            if self.exception_handler.is_none() {
+                self.to_mut().exception_handler = Some(handler);
+            }
+        } else if self.uncaught_exception.is_none() {
+            self.user_own_value(exception);
+            self.to_mut().uncaught_exception = Some(exception);
+        }
+    }
+
+    /// settle the promise when the future completes,
+    /// the future is polled once the queue is empty
+    pub(crate) fn await_future(&self, id: AsyncId, promise: JObject) {
+        self.to_mut().awaited_futures.push((id, promise));
+    }
+
+    /// run every queued microtask, including the ones queued while running,
+    /// futures awaited by promises are polled when the queue is empty.
+    ///
+    /// an exception thrown by a job is reported to the exception handler and the next job runs,
+    /// without a handler the first exception is returned once the queue is drained.
+    pub fn run_microtasks(&self) -> Result<(), Error> {
+        // the checkpoint is not reentrant
+        if self.performing_microtasks {
+            return Ok(());
+        }
+        self.to_mut().performing_microtasks = true;
+
+        let re = self.call_from_host(|ctx| {
+            while self.run_next_microtask(ctx)? || self.poll_awaited_futures(ctx) {}
+
+            if let Some(e) = self.to_mut().uncaught_exception.take() {
+                self.user_drop_value(e);
+                return Err(e);
+            }
+            Ok(JValue::UNDEFINED)
+        });
+
+        self.to_mut().performing_microtasks = false;
+        re.map(|_| ())
+    }
+
+    /// return true if there are jobs waiting in the queue
+    pub fn has_pending_microtasks(&self) -> bool {
+        !self.microtasks.is_empty()
+    }
+
+    fn run_next_microtask(&self, ctx: JSContext) -> Result<bool, JValue> {
+        let job = match self.to_mut().microtasks.pop_front() {
+            Some(job) => job,
+            None => return Ok(false),
+        };
+
+        // keep the job alive while it runs
+        self.to_mut().running_microtasks.push(job.clone());
+        let re = job.run(ctx);
+        self.to_mut().running_microtasks.pop();
+
+        match re {
+            Ok(()) => Ok(true),
+            // termination cannot be reported
+            Err(e) if self.is_terminating() => Err(e),
+            Err(e) => {
+                self.report_exception(e);
+                Ok(true)
+            }
+        }
+    }
+
+    /// poll the futures awaited by promises and the module fetches once,
+    /// return true if any of them completed
+    fn poll_awaited_futures(&self, ctx: JSContext) -> bool {
+        let mut completed = false;
+        let mut i = 0;
+
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+
+        while i < self.awaited_futures.len() {
+            let (id, promise) = self.awaited_futures[i];
+
+            match self.poll_future(id, &mut cx) {
+                Poll::Ready(re) => {
+                    // the promise stays rooted by the list until it is settled
+                    match re {
+                        Ok(v) => promise::resolve_promise(ctx, promise, v),
+                        Err(e) => promise::reject_promise(self, promise, e),
+                    }
+                    self.to_mut().awaited_futures.remove(i);
+                    completed = true;
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+
+        let mut i = 0;
+        while i < self.module_fetches.len() {
+            match self.poll_future(self.module_fetches[i].task, &mut cx) {
+                Poll::Ready(re) => {
+                    let fetch = self.to_mut().module_fetches.remove(i);
+                    match re {
+                        Ok(_) => self.enqueue_microtask(Microtask::EvaluateModule {
+                            promise: fetch.promise,
+                            specifier: fetch.specifier,
+                            asserts: fetch.asserts,
+                        }),
+                        Err(e) => promise::reject_promise(self, fetch.promise, e),
+                    }
+                    completed = true;
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+        completed
+    }
+}
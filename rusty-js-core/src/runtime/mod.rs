@@ -1,5 +1,6 @@
 use std::alloc::Layout;
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Weak;
@@ -11,18 +12,53 @@ use parking_lot::RwLock;
 //mod runtime_context;
 //mod async_executor;
 mod bigint_allocator;
+mod builder;
+mod code_cache;
+mod dynamic_import;
+mod event_loop;
 mod finalize_registry;
+mod fs_resolver;
 mod gc;
+mod global_scope;
+mod heap;
+mod import_map;
+mod import_meta;
 mod import_resolver;
+mod interrupt;
+mod microtask;
+mod module_loader;
+mod module_map;
 mod object_allocater;
 mod profiler;
+mod script;
+mod snapshot;
 mod string_allocator;
+mod typescript;
 
-use import_resolver::ImportAssertion;
-use import_resolver::ImportResolver;
+use import_resolver::SyncResolver;
+pub use import_resolver::{
+    AsyncImportResolver, ImportAssertion, ImportResolver, ModuleFuture, ModuleKind, ModuleSource,
+};
 
 use finalize_registry::FinalizeRegistry;
 
+pub use fs_resolver::FsResolver;
+pub use import_map::ImportMap;
+pub use import_meta::ImportMetaHook;
+
+use interrupt::InterruptState;
+pub use interrupt::InterruptHandle;
+
+pub(crate) use microtask::Microtask;
+pub use microtask::{ExceptionHandler, RejectionHandler, RejectionOperation};
+
+use heap::HeapQuota;
+pub use heap::HeapLimitCallback;
+
+pub use builder::{Builtins, RuntimeBuilder, RuntimeOptions};
+
+pub use script::Script;
+
 pub use gc::GcFlag;
 
 //pub use async_executor::*;
@@ -38,6 +74,7 @@ use crate::bytecodes::function_builder_context::FunctionBuilderContext;
 use crate::value::JValue;
 use crate::utils::string_interner::StringInterner;
 use crate::utils::string_interner::NAMES;
+use crate::utils::string_interner::SYMBOLS;
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FuncID(pub(crate) u32);
@@ -63,6 +100,42 @@ pub struct ModuleId(u32);
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct AsyncId(usize);
 
+/// values owned by a task until it is dropped
+pub(crate) struct TaskRoots {
+    runtime: Weak<Runtime>,
+    values: Vec<JValue>,
+}
+
+impl TaskRoots {
+    pub(crate) fn new(runtime: &Runtime, this: JValue, args: &[JValue]) -> Self {
+        let mut values = Vec::with_capacity(args.len() + 1);
+        values.push(this);
+        values.extend_from_slice(args);
+        values.iter().for_each(|v| runtime.user_own_value(*v));
+
+        Self {
+            runtime: runtime.weak_ref.clone().unwrap(),
+            values,
+        }
+    }
+}
+
+impl Drop for TaskRoots {
+    fn drop(&mut self) {
+        if let Some(rt) = self.runtime.upgrade() {
+            self.values.iter().for_each(|v| rt.user_drop_value(*v));
+        }
+    }
+}
+
+/// a future registered by run_async
+enum AsyncTask {
+    Running(Pin<Box<dyn Future<Output = Result<JValue, JValue>>>>),
+    /// taken out by poll_future
+    Polling,
+    Done(Result<JValue, JValue>),
+}
+
 /// a Variable declared on the global context
 #[derive(Debug, Clone, Copy, Hash)]
 pub(crate) enum Variable {
@@ -72,6 +145,8 @@ pub(crate) enum Variable {
     Let(JValue),
     /// const declare
     Const(JValue),
+    /// a lexical module export read before its declaration is evaluated
+    Uninitialized,
     // todo: var on stack
     /// a variable imported from a module
     Import {
@@ -126,12 +201,42 @@ enum ExportVariable {
     ImportModule {
         id: ModuleId,
     },
+    /// a value created by the host,
+    /// the default export of a JSON module
+    Value(JValue),
 }
 
 pub(crate) struct Module {
     name: String,
+    /// the default export is exported as "default"
     exports: HashMap<u32, ExportVariable>,
-    default_export: JValue,
+    status: module_map::ModuleStatus,
+    /// the strictness of an entry module is set by the host
+    entry: bool,
+    /// the modules imported, in the order of the import declarations
+    requested: Vec<ModuleId>,
+    /// the translated code, taken when the module is evaluated
+    code: Option<Vec<crate::bytecodes::OpCode>>,
+    op_stack: usize,
+    dfs_index: usize,
+    dfs_ancestor_index: usize,
+    /// the error thrown by the evaluation, thrown again to later importers
+    error: Option<crate::error::Error>,
+    /// the module awaits at the top level
+    has_top_level_await: bool,
+    /// the order the module started evaluating asynchronously,
+    /// None once it is evaluated
+    async_evaluation: Option<usize>,
+    /// the imported modules that are still evaluating asynchronously
+    pending_async_dependencies: usize,
+    /// the importers waiting for the module to evaluate
+    async_parents: Vec<ModuleId>,
+    /// the root of the strongly connected component of the module
+    cycle_root: ModuleId,
+    /// promises settled once the module graph is evaluated, with their fulfilled value
+    waiting: Vec<(JObject, JValue)>,
+    /// the registers, temps and iterators of the code while it is suspended by an await
+    roots: Vec<JValue>,
 }
 
 thread_local! {
@@ -139,7 +244,10 @@ thread_local! {
 }
 
 pub const DEFAULT_STACK_SIZE: usize = 4096 * 256 / std::mem::size_of::<JValue>();
-const NUM_WORKER_THREAD: usize = 3;
+
+/// the collections an unreachable value survives at most,
+/// it ages from Used to Old to NotUsed and is freed by the next sweep
+const GC_GENERATIONS: usize = 3;
 
 pub struct Runtime {
     weak_ref:Option<Weak<Self>>,
@@ -151,6 +259,11 @@ pub struct Runtime {
 
     obj_field_names: RwLock<StringInterner>,
     dynamic_var_names: RwLock<StringInterner>,
+    /// the descriptions of the symbols created by the runtime,
+    /// a symbol is a reserved field name so it can be used as a property key
+    symbol_descriptions: HashMap<u32, Option<String>>,
+    /// the GlobalSymbolRegistry, 20.4.2.2 Symbol.for
+    symbol_registry: HashMap<String, u32>,
 
     dynamic_var_suffix: Option<String>,
 
@@ -159,21 +272,31 @@ pub struct Runtime {
     strings: StringInterner,
 
     /// the stack is used for storing variables and call frames,
-    pub(crate) stack: Box<[JValue; DEFAULT_STACK_SIZE]>,
+    pub(crate) stack: Box<[JValue]>,
     /// the operational stack is used for operations
     /// such as calling getters and setters,
     /// all values are disposed after one call
-    pub(crate) operational_stack: Box<[JValue; DEFAULT_STACK_SIZE]>,
+    pub(crate) operational_stack: Box<[JValue]>,
+    /// the first free slot while a native function runs,
+    /// calls from the host made by the native start there
+    pub(crate) host_stack_top: Option<*mut JValue>,
 
     object_allocator: object_allocater::ObjectAllocator,
     string_allocator: string_allocator::StringAllocator,
     bigint_allocator: bigint_allocator::BigIntAllocator,
 
+    /// memory acquired by the allocators
+    heap: HeapQuota,
+
     /// variable that belongs to a module are formatted with { name @ moduleID }
     pub(crate) variables: HashMap<u32, Variable>,
 
     functions: Vec<Option<Arc<JSFunction>>>,
     classes: Vec<Option<Arc<JSClass>>>,
+    /// the last brand given to a class evaluation, private names are unique to a brand
+    private_brands: u32,
+    /// native functions reconnected by their id when a snapshot is restored
+    natives: snapshot::NativeRegistry,
     regexs: Vec<Box<bultins::regex::RegExp>>,
     templates: Vec<bultins::strings::Template>,
 
@@ -182,26 +305,74 @@ pub struct Runtime {
     pub prototypes: bultins::BuiltinPrototypes,
 
     pub new_target: JValue,
-    pub(crate) import_meta: JValue,
-
-    import_resolver: Option<Arc<RwLock<dyn ImportResolver>>>,
+    /// the import.meta of the modules, keyed by url
+    import_metas: HashMap<String, JObject>,
+    import_meta_hook: Option<ImportMetaHook>,
+
+    import_resolver: Option<Arc<RwLock<dyn AsyncImportResolver>>>,
+    /// remaps the specifiers before they reach the resolver
+    import_map: Option<ImportMap>,
+    /// the url of the script or module being built, the referrer of its imports
+    pub(crate) module_url: Option<String>,
     modules: Vec<Module>,
+    /// the linked modules, keyed by resolved specifier and assertions
+    module_map: HashMap<String, ModuleId>,
+    /// the module being translated, its imports are requested by it
+    linking_module: Option<ModuleId>,
+    /// the imported names checked once the module graph is linked
+    unresolved_imports: Vec<(ModuleId, String)>,
+    /// sources fetched ahead of evaluation, keyed by specifier and assertions
+    module_sources: HashMap<String, ModuleSource>,
+    /// dynamic imports waiting for their module graph to be fetched
+    module_fetches: Vec<dynamic_import::ModuleFetch>,
+    /// the number of modules that started evaluating asynchronously
+    async_module_count: usize,
+    /// the depth of module graph evaluations on the stack
+    module_evaluation_depth: usize,
+    /// async modules settled while a graph is evaluated, continued once it returns
+    settled_modules: Vec<(ModuleId, Result<(), JValue>)>,
 
     /// temporary map to store exports
     exported_variables: HashMap<String, ExportVariable>,
 
-    futures: Vec<Pin<Box<dyn Future<Output = Result<JValue, JValue>>>>>,
+    futures: Vec<AsyncTask>,
     async_stacks: Vec<&'static [JValue]>,
 
+    /// the promise job queue
+    microtasks: VecDeque<Microtask>,
+    /// jobs taken from the queue that are still running
+    running_microtasks: Vec<Microtask>,
+    /// futures that settle a promise once completed
+    awaited_futures: Vec<(AsyncId, JObject)>,
+    /// rust tasks waiting for a promise to settle
+    promise_wakers: Vec<(JObject, std::task::Waker)>,
+    /// rejected promises without a handler,
+    /// dropped when the event loop finishes or taken by the host
+    unhandled_rejections: std::collections::HashSet<JValue>,
+    rejection_handler: Option<RejectionHandler>,
+    exception_handler: Option<ExceptionHandler>,
+    /// the first exception of a job without an exception handler,
+    /// thrown once the checkpoint drained the queue
+    uncaught_exception: Option<JValue>,
+    performing_microtasks: bool,
+
+    clock: event_loop::Clock,
+    timers: event_loop::TimerQueue,
+
     pub(crate) finalize_registry: FinalizeRegistry,
 
+    /// execution budget and interrupt requests
+    pub(crate) interrupt: Arc<InterruptState>,
+
     /// a reference counted user owned value
     user_owned: HashMap<JValue, AtomicUsize>,
 
     pub(crate) worker_task_sender: crossbeam_channel::Sender<Box<dyn FnOnce() + Sync + Send>>,
+    worker_threads: usize,
 
-    pub(crate) baseline_context: Arc<inkwell::context::Context>,
-    pub(crate) baseline_module: Arc<inkwell::module::Module<'static>>,
+    /// the llvm baseline tier, None if disabled
+    pub(crate) baseline_context: Option<Arc<inkwell::context::Context>>,
+    pub(crate) baseline_module: Option<Arc<inkwell::module::Module<'static>>>,
     pub(crate) baseline_engine: Option<Arc<inkwell::execution_engine::ExecutionEngine<'static>>>,
 }
 
@@ -209,59 +380,90 @@ unsafe impl Sync for Runtime {}
 unsafe impl Send for Runtime {}
 
 impl Runtime {
+    /// create a runtime with the default options
     pub fn new() -> Arc<Self> {
+        Self::with_options(Default::default()).expect("the default options are valid")
+    }
+
+    /// create a runtime builder
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::new()
+    }
+
+    /// return a RangeError if a stack size is zero or too large
+    pub fn with_options(options: RuntimeOptions) -> Result<Arc<Self>, crate::error::Error> {
+        options.validate()?;
+
         // allocate without writing on stack to prevent stackoverflow
         let stack = unsafe {
-            let ptr = std::alloc::alloc_zeroed(Layout::new::<[JValue; DEFAULT_STACK_SIZE]>())
-                as *mut [JValue; DEFAULT_STACK_SIZE];
-            Box::from_raw(ptr)
+            let ptr = std::alloc::alloc_zeroed(Layout::array::<JValue>(options.stack_size).unwrap())
+                as *mut JValue;
+            Box::from_raw(std::slice::from_raw_parts_mut(ptr, options.stack_size))
         };
         let op_stack = unsafe {
-            let ptr = std::alloc::alloc_zeroed(Layout::new::<[JValue; DEFAULT_STACK_SIZE]>())
-                as *mut [JValue; DEFAULT_STACK_SIZE];
-            Box::from_raw(ptr)
+            let ptr = std::alloc::alloc_zeroed(
+                Layout::array::<JValue>(options.operational_stack_size).unwrap(),
+            ) as *mut JValue;
+            Box::from_raw(std::slice::from_raw_parts_mut(
+                ptr,
+                options.operational_stack_size,
+            ))
         };
 
         let (worker_send, worker_recv) = crossbeam_channel::unbounded();
 
-        static mut LLVM_TARGET_INIT: bool = false;
+        let (baseline_context, baseline_module, baseline_engine) = if options.baseline_jit {
+            static mut LLVM_TARGET_INIT: bool = false;
 
-        if !unsafe { LLVM_TARGET_INIT } {
-            inkwell::targets::Target::initialize_native(&Default::default())
-                .expect("failed to initialize llvm");
-            unsafe { LLVM_TARGET_INIT = true };
-        }
+            if !unsafe { LLVM_TARGET_INIT } {
+                inkwell::targets::Target::initialize_native(&Default::default())
+                    .expect("failed to initialize llvm");
+                unsafe { LLVM_TARGET_INIT = true };
+            }
 
-        let baseline_context = inkwell::context::Context::create();
-        let baseline_module: inkwell::module::Module =
-            unsafe { std::mem::transmute(baseline_context.create_module("baseline")) };
-        let baseline_engine = unsafe {
-            std::mem::transmute(
-                baseline_module
-                    .create_jit_execution_engine(inkwell::OptimizationLevel::None)
-                    .unwrap(),
+            let baseline_context = inkwell::context::Context::create();
+            let baseline_module: inkwell::module::Module =
+                unsafe { std::mem::transmute(baseline_context.create_module("baseline")) };
+            let baseline_engine = unsafe {
+                std::mem::transmute(
+                    baseline_module
+                        .create_jit_execution_engine(inkwell::OptimizationLevel::None)
+                        .unwrap(),
+                )
+            };
+            (
+                Some(Arc::new(baseline_context)),
+                Some(Arc::new(baseline_module)),
+                Some(Arc::new(baseline_engine)),
             )
+        } else {
+            (None, None, None)
         };
 
         let runtime = Arc::new(Self {
             weak_ref:None,
 
-            strict_mode: false,
+            strict_mode: options.strict_mode,
 
             parser_globals: swc_common::Globals::new(),
             function_builder_context: FunctionBuilderContext::new(),
 
             obj_field_names: RwLock::new(crate::utils::string_interner::INTERNER.clone()),
             dynamic_var_names: RwLock::new(crate::utils::string_interner::INTERNER.clone()),
+            symbol_descriptions: Default::default(),
+            symbol_registry: Default::default(),
             dynamic_var_suffix: None,
 
             stack: stack,
             operational_stack: op_stack,
+            host_stack_top: None,
 
             object_allocator: Default::default(),
             string_allocator: Default::default(),
             bigint_allocator: Default::default(),
 
+            heap: Default::default(),
+
             constants: vec![],
             regexs: vec![],
             strings: StringInterner::new(),
@@ -269,6 +471,8 @@ impl Runtime {
 
             functions: vec![],
             classes: vec![],
+            private_brands: 0,
+            natives: Default::default(),
             templates: vec![],
             global: JValue::UNDEFINED,
             global_this: unsafe {
@@ -278,23 +482,53 @@ impl Runtime {
             prototypes: bultins::BuiltinPrototypes::zero(),
 
             new_target: JValue::UNDEFINED,
-            import_meta: JValue::UNDEFINED,
+            import_metas: Default::default(),
+            import_meta_hook: None,
 
             import_resolver: None,
+            import_map: None,
+            module_url: None,
             modules: Default::default(),
+            module_map: Default::default(),
+            linking_module: None,
+            unresolved_imports: Vec::new(),
+            module_sources: Default::default(),
+            module_fetches: Vec::new(),
+            async_module_count: 0,
+            module_evaluation_depth: 0,
+            settled_modules: Vec::new(),
             exported_variables: Default::default(),
 
             futures: Vec::new(),
             async_stacks: Vec::new(),
 
+            microtasks: VecDeque::new(),
+            running_microtasks: Vec::new(),
+            awaited_futures: Vec::new(),
+            promise_wakers: Vec::new(),
+            unhandled_rejections: Default::default(),
+            rejection_handler: None,
+            exception_handler: None,
+            uncaught_exception: None,
+            performing_microtasks: false,
+
+            clock: if options.virtual_clock {
+                event_loop::Clock::Virtual(std::time::Duration::ZERO)
+            } else {
+                event_loop::Clock::Real(std::time::Instant::now())
+            },
+            timers: Default::default(),
+
             finalize_registry: Default::default(),
+            interrupt: Default::default(),
             user_owned: Default::default(),
 
             worker_task_sender: worker_send,
+            worker_threads: options.worker_threads,
 
-            baseline_context: Arc::new(baseline_context),
-            baseline_engine: Some(Arc::new(baseline_engine)),
-            baseline_module: Arc::new(baseline_module),
+            baseline_context: baseline_context,
+            baseline_engine: baseline_engine,
+            baseline_module: baseline_module,
         });
 
         runtime.to_mut().weak_ref = Some(Arc::downgrade(&runtime));
@@ -304,14 +538,12 @@ impl Runtime {
 
         runtime.to_mut().global = runtime.global_this.into();
 
-        runtime.to_mut().import_meta = runtime.create_object().into();
-
         // allocate builtin prototypes
         runtime.to_mut().prototypes.init(runtime.to_mut());
 
         let weak_rt = Arc::downgrade(&runtime);
 
-        for _ in 0..NUM_WORKER_THREAD {
+        for _ in 0..options.worker_threads {
             let recv = worker_recv.clone();
 
             let weak_rt = weak_rt.clone();
@@ -339,12 +571,27 @@ impl Runtime {
 
         runtime.clone().attach();
         // enable ecma features
-        // todo: let user decide to enable or not
-        crate::ecma::enable(&runtime);
+        crate::ecma::enable(&runtime, options.builtins);
+
+        runtime
+            .global_this
+            .insert_property_builtin(NAMES["globalThis"], runtime.global_this.into());
 
         Runtime::deattach();
 
-        runtime
+        Ok(runtime)
+    }
+
+    /// run a task on a worker thread,
+    /// the task is run on the current thread if the runtime has no worker
+    pub(crate) fn spawn_worker_task(&self, task: Box<dyn FnOnce() + Sync + Send>) {
+        if self.worker_threads == 0 {
+            (task)();
+        } else {
+            self.worker_task_sender
+                .send(task)
+                .expect("failed to spawn task on worker thread");
+        }
     }
 
     #[inline]
@@ -366,6 +613,16 @@ impl Runtime {
         })
     }
 
+    /// return the runtime attached on the current thread
+    pub(crate) fn try_current() -> Result<Arc<Runtime>, crate::error::Error> {
+        JS_RUNTIME.with(|runtime| match runtime {
+            Some(r) => Ok(r.clone()),
+            None => Err(crate::error::Error::RuntimeError(
+                "js runtime not attached on the current thread".to_string(),
+            )),
+        })
+    }
+
     #[inline]
     pub fn current() -> Arc<Runtime> {
         JS_RUNTIME.with(|runtime| {
@@ -386,36 +643,123 @@ impl Runtime {
         filename: &str,
         script: &str,
     ) -> Result<JValue, crate::error::Error> {
-        use swc_common::{FileName, SourceFile};
+        let script = self.parse_module(filename, script)?;
+        self.run_module(script, filename)
+    }
 
-        let src = SourceFile::new(
-            FileName::Custom(filename.to_string()),
-            false,
-            FileName::Anon,
-            script.to_string(),
-            swc_common::BytePos(1),
-        );
-        let mut v = Vec::new();
-        let re = swc_ecmascript::parser::parse_file_as_module(
-            &src,
-            swc_ecmascript::parser::Syntax::Es(swc_ecmascript::parser::EsConfig {
-                jsx: false,
-                fn_bind: true,
-                decorators: true,
-                decorators_before_export: true,
-                export_default_from: true,
-                import_assertions: true,
-                private_in_object: true,
-                allow_super_outside_method: false,
-                allow_return_outside_function: false,
-            }),
-            swc_ecmascript::ast::EsVersion::Es2022,
-            None,
-            &mut v,
-        );
+    /// execute the source text as a classic script.
+    ///
+    /// the script is strict only if it starts with a "use strict" directive
+    /// or the runtime is in strict mode, `this` is the global object and
+    /// top level `var` and function declarations become properties of the global object.
+    pub fn execute_script(
+        self: Arc<Self>,
+        filename: &str,
+        script: &str,
+    ) -> Result<JValue, crate::error::Error> {
+        let mut script = self.compile(filename, script)?;
+        script.run(&self)
+    }
+
+    /// execute the source text as an ES module.
+    ///
+    /// the module is always strict and `this` is undefined at the top level.
+    /// if the module or the modules it imports await at the top level,
+    /// a promise settled once the module graph is evaluated is returned.
+    pub fn execute_module(
+        self: Arc<Self>,
+        filename: &str,
+        script: &str,
+    ) -> Result<JValue, crate::error::Error> {
+        let module = self.parse_module(filename, script)?;
+        self.with_global(true, JValue::UNDEFINED, |rt| rt.run_module(module, filename))
+    }
+
+    /// execute TypeScript source text as an ES module.
+    ///
+    /// types are erased without being checked,
+    /// imports only used as types are removed as the TypeScript compiler does.
+    pub fn execute_typescript(
+        self: Arc<Self>,
+        filename: &str,
+        script: &str,
+    ) -> Result<JValue, crate::error::Error> {
+        let module = self.parse_typescript_module(filename, script)?;
+        self.with_global(true, JValue::UNDEFINED, |rt| rt.run_module(module, filename))
+    }
+
+    /// compile the source text as a classic script without running it,
+    /// the script can then be run any number of times on this runtime.
+    pub fn compile(
+        self: &Arc<Self>,
+        filename: &str,
+        source: &str,
+    ) -> Result<Script, crate::error::Error> {
+        let mut script = self.parse_script(filename, source)?;
+
+        let strict = self.strict_mode || global_scope::has_use_strict(&script.body);
+
+        let globals = global_scope::hoist_global_declarations(&mut script.body)
+            .into_iter()
+            .map(|n| n.to_string())
+            .collect();
+
+        let module = swc_ecmascript::ast::Module {
+            span: script.span,
+            body: script
+                .body
+                .into_iter()
+                .map(swc_ecmascript::ast::ModuleItem::Stmt)
+                .collect(),
+            shebang: script.shebang,
+        };
+
+        let (bytecodes, op_stack) = self.clone().build_module(module, filename)?;
+
+        Ok(Script::new(
+            self,
+            filename,
+            Script::hash_source(source),
+            bytecodes,
+            op_stack,
+            strict,
+            globals,
+        ))
+    }
+
+    /// declare the global variables of a script on the global object
+    pub(crate) fn declare_globals(&self, names: &[String]) {
+        use bultins::object_property::{PropFlag, ToProperyKey};
+
+        for name in names {
+            let key = name.to_key(self);
+            if !self.global_this.has_owned_property(key) {
+                self.global_this.insert_property(
+                    key,
+                    JValue::UNDEFINED,
+                    PropFlag::ENUMERABLE | PropFlag::WRITABLE,
+                );
+            }
+        }
+    }
+
+    /// run with the given strictness and top level `this`
+    pub(crate) fn with_global<R>(
+        self: &Arc<Self>,
+        strict: bool,
+        global: JValue,
+        f: impl FnOnce(Arc<Self>) -> R,
+    ) -> R {
+        let old_strict = self.strict_mode;
+        let old_global = self.global;
+        self.to_mut().strict_mode = strict;
+        self.to_mut().global = global;
 
-        let script = re.unwrap();
-        self.run_module(script)
+        let re = f(self.clone());
+
+        self.to_mut().strict_mode = old_strict;
+        self.to_mut().global = old_global;
+        re
     }
 
     /// optimize the sourse code before executing
@@ -428,38 +772,16 @@ impl Runtime {
         filename: &str,
         script: &str,
     ) -> Result<JValue, crate::error::Error> {
-        use swc_common::{FileName, SourceFile};
-        use swc_ecmascript::visit::Fold;
-
-        let src = SourceFile::new(
-            FileName::Custom(filename.to_string()),
-            false,
-            FileName::Anon,
-            script.to_string(),
-            swc_common::BytePos(1),
-        );
-        let mut v = Vec::new();
-        let re = swc_ecmascript::parser::parse_file_as_module(
-            &src,
-            swc_ecmascript::parser::Syntax::Es(swc_ecmascript::parser::EsConfig {
-                jsx: false,
-                fn_bind: true,
-                decorators: true,
-                decorators_before_export: true,
-                export_default_from: true,
-                import_assertions: true,
-                private_in_object: true,
-                allow_super_outside_method: false,
-                allow_return_outside_function: false,
-            }),
-            swc_ecmascript::ast::EsVersion::Es2022,
-            None,
-            &mut v,
-        );
+        let module = self.parse_module(filename, script)?;
+        let module = self.optimize_module(module);
+        self.run_module(module, filename)
+    }
 
-        let module = re.unwrap();
+    /// run the resolver and the simplifier on the module
+    fn optimize_module(&self, module: swc_ecmascript::ast::Module) -> swc_ecmascript::ast::Module {
+        use swc_ecmascript::visit::Fold;
 
-        let module = swc_common::GLOBALS.set(&self.parser_globals, || {
+        swc_common::GLOBALS.set(&self.parser_globals, || {
             let unresolved_mark = swc_common::Mark::new();
             let top_level_mark = swc_common::Mark::new();
 
@@ -480,16 +802,177 @@ impl Runtime {
             );
 
             r.fold_module(module)
+        })
+    }
+
+    /// parse the source text as a module
+    fn parse_module(
+        &self,
+        filename: &str,
+        script: &str,
+    ) -> Result<swc_ecmascript::ast::Module, crate::error::Error> {
+        Self::parse(filename, script, |src, errors| {
+            swc_ecmascript::parser::parse_file_as_module(
+                src,
+                Self::parser_syntax(false),
+                swc_ecmascript::ast::EsVersion::Es2022,
+                None,
+                errors,
+            )
+        })
+    }
+
+    /// parse the source text as a classic script
+    fn parse_script(
+        &self,
+        filename: &str,
+        script: &str,
+    ) -> Result<swc_ecmascript::ast::Script, crate::error::Error> {
+        Self::parse(filename, script, |src, errors| {
+            swc_ecmascript::parser::parse_file_as_script(
+                src,
+                Self::parser_syntax(false),
+                swc_ecmascript::ast::EsVersion::Es2022,
+                None,
+                errors,
+            )
+        })
+    }
+
+    /// parse TypeScript source text as a module with type only imports removed
+    pub(crate) fn parse_typescript_module(
+        &self,
+        filename: &str,
+        script: &str,
+    ) -> Result<swc_ecmascript::ast::Module, crate::error::Error> {
+        let mut module = Self::parse(filename, script, |src, errors| {
+            swc_ecmascript::parser::parse_file_as_module(
+                src,
+                Self::parser_syntax(true),
+                swc_ecmascript::ast::EsVersion::Es2022,
+                None,
+                errors,
+            )
+        })?;
+        swc_common::GLOBALS.set(&self.parser_globals, || {
+            typescript::elide_type_imports(&mut module)
         });
-        self.run_module(module)
+        Ok(module)
+    }
+
+    fn parser_syntax(typescript: bool) -> swc_ecmascript::parser::Syntax {
+        if typescript {
+            return swc_ecmascript::parser::Syntax::Typescript(
+                swc_ecmascript::parser::TsConfig {
+                    decorators: true,
+                    ..Default::default()
+                },
+            );
+        }
+        swc_ecmascript::parser::Syntax::Es(swc_ecmascript::parser::EsConfig {
+            jsx: false,
+            fn_bind: true,
+            decorators: true,
+            decorators_before_export: true,
+            export_default_from: true,
+            import_assertions: true,
+            private_in_object: true,
+            allow_super_outside_method: false,
+            allow_return_outside_function: false,
+        })
+    }
+
+    /// run the parser on the source text,
+    ///
+    /// returns a SyntaxError with every error collected by the parser
+    fn parse<T>(
+        filename: &str,
+        script: &str,
+        parser: impl FnOnce(
+            &swc_common::SourceFile,
+            &mut Vec<swc_ecmascript::parser::error::Error>,
+        ) -> swc_ecmascript::parser::PResult<T>,
+    ) -> Result<T, crate::error::Error> {
+        use swc_common::{FileName, SourceFile};
+
+        let src = SourceFile::new(
+            FileName::Custom(filename.to_string()),
+            false,
+            FileName::Anon,
+            script.to_string(),
+            swc_common::BytePos(1),
+        );
+        let mut v = Vec::new();
+        let re = parser(&src, &mut v);
+
+        match re {
+            Ok(m) if v.is_empty() => return Ok(m),
+            Ok(_) => {}
+            Err(e) => v.push(e),
+        };
+
+        let diagnostics = v
+            .into_iter()
+            .map(|e| {
+                let span = e.span();
+                // the source file starts at byte position 1
+                let start = span.lo.0.saturating_sub(src.start_pos.0);
+                let end = span.hi.0.saturating_sub(src.start_pos.0);
+                crate::error::Diagnostic::new(
+                    filename,
+                    script,
+                    start..end,
+                    e.into_kind().msg().to_string(),
+                )
+            })
+            .collect();
+
+        Err(crate::error::Error::SyntaxError(
+            crate::error::SyntaxError::from_diagnostics(diagnostics),
+        ))
     }
 
+    /// link and evaluate an entry module,
+    /// it replaces the module of the same url in the module map
     fn run_module(
         self: Arc<Self>,
         module: swc_ecmascript::ast::Module,
+        url: &str,
     ) -> Result<JValue, crate::error::Error> {
+        let key = if url.is_empty() { None } else { Some(url.to_string()) };
+        let id = self.clone().link_parsed(key, url, module, true)?;
+        self.evaluate_module(id, None)
+    }
+
+    /// translate the module into optimized bytecodes,
+    /// imports are resolved against the url and executed while building
+    fn build_module(
+        self: Arc<Self>,
+        module: swc_ecmascript::ast::Module,
+        url: &str,
+    ) -> Result<(Vec<crate::bytecodes::OpCode>, usize), crate::error::Error> {
+        let url = if url.is_empty() { None } else { Some(url.to_string()) };
+        let referrer = std::mem::replace(&mut self.to_mut().module_url, url);
+
+        let re = self.clone().translate_module(module);
+
+        self.to_mut().module_url = referrer;
+        re
+    }
+
+    fn translate_module(
+        self: Arc<Self>,
+        module: swc_ecmascript::ast::Module,
+    ) -> Result<(Vec<crate::bytecodes::OpCode>, usize), crate::error::Error> {
         let mut builder = crate::bytecodes::bytecode_builder::FunctionBuilder::new_with_context(self.clone(), self.function_builder_context.clone(), false, false, 0);
 
+        // the bindings of a module are only visible to the module
+        builder.ctx.new_context();
+
+        if self.linking_module.is_some() {
+            self.declare_module_exports(&mut builder, &module.body);
+        }
+
         for i in module.body {
             match i {
                 swc_ecmascript::ast::ModuleItem::Stmt(s) => {
@@ -509,107 +992,24 @@ impl Runtime {
                                 // todo: remove split
                                 let n = self.get_dynamic_var_name(*name).split('@').next().unwrap();
 
-                                self.to_mut().exported_variables.insert(
-                                    n.to_string(),
-                                    ExportVariable::Import {
+                                // export * does not export the default
+                                if n == "default" {
+                                    continue;
+                                }
+
+                                // local exports are not replaced by export *
+                                self.to_mut()
+                                    .exported_variables
+                                    .entry(n.to_string())
+                                    .or_insert(ExportVariable::Import {
                                         // the id of module
                                         id: id,
                                         original_name: *name,
-                                    },
-                                );
+                                    });
                             }
                         }
                         swc_ecmascript::ast::ModuleDecl::ExportDecl(e) => {
-                            match &e.decl {
-                                swc_ecmascript::ast::Decl::Class(c) => {
-                                    // a name with @moduleID suffix
-                                    let id = self.regester_dynamic_var_name(&c.ident.sym);
-
-                                    self.to_mut()
-                                        .exported_variables
-                                        .insert(c.ident.sym.to_string(), ExportVariable::Let(id));
-                                }
-                                swc_ecmascript::ast::Decl::Fn(f) => {
-                                    // a name with @moduleID suffix
-                                    let id = self.regester_dynamic_var_name(&f.ident.sym);
-
-                                    self.to_mut()
-                                        .exported_variables
-                                        .insert(f.ident.sym.to_string(), ExportVariable::Let(id));
-                                }
-                                swc_ecmascript::ast::Decl::TsEnum(t) => {
-                                    // a name with @moduleID suffix
-                                    let id = self.regester_dynamic_var_name(&t.id.sym);
-
-                                    self.to_mut()
-                                        .exported_variables
-                                        .insert(t.id.sym.to_string(), ExportVariable::Let(id));
-                                }
-                                swc_ecmascript::ast::Decl::TsInterface(t) => {
-                                    // a name with @moduleID suffix
-                                    let id = self.regester_dynamic_var_name(&t.id.sym);
-
-                                    self.to_mut()
-                                        .exported_variables
-                                        .insert(t.id.sym.to_string(), ExportVariable::Let(id));
-                                }
-                                swc_ecmascript::ast::Decl::TsTypeAlias(t) => {
-                                    // a name with @moduleID suffix
-                                    let id = self.regester_dynamic_var_name(&t.id.sym);
-
-                                    self.to_mut()
-                                        .exported_variables
-                                        .insert(t.id.sym.to_string(), ExportVariable::Let(id));
-                                }
-                                swc_ecmascript::ast::Decl::TsModule(t) => {
-                                    let s = match &t.id {
-                                        swc_ecmascript::ast::TsModuleName::Ident(i) => {
-                                            i.sym.to_string()
-                                        }
-                                        swc_ecmascript::ast::TsModuleName::Str(s) => {
-                                            s.value.to_string()
-                                        }
-                                    };
-                                    // a name with @moduleID suffix
-                                    let id = self.regester_dynamic_var_name(&s);
-
-                                    self.to_mut()
-                                        .exported_variables
-                                        .insert(s, ExportVariable::Let(id));
-                                }
-
-                                swc_ecmascript::ast::Decl::Var(v) => {
-                                    for i in &v.decls {
-                                        let names = builder.pat_to_names(&i.name);
-                                        names.iter().for_each(|n| {
-                                            // a name with @moduleID suffix
-                                            let id = self.regester_dynamic_var_name(n);
-
-                                            match v.kind {
-                                                swc_ecmascript::ast::VarDeclKind::Const => {
-                                                    self.to_mut().exported_variables.insert(
-                                                        n.to_string(),
-                                                        ExportVariable::Const(id),
-                                                    );
-                                                }
-                                                swc_ecmascript::ast::VarDeclKind::Let => {
-                                                    self.to_mut().exported_variables.insert(
-                                                        n.to_string(),
-                                                        ExportVariable::Let(id),
-                                                    );
-                                                }
-                                                swc_ecmascript::ast::VarDeclKind::Var => {
-                                                    self.to_mut().exported_variables.insert(
-                                                        n.to_string(),
-                                                        ExportVariable::Var(id),
-                                                    );
-                                                }
-                                            }
-                                        });
-                                    }
-                                }
-                            };
-
+                            // the exported variables are declared by declare_module_exports
                             builder.translate_statement(
                                 None,
                                 &swc_ecmascript::ast::Stmt::Decl(e.decl),
@@ -653,9 +1053,8 @@ impl Runtime {
                                         ),
                                     )?;
                                 }
-                                swc_ecmascript::ast::DefaultDecl::TsInterfaceDecl(_i) => {
-                                    todo!()
-                                }
+                                // types are erased
+                                swc_ecmascript::ast::DefaultDecl::TsInterfaceDecl(_i) => {}
                             }
                         }
                         swc_ecmascript::ast::ModuleDecl::ExportDefaultExpr(e) => {
@@ -693,6 +1092,9 @@ impl Runtime {
                                 for i in &n.specifiers {
                                     match i {
                                         swc_ecmascript::ast::ExportSpecifier::Default(d) => {
+                                            self.to_mut()
+                                                .unresolved_imports
+                                                .push((module_id, "default".to_string()));
                                             self.to_mut().exported_variables.insert(
                                                 d.exported.sym.to_string(),
                                                 ExportVariable::ImportDefault { id: module_id },
@@ -708,6 +1110,10 @@ impl Runtime {
                                                 }
                                             };
 
+                                            self.to_mut()
+                                                .unresolved_imports
+                                                .push((module_id, name.clone()));
+
                                             // variable name in Module without suffix
                                             let old = self.to_mut().dynamic_var_suffix.take();
                                             let id = self.regester_dynamic_var_name(&name);
@@ -751,40 +1157,13 @@ impl Runtime {
                                     }
                                 }
                             } else {
+                                // local exports are declared by declare_module_exports
                                 for i in &n.specifiers {
                                     match i {
                                         swc_ecmascript::ast::ExportSpecifier::Default(_d) => {
                                             unimplemented!("export default without module source.")
                                         }
-                                        swc_ecmascript::ast::ExportSpecifier::Named(n) => {
-                                            let mut name = match &n.orig {
-                                                swc_ecmascript::ast::ModuleExportName::Ident(i) => {
-                                                    i.sym.to_string()
-                                                }
-                                                swc_ecmascript::ast::ModuleExportName::Str(s) => {
-                                                    s.value.to_string()
-                                                }
-                                            };
-
-                                            // variable name with suffix
-                                            let id = self.regester_dynamic_var_name(&name);
-
-                                            if let Some(n) = &n.exported {
-                                                name = match n{
-                                                    swc_ecmascript::ast::ModuleExportName::Ident(i) => {
-                                                        i.sym.to_string()
-                                                    },
-                                                    swc_ecmascript::ast::ModuleExportName::Str(s) => {
-                                                        s.value.to_string()
-                                                    }
-                                                };
-                                            };
-
-                                            // export variable
-                                            self.to_mut()
-                                                .exported_variables
-                                                .insert(name, ExportVariable::Var(id));
-                                        }
+                                        swc_ecmascript::ast::ExportSpecifier::Named(_n) => {}
                                         swc_ecmascript::ast::ExportSpecifier::Namespace(_n) => {
                                             // no module is imported
                                             unimplemented!(
@@ -803,6 +1182,9 @@ impl Runtime {
                             for i in &i.specifiers {
                                 match i {
                                     swc_ecmascript::ast::ImportSpecifier::Default(d) => {
+                                        self.to_mut()
+                                            .unresolved_imports
+                                            .push((module_id, "default".to_string()));
                                         let key = self.regester_dynamic_var_name(&d.local.sym);
 
                                         self.to_mut()
@@ -823,27 +1205,27 @@ impl Runtime {
                                             n.local.sym.as_ref()
                                         };
 
-                                        let key = self
+                                        // a module in a cycle is not translated yet,
+                                        // so the name is checked once the graph is linked
+                                        self.to_mut()
+                                            .unresolved_imports
+                                            .push((module_id, import_name.to_string()));
+
+                                        // the exported name without suffix
+                                        let original_name = self
                                             .to_mut()
                                             .dynamic_var_names
                                             .write()
                                             .get_or_intern(import_name)
                                             as u32;
 
-                                        if !self.modules[module_id.0 as usize]
-                                            .exports
-                                            .contains_key(&key)
-                                        {
-                                            return Err(crate::error::Error::ImportError(format!("The requested module '{}' does not provide an export named '{}'", self.modules[module_id.0 as usize].name.as_str(), import_name)));
-                                        };
-
                                         let key = self.regester_dynamic_var_name(&n.local.sym);
 
                                         self.to_mut().variables.insert(
                                             key,
                                             Variable::Import {
                                                 id: module_id,
-                                                original_name: key,
+                                                original_name,
                                             },
                                         );
                                     }
@@ -858,38 +1240,77 @@ impl Runtime {
                             }
                         }
                         swc_ecmascript::ast::ModuleDecl::TsExportAssignment(_t) => {
-                            todo!()
-                        }
-                        swc_ecmascript::ast::ModuleDecl::TsImportEquals(_i) => {
-                            todo!()
+                            return Err(crate::error::Error::SyntaxError(
+                                "Export assignment cannot be used when targeting ECMAScript modules."
+                                    .to_string()
+                                    .into(),
+                            ));
                         }
-                        swc_ecmascript::ast::ModuleDecl::TsNamespaceExport(_n) => {
-                            todo!()
+                        // the exported name is declared by declare_module_exports
+                        swc_ecmascript::ast::ModuleDecl::TsImportEquals(i) => {
+                            builder.translate_import_equals(&i)?;
                         }
+                        // export as namespace only declares a type
+                        swc_ecmascript::ast::ModuleDecl::TsNamespaceExport(_n) => {}
                     }
                 }
             };
         }
 
+        builder.ctx.close_context();
+
         let bytecodes = builder.bytecode;
         let op_stack = builder.ctx.max_stack_offset() as usize;
 
         let bytecodes = crate::baseline::optimize(bytecodes);
 
-        let mut cl = crate::interpreter::clousure::Clousure::create(&bytecodes);
+        Ok((bytecodes, op_stack))
+    }
+
+    /// run the global code
+    pub(crate) fn run_clousure(
+        self: Arc<Self>,
+        cl: &mut crate::interpreter::clousure::Clousure,
+        op_stack: usize,
+    ) -> Result<JValue, crate::error::Error> {
+        let op_stack = self.to_mut().stack[op_stack..].as_mut_ptr();
+        self.run_clousure_on(cl, op_stack)
+    }
+
+    /// run the global code with the operational stack starting at op_stack,
+    /// used to run a module while other code is running
+    fn run_clousure_on(
+        self: Arc<Self>,
+        cl: &mut crate::interpreter::clousure::Clousure,
+        op_stack: *mut JValue,
+    ) -> Result<JValue, crate::error::Error> {
+        let op_stack = self.stack_from(op_stack);
+
+        self.enter_execution();
+
         let re = cl.run(
             &self,
-            self.to_mut().stack.as_mut_slice(),
-            &mut self.to_mut().stack.as_mut_slice()[op_stack..],
+            &mut self.to_mut().stack[..],
+            op_stack,
             None,
             None,
             self.global,
             &[],
         );
 
+        let terminated = self.interrupt.is_terminating();
+        self.interrupt.exit();
+
+        if terminated {
+            return Err(crate::error::Error::Terminated);
+        }
+
+        let re = re.map_err(Self::error_from_value);
+        let checkpoint = self.microtask_checkpoint(&re);
+
         /*
         let mut intpr =
-            crate::interpreter::Interpreter::global(&self, self.to_mut().stack.as_mut_slice(), op_stack);
+            crate::interpreter::Interpreter::global(&self, &mut self.to_mut().stack[..], op_stack);
 
         let re = intpr.run(self.global, &[], &bytecodes);
         */
@@ -897,74 +1318,242 @@ impl Runtime {
         // finish all the async tasks
         self.finish_async();
 
-        match re {
-            Ok(v) => Ok(v),
-            Err(e) => return Err(crate::error::Error::Value(e)),
+        let v = re?;
+        checkpoint?;
+        Ok(v)
+    }
+
+    /// the rest of the stack the pointer points into
+    pub(crate) fn stack_from(&self, ptr: *mut JValue) -> &'static mut [JValue] {
+        for stack in [&self.stack, &self.operational_stack] {
+            let range = stack.as_ptr_range();
+            if range.contains(&(ptr as *const JValue)) {
+                let len = unsafe { range.end.offset_from(ptr) } as usize;
+                return unsafe { std::slice::from_raw_parts_mut(ptr, len) };
+            }
         }
+        &mut []
     }
 
-    #[inline]
-    fn import(
-        self: Arc<Self>,
-        name: &str,
-        asserts: Option<&swc_ecmascript::ast::ObjectLit>,
-    ) -> Result<ModuleId, crate::error::Error> {
-        if self.import_resolver.is_none() {
-            return Err(crate::error::Error::ImportError(
-                "cannot import module: import resolver is not defined.".to_owned(),
-            ));
+    /// run the microtasks if no execution is running,
+    /// the completion is kept alive during the checkpoint
+    fn microtask_checkpoint(
+        &self,
+        completion: &Result<JValue, crate::error::Error>,
+    ) -> Result<(), crate::error::Error> {
+        if self.interrupt.is_running() {
+            return Ok(());
         }
+        let value = match completion {
+            Ok(v) | Err(crate::error::Error::Value(v)) => *v,
+            Err(_) => JValue::UNDEFINED,
+        };
 
-        let re = self
-            .import_resolver
-            .as_ref()
-            .unwrap()
-            .write()
-            .import(name, ImportAssertion::from(asserts));
-
-        let script = match re {
-            Ok(s) => s,
-            Err(e) => return Err(crate::error::Error::ImportError(e)),
+        self.user_own_value(value);
+        let re = self.run_microtasks();
+        self.user_drop_value(value);
+        re
+    }
+
+    /// convert a thrown value into an error,
+    /// errors raised by the engine are returned as is
+    pub(crate) fn error_from_value(e: JValue) -> crate::error::Error {
+        if let Some(obj) = e.as_object() {
+            if let Some(err) = obj.as_error() {
+                return err.clone();
+            }
+        }
+        crate::error::Error::Value(e)
+    }
+
+    /// convert an error into a thrown value, the runtime must be attached
+    pub(crate) fn value_from_error(e: crate::error::Error) -> JValue {
+        match e {
+            crate::error::Error::Value(v) => v,
+            e => e.into(),
+        }
+    }
+
+    /// the Arc holding this runtime
+    pub(crate) fn arc(&self) -> Arc<Self> {
+        self.weak_ref.as_ref().unwrap().upgrade().unwrap()
+    }
+
+    /// run f with the runtime attached on the current thread,
+    /// the previously attached runtime is restored after
+    pub(crate) fn with_attached<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = JS_RUNTIME.with(|runtime| runtime.clone());
+        let attached = match &previous {
+            Some(rt) => std::ptr::eq(rt.as_ref(), self),
+            None => false,
         };
+        if !attached {
+            self.arc().attach();
+        }
 
-        let old_strict = self.strict_mode;
-        self.to_mut().strict_mode = true;
+        let re = f();
 
-        let old_global = self.global;
-        self.to_mut().global = JValue::UNDEFINED;
+        if !attached {
+            match previous {
+                Some(rt) => rt.attach(),
+                None => Runtime::deattach(),
+            }
+        }
+        re
+    }
+
+    /// the stack a call from the host starts at,
+    /// the bottom of the operational stack unless re-entered from a native function
+    pub(crate) fn host_stack(&self) -> *mut JValue {
+        match self.host_stack_top {
+            // the frames below are still in use
+            Some(top) if self.interrupt.is_running() => top,
+            _ => self.operational_stack.as_ptr() as *mut JValue,
+        }
+    }
+
+    /// run a call from the host on the operational stack,
+    /// the runtime is attached on the current thread during the call
+    pub(crate) fn call_from_host<F>(&self, f: F) -> Result<JValue, crate::error::Error>
+    where
+        F: FnOnce(JSContext) -> Result<JValue, JValue>,
+    {
+        self.with_attached(|| {
+            self.enter_execution();
 
-        let mut oldsuffix = Some(format!("@{}", self.modules.len()));
-        std::mem::swap(&mut oldsuffix, &mut self.to_mut().dynamic_var_suffix);
+            let re = f(JSContext {
+                stack: self.host_stack(),
+                runtime: self,
+            });
 
-        // variables in module doesn't matter anyway, so we optimize it out
-        self.clone().execute_optimized(name, &script)?;
+            let terminated = self.interrupt.is_terminating();
+            self.interrupt.exit();
 
-        self.to_mut().global = old_global;
-        self.to_mut().strict_mode = old_strict;
+            if terminated {
+                return Err(crate::error::Error::Terminated);
+            }
+            let re = re.map_err(Self::error_from_value);
+            let checkpoint = self.microtask_checkpoint(&re);
 
-        // reverse to the oldsuffix
-        self.to_mut().dynamic_var_suffix = oldsuffix;
+            let v = re?;
+            checkpoint?;
+            Ok(v)
+        })
+    }
+
+    /// get a global variable or a property of the global object
+    pub fn get_global(&self, name: &str) -> Result<JValue, crate::error::Error> {
+        let key = self.dynamic_var_names.write().get_or_intern(name) as u32;
+
+        if !self.variables.contains_key(&key)
+            && !self
+                .global_this
+                .has_owned_property(bultins::object_property::ToProperyKey::to_key(
+                    &name, self,
+                ))
+        {
+            return Err(crate::error::Error::ReferenceError(format!(
+                "{} is not defined",
+                name
+            )));
+        }
+
+        self.call_from_host(|_ctx| self.get_variable(key))
+    }
+
+    /// set the resolver that loads the source text of imported modules
+    pub fn set_import_resolver<R>(&self, resolver: R)
+    where
+        R: ImportResolver + 'static,
+    {
+        self.set_async_import_resolver(SyncResolver(resolver));
+    }
+
+    /// set a resolver that loads modules without blocking,
+    /// modules not fetched ahead of evaluation are waited for on the current thread
+    pub fn set_async_import_resolver<R>(&self, resolver: R)
+    where
+        R: AsyncImportResolver + 'static,
+    {
+        self.to_mut().import_resolver = Some(Arc::new(RwLock::new(resolver)));
+    }
 
-        let mut exports = HashMap::default();
+    /// set the import map applied to specifiers before the import resolver
+    pub fn set_import_map(&self, map: ImportMap) {
+        self.to_mut().import_map = Some(map);
+    }
 
-        let mut e = HashMap::default();
-        std::mem::swap(&mut e, &mut self.to_mut().exported_variables);
+    #[inline]
+    fn import(
+        self: Arc<Self>,
+        name: &str,
+        asserts: Option<&swc_ecmascript::ast::ObjectLit>,
+    ) -> Result<ModuleId, crate::error::Error> {
+        let name = self.resolve_specifier(name, self.module_url.as_deref())?;
+        let id = self
+            .clone()
+            .link_module(&name, ImportAssertion::from(asserts))?;
 
-        for (name, v) in e {
-            // a name without @moduleID suffix
-            let key = self.to_mut().dynamic_var_names.write().get_or_intern(name) as u32;
-            exports.insert(key, v);
+        if let Some(parent) = self.linking_module {
+            self.to_mut().modules[parent.0 as usize].requested.push(id);
         }
+        Ok(id)
+    }
 
-        let default_export_key = NAMES["#default export"];
+    /// return a handle that can be sent to another thread to terminate the execution
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            state: self.interrupt.clone(),
+        }
+    }
 
-        self.to_mut().modules.push(Module {
-            name: name.to_owned(),
-            exports: exports,
-            default_export: self.get_variable(default_export_key.0).unwrap(),
-        });
+    /// set the number of loop iterations and function calls an execution may perform,
+    /// `None` removes the limit.
+    ///
+    /// the budget is refilled every time a script is executed,
+    /// when it is exhausted, the execution returns `Error::Terminated`.
+    pub fn set_execution_budget(&self, budget: Option<u64>) {
+        self.interrupt.set_budget(budget);
+    }
 
-        return Ok(ModuleId(self.modules.len() as u32 - 1));
+    /// the remaining budget of the current execution, `None` if unlimited
+    pub fn remaining_execution_budget(&self) -> Option<u64> {
+        self.interrupt.remaining()
+    }
+
+    /// consume one unit of the execution budget,
+    ///
+    /// called by the interpreter and the baseline jit at loop back edges and function calls
+    ///
+    /// pending out of memory errors are also raised here.
+    #[inline]
+    pub(crate) fn consume_fuel(&self) -> Result<(), JValue> {
+        if !self.interrupt.consume() {
+            return Err(crate::error::Error::Terminated.into());
+        }
+        if self.to_mut().heap.take_exhausted() {
+            return Err(crate::error::Error::RangeError(
+                "out of memory: heap limit exceeded".to_owned(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// called before an execution starts instead of `InterruptState::enter`,
+    /// an out of memory error left pending by host allocations is dropped
+    /// when the outermost execution starts
+    pub(crate) fn enter_execution(&self) {
+        if !self.interrupt.is_running() {
+            self.to_mut().heap.take_exhausted();
+        }
+        self.interrupt.enter();
+    }
+
+    /// the execution is being terminated, catch blocks must not be entered
+    #[inline]
+    pub(crate) fn is_terminating(&self) -> bool {
+        self.interrupt.is_terminating()
     }
 
     /// a lazy workaround to mutate runtime
@@ -973,9 +1562,84 @@ impl Runtime {
         unsafe { &mut *(self as *const Self as *mut Self) }
     }
 
+    /// set the maximum number of bytes the object, string and bigint allocators may acquire,
+    /// `None` removes the limit.
+    ///
+    /// when the heap reaches the limit, collections are forced until the values that
+    /// became unreachable are freed, a value is only freed by the third collection after
+    /// it is dropped as it ages through the generations of the collector.
+    /// if the heap is still full, the limit callback is asked to raise the limit,
+    /// otherwise a `RangeError` is thrown at the next loop iteration or function call.
+    ///
+    /// the allocators cannot fail, the allocation that exceeds the limit is still made.
+    /// the heap may therefore grow past the limit by what is allocated before the next
+    /// loop iteration or function call, e.g. one large string.
+    pub fn set_heap_limit(&self, limit: Option<usize>) {
+        self.to_mut().heap.set_limit(limit);
+    }
+
+    /// set the callback invoked when the heap limit is reached,
+    /// the callback may return a new limit or refuse by returning `None`.
+    pub fn set_heap_limit_callback<F>(&self, callback: F)
+    where
+        F: FnMut(usize, usize) -> Option<usize> + Send + Sync + 'static,
+    {
+        self.to_mut().heap.set_callback(Some(Box::new(callback)));
+    }
+
+    /// the number of bytes acquired by the allocators
+    pub fn heap_size(&self) -> usize {
+        self.heap.used()
+    }
+
+    /// the number of values the stack holds
+    pub fn stack_size(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// the number of values the operational stack holds
+    pub fn operational_stack_size(&self) -> usize {
+        self.operational_stack.len()
+    }
+
+    /// account size bytes of heap growth against the heap limit,
+    ///
+    /// `still_full` is checked after a forced collection,
+    /// the growth is no longer needed if the collection freed a slot.
+    fn reserve_heap<F>(&self, size: usize, still_full: F)
+    where
+        F: Fn(&Self) -> bool,
+    {
+        if self.heap.fits(size) {
+            self.to_mut().heap.acquire(size);
+            return;
+        }
+
+        // the heap reaches its limit, force collections until the objects
+        // that became unreachable since the last collection are freed
+        for _ in 0..GC_GENERATIONS {
+            unsafe { self.collect_garbage() };
+
+            if !still_full(self) {
+                return;
+            }
+        }
+
+        if self.to_mut().heap.raise(size) {
+            self.to_mut().heap.acquire(size);
+        } else {
+            self.to_mut().heap.exhaust(size);
+        }
+    }
+
     /// allocate object from the allocater
     #[inline]
     pub(crate) fn allocate_obj(&self) -> &'static mut JObjectInner {
+        if self.object_allocator.is_full() {
+            self.reserve_heap(self.object_allocator.growth_size(), |rt| {
+                rt.object_allocator.is_full()
+            });
+        }
         let inner = unsafe { self.to_mut().object_allocator.allocate(self.weak_ref.as_ref().unwrap().upgrade().unwrap()) };
         inner.__proto__ = None;
         inner
@@ -992,10 +1656,22 @@ impl Runtime {
         if s.len() == 0 {
             return JSString(std::ptr::null_mut());
         }
+        if !self.string_allocator.contains(s) {
+            // strings are never collected
+            self.reserve_heap(
+                string_allocator::StringAllocator::allocation_size(s),
+                |_| true,
+            );
+        }
         self.to_mut().string_allocator.allocate(s)
     }
 
     pub fn allocate_bigint(&self) -> &'static mut JSBigInt {
+        if self.bigint_allocator.is_full() {
+            self.reserve_heap(self.bigint_allocator.growth_size(), |rt| {
+                rt.bigint_allocator.is_full()
+            });
+        }
         self.to_mut().bigint_allocator.alloc()
     }
 
@@ -1030,6 +1706,55 @@ impl Runtime {
         unsafe { std::mem::transmute_copy(&s) }
     }
 
+    /// create a new unique symbol
+    pub fn new_symbol(&self, description: Option<&str>) -> JValue {
+        let id = self.obj_field_names.write().reserve() as u32;
+        self.to_mut()
+            .symbol_descriptions
+            .insert(id, description.map(|d| d.to_string()));
+        JValue::create_symbol(id)
+    }
+
+    /// a well known symbol by name, e.g. "iterator" for Symbol.iterator
+    pub fn well_known_symbol(&self, name: &str) -> Option<JValue> {
+        SYMBOLS.get(name).map(|key| JValue::create_symbol(key.0))
+    }
+
+    /// the symbol registered for a key, 20.4.2.2 Symbol.for
+    pub fn symbol_for(&self, key: &str) -> JValue {
+        if let Some(id) = self.symbol_registry.get(key) {
+            return JValue::create_symbol(*id);
+        }
+        let sym = self.new_symbol(Some(key));
+        self.to_mut()
+            .symbol_registry
+            .insert(key.to_string(), sym.as_symbol().unwrap());
+        sym
+    }
+
+    /// the key of a registered symbol, 20.4.2.6 Symbol.keyFor
+    pub fn symbol_key_for(&self, sym: JValue) -> Option<&str> {
+        let id = sym.as_symbol()?;
+        self.symbol_registry
+            .iter()
+            .find(|(_, s)| **s == id)
+            .map(|(k, _)| k.as_str())
+    }
+
+    /// the description of a symbol created by the runtime,
+    /// None if the symbol is well known or the id is a field name
+    pub(crate) fn symbol_description(&self, id: u32) -> Option<Option<&str>> {
+        self.symbol_descriptions.get(&id).map(|d| d.as_deref())
+    }
+
+    /// a new brand for the private names of a class evaluation, 6.2.12 Private Names
+    #[inline]
+    pub(crate) fn new_private_brand(&self) -> u32 {
+        let rt = self.to_mut();
+        rt.private_brands += 1;
+        rt.private_brands
+    }
+
     #[inline]
     pub(crate) fn register_template(&self, tpl: bultins::strings::Template) -> TemplateID {
         let id = self.templates.len();
@@ -1050,57 +1775,150 @@ impl Runtime {
     where
         F: Future<Output = Result<JValue, JValue>> + 'static,
     {
-        self.futures.push(Box::pin(future));
         bultins::promise::Promise::Pending {
-            id: AsyncId(self.futures.len() - 1),
+            id: self.spawn_task(future),
         }
     }
 
+    /// register a task polled by the runtime
+    pub(crate) fn spawn_task<F>(&self, future: F) -> AsyncId
+    where
+        F: Future<Output = Result<JValue, JValue>> + 'static,
+    {
+        let futures = &mut self.to_mut().futures;
+        futures.push(AsyncTask::Running(Box::pin(future)));
+        AsyncId(futures.len() - 1)
+    }
+
+    /// a future resolved with the result of the task,
+    /// the result is kept after the task completes
     pub fn get_future<'a>(
-        &'a mut self,
+        &'a self,
+        id: AsyncId,
+    ) -> impl Future<Output = Result<JValue, JValue>> + 'a {
+        std::future::poll_fn(move |cx| self.poll_future(id, cx))
+    }
+
+    pub(crate) fn poll_future(
+        &self,
         id: AsyncId,
-    ) -> Pin<&'a mut dyn Future<Output = Result<JValue, JValue>>> {
-        self.futures.get_mut(id.0 as usize).unwrap().as_mut()
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Result<JValue, JValue>> {
+        // the task is taken out while polling as it may register new tasks
+        let task = std::mem::replace(&mut self.to_mut().futures[id.0], AsyncTask::Polling);
+
+        let (task, p) = match task {
+            AsyncTask::Done(re) => (AsyncTask::Done(re), std::task::Poll::Ready(re)),
+            AsyncTask::Polling => (AsyncTask::Polling, std::task::Poll::Pending),
+            AsyncTask::Running(mut f) => match f.as_mut().poll(cx) {
+                std::task::Poll::Ready(re) => (AsyncTask::Done(re), std::task::Poll::Ready(re)),
+                std::task::Poll::Pending => (AsyncTask::Running(f), std::task::Poll::Pending),
+            },
+        };
+        self.to_mut().futures[id.0] = task;
+        p
     }
 
     #[inline]
     pub fn poll_async(&mut self, id: AsyncId) -> std::task::Poll<Result<JValue, JValue>> {
-        let f = self.get_future(id);
-        let handle = tokio::runtime::Handle::current();
-        let p = handle.block_on(async { futures::poll!(f) });
-        return p;
+        let mut cx = std::task::Context::from_waker(futures::task::noop_waker_ref());
+        self.poll_future(id, &mut cx)
+    }
+
+    /// create a native function returning a promise settled by the future,
+    /// the future is driven by the event loop.
+    ///
+    /// ```ignore
+    /// let fetch = runtime.create_async_function(|_this, args| async move {
+    ///     let body = fetch_url(args[0].to_string()).await?;
+    ///     Ok(JValue::create_string(body.into()))
+    /// });
+    /// runtime.declare_variable("fetch", fetch.into());
+    /// ```
+    pub fn create_async_function<F, Fut>(&self, func: F) -> JObject
+    where
+        F: Fn(JValue, Vec<JValue>) -> Fut + 'static,
+        Fut: Future<Output = Result<JValue, crate::error::Error>> + 'static,
+    {
+        self.create_native_function(move |ctx, this, args| {
+            // the arguments are owned by the task until it completes or is cancelled
+            let roots = TaskRoots::new(ctx.runtime, this, args);
+            let future = func(this, args.to_vec());
+
+            let p = ctx.runtime.to_mut().run_async(async move {
+                let re = future.await;
+                drop(roots);
+                re.map_err(Runtime::value_from_error)
+            });
+            Ok(JObject::with_promise(p).into())
+        })
+    }
+
+    /// cancel the running tasks,
+    /// the promises waiting for them are rejected with an AbortError
+    pub fn cancel_async_tasks(&self) {
+        self.with_attached(|| {
+            for i in 0..self.futures.len() {
+                if let AsyncTask::Running(_) = self.futures[i] {
+                    let e = crate::error::Error::AbortError("the task was cancelled".to_string());
+                    self.to_mut().futures[i] = AsyncTask::Done(Err(e.into()));
+                }
+            }
+        })
+    }
+
+    pub(crate) fn register_promise_waker(&self, promise: JObject, waker: &std::task::Waker) {
+        let wakers = &mut self.to_mut().promise_wakers;
+        if !wakers.iter().any(|(p, w)| *p == promise && w.will_wake(waker)) {
+            wakers.push((promise, waker.clone()));
+        }
+    }
+
+    /// wake the rust tasks waiting for the promise
+    pub(crate) fn wake_promise(&self, promise: JObject) {
+        self.to_mut().promise_wakers.retain(|(p, w)| {
+            if *p == promise {
+                w.wake_by_ref();
+                return false;
+            }
+            true
+        });
     }
 
+    /// poll every running task once on the current thread
     #[inline]
     pub fn finish_async(self: Arc<Self>) {
-        if self.futures.len() > 0 {
-            self.worker_task_sender
-                .clone()
-                .send(Box::new(move || {
-                    self.clone().attach();
-                    futures::executor::block_on(async {
-                        let mut i = 0;
-                        for f in &mut self.to_mut().futures {
-                            let p = futures::poll!(f.as_mut());
-                            if p.is_ready() {
-                                i += 1;
-                            };
-                            if i >= self.futures.len() {
-                                break;
-                            }
-                        }
-                    })
-                }))
-                .expect("failed to spawn task on worker thread");
+        let mut cx = std::task::Context::from_waker(futures::task::noop_waker_ref());
+        self.poll_tasks(&mut cx);
+    }
+
+    /// poll the running tasks, the waker is registered by the pending ones.
+    ///
+    /// return the number of completed and running tasks
+    pub(crate) fn poll_tasks(&self, cx: &mut std::task::Context) -> (usize, usize) {
+        let mut completed = 0;
+        let mut running = 0;
+
+        // tasks registered while polling are polled as well
+        let mut i = 0;
+        while i < self.futures.len() {
+            if let AsyncTask::Running(_) = self.futures[i] {
+                match self.poll_future(AsyncId(i), cx) {
+                    std::task::Poll::Ready(_) => completed += 1,
+                    std::task::Poll::Pending => running += 1,
+                }
+            }
+            i += 1;
         }
+        (completed, running)
     }
 
     #[inline]
     pub fn get_async_stack(&mut self, stack_size: usize) -> &'static mut [JValue] {
         let size = stack_size / JValue::SIZE;
-        let data =
-            unsafe { std::alloc::alloc(std::alloc::Layout::array::<u8>(stack_size).unwrap()) }
-                as *mut _;
+        // the stack is traced by the gc before it is written, zero is the number 0
+        let data = unsafe { std::alloc::alloc_zeroed(Layout::array::<JValue>(size).unwrap()) }
+            as *mut JValue;
         self.async_stacks
             .push(unsafe { std::slice::from_raw_parts(data, size) });
         return unsafe { std::slice::from_raw_parts_mut(data, size) };
@@ -1159,7 +1977,8 @@ impl Runtime {
     where
         F: Fn(JSContext, JValue, &[JValue]) -> Result<JValue, JValue> + 'static,
     {
-        let f = Arc::new(RwLock::new(func));
+        let f: snapshot::NativeFn = Arc::new(RwLock::new(func));
+        self.to_mut().natives.register_builtin(&f);
 
         let inner = self.allocate_obj();
         inner.__proto__ = Some(self.prototypes.function);
@@ -1189,13 +2008,14 @@ impl Runtime {
     where
         F: Fn(JSContext, JValue, &[JValue]) -> Result<JValue, JValue> + 'static,
     {
-        let f = Arc::new(RwLock::new(func));
+        let f: snapshot::NativeFn = Arc::new(RwLock::new(func));
+        self.to_mut().natives.register_builtin(&f);
 
         let prototype = self.create_object();
         let obj = self.create_object();
 
         obj.set_inner(bultins::object::JObjectValue::NativeFunction(f));
-        obj.insert_property_builtin(NAMES["__proto__"], self.prototypes.function.into());
+        obj.set_prototype(self.prototypes.function.into());
         obj.insert_property(
             NAMES["prototype"],
             prototype.into(),
@@ -1214,6 +2034,33 @@ impl Runtime {
         obj
     }
 
+    /// create a native function reconnected by the id when a snapshot is restored,
+    /// an id registered again refers to the new function.
+    ///
+    /// ```ignore
+    /// let runtime = Runtime::from_snapshot(options, &snapshot, |rt| {
+    ///     rt.register_native_function("app.log", log);
+    /// })?;
+    /// ```
+    pub fn register_native_function<F>(&self, id: &str, func: F) -> JObject
+    where
+        F: Fn(JSContext, JValue, &[JValue]) -> Result<JValue, JValue> + 'static,
+    {
+        let obj = self.create_native_function(func);
+        let f = obj.as_native_function().unwrap();
+        self.to_mut().natives.register(id.to_string(), f);
+        obj
+    }
+
+    /// the native functions created by `f` are named by the group and their order in it,
+    /// the ids of a group do not depend on the other builtins installed
+    pub(crate) fn builtin_group<T>(&self, group: &'static str, f: impl FnOnce() -> T) -> T {
+        self.to_mut().natives.builtins = Some((group, 0));
+        let re = f();
+        self.to_mut().natives.builtins = None;
+        re
+    }
+
     ////////////////////////////////////////////////////////////////////
     //          Class
     ////////////////////////////////////////////////////////////////////
@@ -1342,6 +2189,69 @@ impl Runtime {
         name
     }
 
+    #[inline]
+    pub(crate) fn bind_class_private_method(
+        &self,
+        class_id: ClassID,
+        name: u32,
+        func_id: FuncID,
+        is_static: bool,
+    ) {
+        let c = self.classes[class_id.0 as usize].clone().unwrap();
+        let c = c.to_mut();
+        let methods = if is_static {
+            &mut c.static_private_methods
+        } else {
+            &mut c.private_methods
+        };
+        methods.insert(name, self.get_function(func_id).unwrap());
+    }
+
+    #[inline]
+    pub(crate) fn bind_class_private_getter(
+        &self,
+        class_id: ClassID,
+        name: u32,
+        func_id: FuncID,
+        is_static: bool,
+    ) {
+        let c = self.classes[class_id.0 as usize].clone().unwrap();
+        let c = c.to_mut();
+        let get_setters = if is_static {
+            &mut c.static_private_get_setters
+        } else {
+            &mut c.private_get_setters
+        };
+        let f = self.get_function(func_id).unwrap();
+        get_setters.entry(name).or_insert((None, None)).0 = Some(f);
+    }
+
+    #[inline]
+    pub(crate) fn bind_class_private_setter(
+        &self,
+        class_id: ClassID,
+        name: u32,
+        func_id: FuncID,
+        is_static: bool,
+    ) {
+        let c = self.classes[class_id.0 as usize].clone().unwrap();
+        let c = c.to_mut();
+        let get_setters = if is_static {
+            &mut c.static_private_get_setters
+        } else {
+            &mut c.private_get_setters
+        };
+        let f = self.get_function(func_id).unwrap();
+        get_setters.entry(name).or_insert((None, None)).1 = Some(f);
+    }
+
+    /// bind the function that defines the private fields of the instances
+    #[inline]
+    pub(crate) fn bind_class_field_initializer(&self, class_id: ClassID, func_id: FuncID) {
+        let c = self.classes[class_id.0 as usize].clone().unwrap();
+        c.to_mut().field_initializer = Some(self.get_function(func_id).unwrap());
+    }
+
     pub fn default_constructor(&self) -> FuncID {
         FuncID(0)
     }
@@ -1433,18 +2343,39 @@ impl Runtime {
                 Variable::Const(v) => *v,
                 Variable::Let(v) => *v,
                 Variable::Var(v) => *v,
-                Variable::Import { id, original_name } => {
-                    self.get_exported(*id, *original_name).unwrap()
+                Variable::Uninitialized => {
+                    let name = self.get_dynamic_var_name(key).split('@').next().unwrap();
+                    return Err(JValue::from(crate::error::Error::ReferenceError(format!(
+                        "Cannot access '{}' before initialization",
+                        name
+                    ))));
                 }
-                Variable::ImportDefault(id) => self.modules[id.0 as usize].default_export,
+                Variable::Import { id, original_name } => self
+                    .read_exported(*id, *original_name)?
+                    .unwrap_or(JValue::UNDEFINED),
+                Variable::ImportDefault(id) => self.get_exported_default(*id),
                 Variable::ImportModule { id } => self.get_module_objet(*id).into(),
             };
             return Ok(v);
         } else {
+            // names in modules are suffixed with the module id,
+            // a name not declared by the module is a global
+            if let Some((name, _)) = self.get_dynamic_var_name(key).split_once('@') {
+                let key = self.dynamic_var_names.write().get_or_intern(name) as u32;
+                return self.get_variable(key);
+            }
+
             let guard = self.dynamic_var_names.read();
             let key = guard.resolve(key as usize).unwrap();
 
-            if self.strict_mode {
+            // globals such as Math are still readable in strict mode
+            if self.strict_mode
+                && !self
+                    .global_this
+                    .has_owned_property(bultins::object_property::ToProperyKey::to_key(
+                        &key, self,
+                    ))
+            {
                 return Err(JValue::from(crate::error::Error::ReferenceError(format!(
                     "{} is not defined",
                     key
@@ -1462,45 +2393,54 @@ impl Runtime {
     }
 
     pub(crate) fn get_exported(&self, module_id: ModuleId, key: u32) -> Option<JValue> {
+        self.read_exported(module_id, key).unwrap_or(None)
+    }
+
+    /// get an export, reading a lexical export before its declaration is an error
+    fn read_exported(&self, module_id: ModuleId, key: u32) -> Result<Option<JValue>, JValue> {
         if let Some(m) = self.modules.get(module_id.0 as usize) {
             if let Some(v) = m.exports.get(&key) {
                 match v {
-                    ExportVariable::Const(c) => match self.get_variable(*c) {
-                        Ok(v) => return Some(v),
-                        Err(_e) => return None,
-                    },
-                    ExportVariable::Let(c) => match self.get_variable(*c) {
-                        Ok(v) => return Some(v),
-                        Err(_e) => return None,
-                    },
-                    ExportVariable::Var(c) => match self.get_variable(*c) {
-                        Ok(v) => return Some(v),
-                        Err(_e) => return None,
-                    },
+                    ExportVariable::Const(c) | ExportVariable::Let(c) | ExportVariable::Var(c) => {
+                        return self.get_variable(*c).map(Some)
+                    }
                     ExportVariable::Import { id, original_name } => {
-                        return self.get_exported(*id, *original_name);
+                        return self.read_exported(*id, *original_name);
                     }
                     ExportVariable::ImportDefault { id } => {
-                        return Some(self.get_exported_default(*id))
+                        return Ok(Some(self.get_exported_default(*id)))
                     }
                     ExportVariable::ImportModule { id } => {
                         // create a Module Object
-                        return Some(self.get_module_objet(*id).into());
+                        return Ok(Some(self.get_module_objet(*id).into()));
                     }
+                    ExportVariable::Value(v) => return Ok(Some(*v)),
                 };
             };
         }
-        return None;
+        return Ok(None);
     }
 
     pub(crate) fn get_exported_default(&self, module_id: ModuleId) -> JValue {
-        self.modules[module_id.0 as usize].default_export
+        let key = self.dynamic_var_names.write().get_or_intern("default") as u32;
+        self.get_exported(module_id, key)
+            .unwrap_or(JValue::UNDEFINED)
     }
 
     pub(crate) fn get_module_objet(&self, module_id: ModuleId) -> JObject {
         JObject::with_module(module_id)
     }
 
+    /// get an export by the property key of the namespace object
+    pub(crate) fn get_namespace_property(&self, module_id: ModuleId, key: u32) -> Option<JValue> {
+        if key == NAMES["default"].0 {
+            return Some(self.get_exported_default(module_id));
+        }
+        let name = self.get_field_name(key);
+        let key = self.dynamic_var_names.write().get_or_intern(name) as u32;
+        self.get_exported(module_id, key)
+    }
+
     #[inline]
     pub fn set_variable(&mut self, key: u32, value: JValue) -> Result<(), JValue> {
         if let Some(v) = self.variables.get_mut(&key) {
@@ -1508,13 +2448,35 @@ impl Runtime {
                 Variable::Const(_v) => {}
                 Variable::Let(v) => *v = value,
                 Variable::Var(v) => *v = value,
+                Variable::Uninitialized => *v = Variable::Let(value),
                 _ => {}
             };
             Ok(())
         } else {
+            // names in modules are suffixed with the module id,
+            // a name not declared by the module is a global
+            if let Some((name, _)) = self.get_dynamic_var_name(key).split_once('@') {
+                let key = self.dynamic_var_names.write().get_or_intern(name) as u32;
+                return self.set_variable(key, value);
+            }
+
             let guard = self.dynamic_var_names.read();
             let key = guard.resolve(key as usize).unwrap();
 
+            // assigning to an undeclared variable is an error in strict mode
+            if self.strict_mode
+                && !self
+                    .global_this
+                    .has_owned_property(bultins::object_property::ToProperyKey::to_key(
+                        &key, self,
+                    ))
+            {
+                return Err(JValue::from(crate::error::Error::ReferenceError(format!(
+                    "{} is not defined",
+                    key
+                ))));
+            };
+
             self.global_this.set_property(
                 key,
                 value,
@@ -1532,7 +2494,29 @@ impl Runtime {
 
     #[inline]
     pub unsafe fn run_gc(self: Arc<Self>) {
-        // scan root and stack
+        self.trace_roots();
+
+        let rt = self.clone();
+
+        self.spawn_worker_task(Box::new(move || {
+            rt.sweep();
+        }));
+    }
+
+    /// run a full collection on the current thread,
+    /// values held by the host are only kept alive by `user_own_value`
+    pub fn gc(&self) {
+        unsafe { self.collect_garbage() }
+    }
+
+    /// run a full collection on the current thread
+    pub(crate) unsafe fn collect_garbage(&self) {
+        self.trace_roots();
+        self.sweep();
+    }
+
+    /// scan root and stack
+    unsafe fn trace_roots(&self) {
         self.finalize_registry.trace();
 
         self.constants.iter().for_each(|v| v.trace());
@@ -1543,33 +2527,59 @@ impl Runtime {
 
         self.user_owned.keys().into_iter().for_each(|v| v.trace());
 
-        for i in 0..self.variables.len() {
-            match self.get_variable(i as u32) {
-                Ok(v) => v.trace(),
-                Err(e) => e.trace(),
-            };
+        for v in self.variables.values() {
+            match v {
+                Variable::Var(v) | Variable::Let(v) | Variable::Const(v) => v.trace(),
+                _ => {}
+            }
         }
         self.stack.iter().for_each(|v| v.trace());
         self.async_stacks
             .iter()
             .for_each(|v| v.iter().for_each(|v| v.trace()));
 
-        self.modules.iter().for_each(|v| {
-            v.default_export.trace();
+        self.futures.iter().for_each(|task| {
+            if let AsyncTask::Done(Ok(v) | Err(v)) = task {
+                v.trace();
+            }
+        });
+        self.microtasks.iter().for_each(|job| job.trace());
+        self.timers.trace();
+        self.running_microtasks.iter().for_each(|job| job.trace());
+        self.awaited_futures.iter().for_each(|(_, p)| p.trace());
+        self.module_fetches.iter().for_each(|f| f.promise.trace());
+        self.import_metas.values().for_each(|m| m.trace());
+        self.unhandled_rejections.iter().for_each(|p| p.trace());
+
+        self.modules.iter().for_each(|m| {
+            if let Some(crate::error::Error::Value(v)) = &m.error {
+                v.trace();
+            }
+            m.waiting.iter().for_each(|(p, v)| {
+                p.trace();
+                v.trace();
+            });
+            m.roots.iter().for_each(|v| v.trace());
+            m.exports.values().for_each(|e| {
+                if let ExportVariable::Value(v) = e {
+                    v.trace();
+                }
+            });
+        });
+        self.settled_modules.iter().for_each(|(_, re)| {
+            if let Err(e) = re {
+                e.trace();
+            }
         });
 
         self.to_mut().finalize_registry.garbage_collect(&self);
+    }
 
-        let rt = self.clone();
-
-        self.worker_task_sender
-            .send(Box::new(move || {
-                rt.to_mut().string_allocator.garbage_collect();
-                rt.to_mut().object_allocator.garbage_collect();
-                rt.to_mut().bigint_allocator.garbage_collect();
-                rt.to_mut().clean_functions();
-            }))
-            .unwrap();
+    fn sweep(&self) {
+        self.to_mut().string_allocator.garbage_collect();
+        self.to_mut().object_allocator.garbage_collect();
+        self.to_mut().bigint_allocator.garbage_collect();
+        self.to_mut().clean_functions();
     }
 
     /// return the reference counter
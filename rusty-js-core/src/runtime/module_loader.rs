@@ -0,0 +1,165 @@
+// fetching modules through the import resolver,
+// the static import graph is fetched concurrently before a module is evaluated
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use super::{ImportAssertion, ModuleKind, ModuleSource, Runtime};
+use crate::error::Error;
+use crate::value::JValue;
+
+impl Runtime {
+    /// execute the source text as an ES module,
+    /// the modules it imports are fetched concurrently before it runs
+    pub async fn execute_module_async(
+        self: Arc<Self>,
+        filename: &str,
+        script: &str,
+    ) -> Result<JValue, Error> {
+        let module = self.parse_module(filename, script)?;
+        let requests = self.resolve_imports(static_imports(&module), filename)?;
+        self.clone().fetch_module_graph(requests).await?;
+        self.with_global(true, JValue::UNDEFINED, |rt| {
+            rt.run_module(module, filename)
+        })
+    }
+
+    /// resolve the specifier against the url of the importing module,
+    /// the import map is applied before the resolver
+    pub(crate) fn resolve_specifier(
+        &self,
+        specifier: &str,
+        referrer: Option<&str>,
+    ) -> Result<String, Error> {
+        let mapped = match &self.import_map {
+            Some(map) => map
+                .resolve(specifier, referrer)
+                .map_err(Error::ImportError)?,
+            None => None,
+        };
+        let specifier = mapped.as_deref().unwrap_or(specifier);
+
+        match &self.import_resolver {
+            Some(resolver) => resolver
+                .write()
+                .resolve(specifier, referrer)
+                .map_err(Error::ImportError),
+            None => Err(Error::ImportError(
+                "cannot import module: import resolver is not defined.".to_owned(),
+            )),
+        }
+    }
+
+    fn resolve_imports(
+        &self,
+        imports: Vec<(String, ImportAssertion)>,
+        referrer: &str,
+    ) -> Result<Vec<(String, ImportAssertion)>, Error> {
+        let referrer = if referrer.is_empty() {
+            None
+        } else {
+            Some(referrer)
+        };
+        imports
+            .into_iter()
+            .map(|(name, asserts)| Ok((self.resolve_specifier(&name, referrer)?, asserts)))
+            .collect()
+    }
+
+    /// ask the resolver for a module by its resolved name,
+    /// the resolver is only borrowed while creating the future
+    pub(crate) fn fetch_module(
+        &self,
+        name: &str,
+        asserts: ImportAssertion,
+    ) -> impl Future<Output = Result<ModuleSource, Error>> {
+        let future = match &self.import_resolver {
+            Some(resolver) => Ok(resolver.write().import(name, asserts)),
+            None => Err(Error::ImportError(
+                "cannot import module: import resolver is not defined.".to_owned(),
+            )),
+        };
+        async move { future?.await.map_err(Error::ImportError) }
+    }
+
+    /// fetch the resolved modules and everything they import statically,
+    /// the sources are kept until the modules are evaluated
+    pub(crate) async fn fetch_module_graph(
+        self: Arc<Self>,
+        requests: Vec<(String, ImportAssertion)>,
+    ) -> Result<(), Error> {
+        let mut seen = HashSet::new();
+        let mut fetching = FuturesUnordered::new();
+        let mut pending = requests;
+
+        loop {
+            for (name, asserts) in pending.drain(..) {
+                let key = asserts.cache_key(&name);
+                if self.module_map.contains_key(&key)
+                    || self.module_sources.contains_key(&key)
+                    || !seen.insert(key.clone())
+                {
+                    continue;
+                }
+                let future = self.fetch_module(&name, asserts);
+                fetching.push(async move { (key, future.await) });
+            }
+
+            let (key, re) = match fetching.next().await {
+                Some(v) => v,
+                None => return Ok(()),
+            };
+            let source = re?;
+
+            // syntax errors are reported before anything is evaluated
+            let module = match source.kind {
+                ModuleKind::JavaScript => Some(self.parse_module(&source.url, &source.source)?),
+                // imports only used as types are not fetched
+                ModuleKind::TypeScript => {
+                    Some(self.parse_typescript_module(&source.url, &source.source)?)
+                }
+                _ => None,
+            };
+            if let Some(module) = module {
+                pending.extend(self.resolve_imports(static_imports(&module), &source.url)?);
+            }
+            self.to_mut().module_sources.insert(key, source);
+        }
+    }
+}
+
+/// the modules imported by the import and export declarations
+fn static_imports(module: &swc_ecmascript::ast::Module) -> Vec<(String, ImportAssertion)> {
+    use swc_ecmascript::ast::{ModuleDecl, ModuleItem};
+
+    let mut imports = Vec::new();
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::ModuleDecl(decl) => decl,
+            ModuleItem::Stmt(_) => continue,
+        };
+        match decl {
+            ModuleDecl::Import(i) => imports.push((
+                i.src.value.to_string(),
+                ImportAssertion::from(i.asserts.as_ref().map(|v| v.as_ref())),
+            )),
+            ModuleDecl::ExportAll(e) => imports.push((
+                e.src.value.to_string(),
+                ImportAssertion::from(e.asserts.as_ref().map(|v| v.as_ref())),
+            )),
+            ModuleDecl::ExportNamed(n) => {
+                if let Some(src) = &n.src {
+                    imports.push((
+                        src.value.to_string(),
+                        ImportAssertion::from(n.asserts.as_ref().map(|v| v.as_ref())),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+    imports
+}
@@ -0,0 +1,772 @@
+// the module map, a module is linked once by its resolved specifier,
+// the graph is evaluated by the depth first search of 16.2.1.5.3 Evaluate(),
+// modules with top level await are evaluated by the async module evaluation of 16.2.1.5.3
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use swc_ecmascript::ast::{
+    ArrowExpr, AwaitExpr, Constructor, Decl, DefaultDecl, Function, GetterProp, Id, ModuleDecl,
+    ModuleExportName, ModuleItem, Pat, SetterProp, StaticBlock, Stmt, TsModuleName,
+};
+use swc_ecmascript::visit::{Visit, VisitWith};
+
+use super::{
+    ExportVariable, ImportAssertion, Microtask, Module, ModuleId, ModuleKind, Runtime, Variable,
+};
+use crate::bultins::object::JObject;
+use crate::bultins::promise;
+use crate::bytecodes::bytecode_builder::{is_instantiated, FunctionBuilder};
+use crate::error::Error;
+use crate::utils::json::parse_json;
+use crate::value::JValue;
+use crate::JSContext;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ModuleStatus {
+    /// the module and its imports are being translated
+    Linking,
+    Linked,
+    Evaluating,
+    /// the module or the modules it imports are awaiting
+    EvaluatingAsync,
+    /// evaluated or thrown, the error is kept in the module
+    Evaluated,
+}
+
+impl Module {
+    pub(crate) fn new(id: ModuleId, name: &str, entry: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            exports: Default::default(),
+            status: ModuleStatus::Linking,
+            entry,
+            requested: Vec::new(),
+            code: None,
+            op_stack: 0,
+            dfs_index: 0,
+            dfs_ancestor_index: 0,
+            error: None,
+            has_top_level_await: false,
+            async_evaluation: None,
+            pending_async_dependencies: 0,
+            async_parents: Vec::new(),
+            cycle_root: id,
+            waiting: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+}
+
+impl Runtime {
+    /// link an imported module by its resolved name,
+    /// a module already in the module map is returned as it is
+    pub(crate) fn link_module(
+        self: Arc<Self>,
+        name: &str,
+        asserts: ImportAssertion,
+    ) -> Result<ModuleId, Error> {
+        let key = asserts.cache_key(name);
+        if let Some(id) = self.module_map.get(&key) {
+            return Ok(*id);
+        }
+
+        let source = match self.module_sources.get(&key) {
+            Some(source) => source.clone(),
+            None => futures::executor::block_on(self.fetch_module(name, asserts))?,
+        };
+
+        let module = match source.kind {
+            ModuleKind::JavaScript => {
+                // variables in module doesn't matter anyway, so we optimize it out
+                let module = self.parse_module(&source.url, &source.source)?;
+                self.optimize_module(module)
+            }
+            ModuleKind::Json => return self.link_json_module(key, &source.url, &source.source),
+            // types are erased while translating
+            ModuleKind::TypeScript => self.parse_typescript_module(&source.url, &source.source)?,
+            kind => {
+                return Err(Error::ImportError(format!(
+                    "cannot import module {}: {:?} modules are not supported",
+                    name, kind
+                )))
+            }
+        };
+
+        self.with_global(true, JValue::UNDEFINED, |rt| {
+            rt.link_parsed(Some(key), &source.url, module, false)
+        })
+    }
+
+    /// a JSON module is parsed as JSON.parse does and never evaluated,
+    /// the parsed value is its default export
+    fn link_json_module(&self, key: String, url: &str, source: &str) -> Result<ModuleId, Error> {
+        let json = parse_json(url, source).map_err(|e| Error::SyntaxError(e.into()))?;
+
+        let id = ModuleId(self.modules.len() as u32);
+        let mut record = Module::new(id, url, false);
+        let default = self.dynamic_var_names.write().get_or_intern("default") as u32;
+        record
+            .exports
+            .insert(default, ExportVariable::Value(json.to_value(self)));
+        record.status = ModuleStatus::Evaluated;
+
+        self.to_mut().modules.push(record);
+        self.to_mut().module_map.insert(key, id);
+        Ok(id)
+    }
+
+    /// create the module record and translate the module,
+    /// the modules it imports are linked while it is translated
+    pub(crate) fn link_parsed(
+        self: Arc<Self>,
+        key: Option<String>,
+        url: &str,
+        module: swc_ecmascript::ast::Module,
+        entry: bool,
+    ) -> Result<ModuleId, Error> {
+        let id = ModuleId(self.modules.len() as u32);
+        let mut record = Module::new(id, url, entry);
+        record.has_top_level_await = has_top_level_await(&module);
+        self.to_mut().modules.push(record);
+        if let Some(key) = &key {
+            self.to_mut().module_map.insert(key.clone(), id);
+        }
+
+        let suffix = std::mem::replace(
+            &mut self.to_mut().dynamic_var_suffix,
+            Some(format!("@{}", id.0)),
+        );
+        let parent = std::mem::replace(&mut self.to_mut().linking_module, Some(id));
+        // the exports of the importing module are still being collected
+        let parent_exports = std::mem::take(&mut self.to_mut().exported_variables);
+
+        let re = self.clone().build_module(module, url);
+
+        self.to_mut().dynamic_var_suffix = suffix;
+        self.to_mut().linking_module = parent;
+        let exports = std::mem::replace(&mut self.to_mut().exported_variables, parent_exports);
+
+        // the imports are checked once the whole graph is linked
+        let re = re.and_then(|code| {
+            if parent.is_none() {
+                self.check_imports()?;
+            }
+            Ok(code)
+        });
+
+        let (code, op_stack) = match re {
+            Ok(v) => v,
+            Err(e) => {
+                if let Some(key) = &key {
+                    self.to_mut().module_map.remove(key);
+                }
+                if parent.is_none() {
+                    self.to_mut().unresolved_imports.clear();
+                    self.drop_failed_modules(id);
+                }
+                return Err(e);
+            }
+        };
+
+        let exports = exports
+            .into_iter()
+            .map(|(name, v)| (self.dynamic_var_names.write().get_or_intern(name) as u32, v))
+            .collect();
+
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        m.exports = exports;
+        m.code = Some(code);
+        m.op_stack = op_stack;
+        m.status = ModuleStatus::Linked;
+
+        Ok(id)
+    }
+
+    /// drop the records created by a failed link starting at `first`,
+    /// so the modules are linked again when they are imported again
+    fn drop_failed_modules(&self, first: ModuleId) {
+        let rt = self.to_mut();
+        rt.modules.truncate(first.0 as usize);
+        rt.module_map.retain(|_, id| id.0 < first.0);
+
+        // the ids are reused, so the variables suffixed with them are removed
+        let names = self.dynamic_var_names.read();
+        rt.variables.retain(|key, _| {
+            let module = names
+                .resolve(*key as usize)
+                .and_then(|name| name.rsplit_once('@'))
+                .and_then(|(_, id)| id.parse::<u32>().ok());
+            match module {
+                Some(id) => id < first.0,
+                None => true,
+            }
+        });
+    }
+
+    /// every imported name must be exported by the imported module
+    fn check_imports(&self) -> Result<(), Error> {
+        for (id, name) in std::mem::take(&mut self.to_mut().unresolved_imports) {
+            let key = self.dynamic_var_names.write().get_or_intern(&name) as u32;
+            let m = &self.modules[id.0 as usize];
+            if !m.exports.contains_key(&key) {
+                return Err(Error::ImportError(format!(
+                    "The requested module '{}' does not provide an export named '{}'",
+                    m.name, name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// link and evaluate the module imported by import(),
+    /// the promise is fulfilled with the namespace once the module graph is evaluated
+    pub(crate) fn evaluate_dynamic_import(
+        self: Arc<Self>,
+        promise: JObject,
+        name: &str,
+        asserts: ImportAssertion,
+        stack: *mut JValue,
+    ) {
+        let re = self.clone().link_module(name, asserts).and_then(|id| {
+            self.clone().evaluate_graph(id, Some(stack))?;
+            Ok(id)
+        });
+
+        let id = match re {
+            Ok(id) => id,
+            Err(e) => {
+                promise::reject_promise(&self, promise, Runtime::value_from_error(e));
+                return;
+            }
+        };
+
+        let namespace: JValue = self.get_module_objet(id).into();
+        match self.async_root(id) {
+            Some(root) => self.to_mut().modules[root.0 as usize]
+                .waiting
+                .push((promise, namespace)),
+            None => {
+                let ctx = JSContext {
+                    stack,
+                    runtime: &self,
+                };
+                promise::resolve_promise(ctx, promise, namespace);
+            }
+        }
+    }
+
+    /// evaluate the module after the modules it imports,
+    /// returns the completion value of the module.
+    ///
+    /// if the module or the modules it imports await at the top level,
+    /// a promise settled once the module graph is evaluated is returned instead
+    pub(crate) fn evaluate_module(
+        self: Arc<Self>,
+        id: ModuleId,
+        stack: Option<*mut JValue>,
+    ) -> Result<JValue, Error> {
+        let completion = self.clone().evaluate_graph(id, stack)?;
+
+        if let Some(root) = self.async_root(id) {
+            let promise = promise::new_promise(&self);
+            self.to_mut().modules[root.0 as usize]
+                .waiting
+                .push((promise, JValue::UNDEFINED));
+            return Ok(promise.into());
+        }
+        Ok(completion)
+    }
+
+    /// 16.2.1.5.3 Evaluate, returns the completion value of the module
+    fn evaluate_graph(
+        self: Arc<Self>,
+        id: ModuleId,
+        stack: Option<*mut JValue>,
+    ) -> Result<JValue, Error> {
+        let mut dfs_stack = Vec::new();
+        let mut completion = JValue::UNDEFINED;
+
+        self.to_mut().module_evaluation_depth += 1;
+        let re =
+            self.clone()
+                .inner_module_evaluation(id, id, &mut dfs_stack, 0, stack, &mut completion);
+        self.to_mut().module_evaluation_depth -= 1;
+
+        // the async modules settled while the graph was evaluated continue by a job
+        if self.module_evaluation_depth == 0 {
+            for (module, result) in std::mem::take(&mut self.to_mut().settled_modules) {
+                self.enqueue_microtask(Microtask::AsyncModuleSettled { module, result });
+            }
+        }
+
+        if let Err(e) = re {
+            // the modules in the cycle of the thrown module throw the same error
+            for id in dfs_stack {
+                let m = &mut self.to_mut().modules[id.0 as usize];
+                m.status = ModuleStatus::Evaluated;
+                m.error = Some(e.clone());
+            }
+            return Err(e);
+        }
+        Ok(completion)
+    }
+
+    /// 16.2.1.5.3.1 InnerModuleEvaluation, returns the next dfs index
+    fn inner_module_evaluation(
+        self: Arc<Self>,
+        id: ModuleId,
+        root: ModuleId,
+        dfs_stack: &mut Vec<ModuleId>,
+        mut index: usize,
+        stack: Option<*mut JValue>,
+        completion: &mut JValue,
+    ) -> Result<usize, Error> {
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        match m.status {
+            ModuleStatus::Evaluated | ModuleStatus::EvaluatingAsync => match &m.error {
+                Some(e) => return Err(e.clone()),
+                None => return Ok(index),
+            },
+            ModuleStatus::Evaluating => return Ok(index),
+            ModuleStatus::Linking => {
+                return Err(Error::ImportError(format!(
+                    "module {} is not linked",
+                    m.name
+                )))
+            }
+            ModuleStatus::Linked => {}
+        }
+
+        m.status = ModuleStatus::Evaluating;
+        m.dfs_index = index;
+        m.dfs_ancestor_index = index;
+        m.pending_async_dependencies = 0;
+        index += 1;
+        dfs_stack.push(id);
+
+        for required in m.requested.clone() {
+            index = self
+                .clone()
+                .inner_module_evaluation(required, root, dfs_stack, index, stack, completion)?;
+
+            let r = &self.modules[required.0 as usize];
+            let required = if r.status == ModuleStatus::Evaluating {
+                let ancestor = r.dfs_ancestor_index;
+                let m = &mut self.to_mut().modules[id.0 as usize];
+                m.dfs_ancestor_index = m.dfs_ancestor_index.min(ancestor);
+                required
+            } else {
+                // a cycle is waited on as a whole
+                let cycle_root = r.cycle_root;
+                if let Some(e) = &self.modules[cycle_root.0 as usize].error {
+                    return Err(e.clone());
+                }
+                cycle_root
+            };
+
+            if self.modules[required.0 as usize].async_evaluation.is_some() {
+                self.to_mut().modules[id.0 as usize].pending_async_dependencies += 1;
+                self.to_mut().modules[required.0 as usize]
+                    .async_parents
+                    .push(id);
+            }
+        }
+
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        if m.pending_async_dependencies > 0 || m.has_top_level_await {
+            m.async_evaluation = Some(self.async_module_count);
+            self.to_mut().async_module_count += 1;
+            if m.pending_async_dependencies == 0 {
+                self.clone().execute_async_module(id);
+            }
+        } else {
+            let value = self.clone().execute_module_record(id, stack)?;
+            if id == root {
+                *completion = value;
+            }
+        }
+
+        // the module is the root of a strongly connected component
+        let m = &self.modules[id.0 as usize];
+        if m.dfs_ancestor_index == m.dfs_index {
+            while let Some(done) = dfs_stack.pop() {
+                let done_module = &mut self.to_mut().modules[done.0 as usize];
+                done_module.status = match done_module.async_evaluation {
+                    Some(_) => ModuleStatus::EvaluatingAsync,
+                    None => ModuleStatus::Evaluated,
+                };
+                done_module.cycle_root = id;
+                if done == id {
+                    break;
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// run the code of a module,
+    /// the strictness of an entry module is set by the caller
+    fn execute_module_record(
+        self: Arc<Self>,
+        id: ModuleId,
+        stack: Option<*mut JValue>,
+    ) -> Result<JValue, Error> {
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        let code = m.code.take().unwrap_or_default();
+        let op_stack = m.op_stack;
+        let entry = m.entry;
+
+        let run = move |rt: Arc<Self>| {
+            let mut cl = crate::interpreter::clousure::Clousure::create(&code);
+            match stack {
+                Some(stack) => rt.run_clousure_on(&mut cl, stack),
+                None => rt.run_clousure(&mut cl, op_stack),
+            }
+        };
+        if entry {
+            run(self)
+        } else {
+            self.with_global(true, JValue::UNDEFINED, run)
+        }
+    }
+
+    /// keep the values of a module suspended at a top level await alive
+    pub(crate) fn set_module_roots(&self, id: ModuleId, roots: Vec<JValue>) {
+        self.to_mut().modules[id.0 as usize].roots = roots;
+    }
+
+    /// 16.2.1.5.3.3 ExecuteAsyncModule,
+    /// the module runs on its own stack until its first await suspends it
+    fn execute_async_module(self: Arc<Self>, id: ModuleId) {
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        let code = m.code.take().unwrap_or_default();
+        let op_stack = m.op_stack;
+
+        let stack = self
+            .to_mut()
+            .get_async_stack(super::DEFAULT_STACK_SIZE * JValue::SIZE);
+
+        let runtime = self.clone();
+        let mut run = Box::pin(async move {
+            let mut cl = crate::interpreter::clousure::Clousure::create(&code);
+            let len = stack.len();
+            // the variables are stored below the operational stack
+            let op = unsafe {
+                std::slice::from_raw_parts_mut(stack.as_mut_ptr().add(op_stack), len - op_stack)
+            };
+            let re = cl.run_module_async(&runtime, id, stack, op).await;
+            runtime.drop_async_stack(stack);
+            re
+        });
+
+        let rt = self.clone();
+        let task = self.spawn_task(std::future::poll_fn(move |cx| {
+            rt.enter_execution();
+            let p = rt.with_global(true, JValue::UNDEFINED, |_| run.as_mut().poll(cx));
+            rt.interrupt.exit();
+
+            // the importers are continued by a job
+            if let Poll::Ready(re) = p {
+                rt.enqueue_microtask(Microtask::AsyncModuleSettled {
+                    module: id,
+                    result: re.map(|_| ()),
+                });
+            }
+            p
+        }));
+
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let _ = self.poll_future(task, &mut cx);
+    }
+
+    /// continue the importers of an async module once it is evaluated,
+    /// modules settled while a graph is evaluated are continued after it
+    pub(crate) fn async_module_settled(
+        self: Arc<Self>,
+        id: ModuleId,
+        result: Result<(), JValue>,
+        stack: *mut JValue,
+    ) {
+        if self.module_evaluation_depth > 0 {
+            self.to_mut().settled_modules.push((id, result));
+            return;
+        }
+        match result {
+            Ok(()) => self.async_module_fulfilled(id, stack),
+            Err(e) => self.async_module_rejected(id, Runtime::error_from_value(e), stack),
+        }
+    }
+
+    /// 16.2.1.5.3.4 AsyncModuleExecutionFulfilled
+    fn async_module_fulfilled(self: Arc<Self>, id: ModuleId, stack: *mut JValue) {
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        // a module in the same cycle has thrown
+        if m.status == ModuleStatus::Evaluated {
+            return;
+        }
+        m.async_evaluation = None;
+        m.status = ModuleStatus::Evaluated;
+        self.settle_waiting(id, None, stack);
+
+        let mut exec_list = Vec::new();
+        self.gather_available_ancestors(id, &mut exec_list);
+        // the importers run in the order they started evaluating
+        exec_list.sort_by_key(|m| self.modules[m.0 as usize].async_evaluation);
+
+        for m in exec_list {
+            let module = &self.modules[m.0 as usize];
+            if module.error.is_some() {
+                continue;
+            }
+            if module.has_top_level_await {
+                self.clone().execute_async_module(m);
+                continue;
+            }
+
+            match self.clone().execute_module_record(m, Some(stack)) {
+                Ok(_) => {
+                    let module = &mut self.to_mut().modules[m.0 as usize];
+                    module.async_evaluation = None;
+                    module.status = ModuleStatus::Evaluated;
+                    self.settle_waiting(m, None, stack);
+                }
+                Err(e) => self.async_module_rejected(m, e, stack),
+            }
+        }
+    }
+
+    /// 16.2.1.5.3.5 AsyncModuleExecutionRejected
+    fn async_module_rejected(&self, id: ModuleId, error: Error, stack: *mut JValue) {
+        let m = &mut self.to_mut().modules[id.0 as usize];
+        if m.status == ModuleStatus::Evaluated {
+            return;
+        }
+        m.error = Some(error.clone());
+        m.status = ModuleStatus::Evaluated;
+        m.async_evaluation = None;
+
+        for parent in m.async_parents.clone() {
+            self.async_module_rejected(parent, error.clone(), stack);
+        }
+        self.settle_waiting(id, Some(&error), stack);
+    }
+
+    /// 16.2.1.5.3.6 GatherAvailableAncestors,
+    /// collect the importers that are no longer waiting for an async module
+    fn gather_available_ancestors(&self, id: ModuleId, exec_list: &mut Vec<ModuleId>) {
+        for parent in self.modules[id.0 as usize].async_parents.clone() {
+            let cycle_root = self.modules[parent.0 as usize].cycle_root;
+            if exec_list.contains(&parent) || self.modules[cycle_root.0 as usize].error.is_some() {
+                continue;
+            }
+
+            let m = &mut self.to_mut().modules[parent.0 as usize];
+            m.pending_async_dependencies -= 1;
+            if m.pending_async_dependencies == 0 {
+                let has_top_level_await = m.has_top_level_await;
+                exec_list.push(parent);
+                if !has_top_level_await {
+                    self.gather_available_ancestors(parent, exec_list);
+                }
+            }
+        }
+    }
+
+    /// the root of the cycle of the module, if it is still evaluating asynchronously
+    fn async_root(&self, id: ModuleId) -> Option<ModuleId> {
+        let root = self.modules[id.0 as usize].cycle_root;
+        self.modules[root.0 as usize].async_evaluation.map(|_| root)
+    }
+
+    /// settle the promises waiting for the module graph of the module
+    fn settle_waiting(&self, id: ModuleId, error: Option<&Error>, stack: *mut JValue) {
+        let ctx = JSContext {
+            stack,
+            runtime: self,
+        };
+        let waiting = std::mem::take(&mut self.to_mut().modules[id.0 as usize].waiting);
+        for (promise, value) in waiting {
+            match error {
+                Some(e) => {
+                    promise::reject_promise(self, promise, Runtime::value_from_error(e.clone()))
+                }
+                None => promise::resolve_promise(ctx, promise, value),
+            }
+        }
+    }
+
+    /// declare the variables exported by the module as dynamic variables,
+    /// importers read them by id so the bindings stay live
+    pub(crate) fn declare_module_exports(
+        &self,
+        builder: &mut FunctionBuilder,
+        body: &[ModuleItem],
+    ) {
+        let mut exports: Vec<(String, Id, ExportKind)> = Vec::new();
+
+        // the kind of the local bindings exported by name
+        let mut locals: Vec<(Id, ExportKind)> = Vec::new();
+        for item in body {
+            if let ModuleItem::Stmt(Stmt::Decl(d)) = item {
+                decl_bindings(d, &mut locals);
+            }
+        }
+
+        for item in body {
+            let decl = match item {
+                ModuleItem::ModuleDecl(decl) => decl,
+                ModuleItem::Stmt(_) => continue,
+            };
+            match decl {
+                ModuleDecl::ExportDecl(e) => {
+                    let mut ids = Vec::new();
+                    decl_bindings(&e.decl, &mut ids);
+                    for (id, kind) in ids {
+                        exports.push((id.0.to_string(), id, kind));
+                    }
+                }
+                // types are erased
+                ModuleDecl::ExportDefaultDecl(d)
+                    if matches!(d.decl, DefaultDecl::TsInterfaceDecl(_)) => {}
+                ModuleDecl::TsImportEquals(i) if i.is_export && !i.is_type_only => {
+                    exports.push((i.id.sym.to_string(), i.id.to_id(), ExportKind::Const))
+                }
+                ModuleDecl::ExportDefaultDecl(_) | ModuleDecl::ExportDefaultExpr(_) => {
+                    // the default export is declared as "#default export"
+                    let id = ("#default export".into(), Default::default());
+                    exports.push(("default".to_string(), id, ExportKind::Var));
+                }
+                ModuleDecl::ExportNamed(n) if n.src.is_none() => {
+                    for s in &n.specifiers {
+                        if let swc_ecmascript::ast::ExportSpecifier::Named(s) = s {
+                            let orig = match &s.orig {
+                                ModuleExportName::Ident(i) => i,
+                                ModuleExportName::Str(_) => continue,
+                            };
+                            let exported = match &s.exported {
+                                Some(ModuleExportName::Ident(i)) => i.sym.to_string(),
+                                Some(ModuleExportName::Str(s)) => s.value.to_string(),
+                                None => orig.sym.to_string(),
+                            };
+                            let id = orig.to_id();
+                            let kind = locals
+                                .iter()
+                                .find(|(local, _)| *local == id)
+                                .map_or(ExportKind::Var, |(_, kind)| *kind);
+                            exports.push((exported, id, kind));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (exported, local, kind) in exports {
+            // a name with @moduleID suffix
+            let id = self.regester_dynamic_var_name(&local.0);
+
+            // lexical bindings cannot be read before their declaration is evaluated
+            let (variable, export) = match kind {
+                ExportKind::Var => (Variable::Var(JValue::UNDEFINED), ExportVariable::Var(id)),
+                ExportKind::Let => (Variable::Uninitialized, ExportVariable::Let(id)),
+                ExportKind::Const => (Variable::Uninitialized, ExportVariable::Const(id)),
+            };
+            self.to_mut().variables.insert(id, variable);
+            builder.ctx.declare_dynamic(local, id);
+            self.to_mut().exported_variables.insert(exported, export);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ExportKind {
+    /// functions, enums and namespaces are exported as vars,
+    /// they have no temporal dead zone
+    Var,
+    Let,
+    Const,
+}
+
+/// the bindings of a declaration with a value
+fn decl_bindings(decl: &Decl, ids: &mut Vec<(Id, ExportKind)>) {
+    match decl {
+        // ambient declarations and overload signatures have no value
+        Decl::Class(c) if c.declare => {}
+        Decl::Fn(f) if f.declare || f.function.body.is_none() => {}
+        Decl::Var(v) if v.declare => {}
+
+        Decl::Class(c) => ids.push((c.ident.to_id(), ExportKind::Let)),
+        Decl::Fn(f) => ids.push((f.ident.to_id(), ExportKind::Var)),
+        Decl::Var(v) => {
+            let kind = match v.kind {
+                swc_ecmascript::ast::VarDeclKind::Var => ExportKind::Var,
+                swc_ecmascript::ast::VarDeclKind::Let => ExportKind::Let,
+                swc_ecmascript::ast::VarDeclKind::Const => ExportKind::Const,
+            };
+            let mut names = Vec::new();
+            for d in &v.decls {
+                binding_ids(&d.name, &mut names);
+            }
+            ids.extend(names.into_iter().map(|id| (id, kind)));
+        }
+        Decl::TsEnum(e) if !e.declare => ids.push((e.id.to_id(), ExportKind::Var)),
+        Decl::TsModule(m) => {
+            // only namespaces with values are declared
+            if let (TsModuleName::Ident(i), Some(body)) = (&m.id, &m.body) {
+                if !m.declare && !m.global && is_instantiated(body) {
+                    ids.push((i.to_id(), ExportKind::Var))
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// the identifiers bound by a pattern
+fn binding_ids(pat: &Pat, ids: &mut Vec<Id>) {
+    use swc_ecmascript::ast::ObjectPatProp;
+
+    match pat {
+        Pat::Ident(i) => ids.push(i.id.to_id()),
+        Pat::Array(a) => a.elems.iter().flatten().for_each(|p| binding_ids(p, ids)),
+        Pat::Assign(a) => binding_ids(&a.left, ids),
+        Pat::Rest(r) => binding_ids(&r.arg, ids),
+        Pat::Object(o) => {
+            for prop in &o.props {
+                match prop {
+                    ObjectPatProp::Assign(a) => ids.push(a.key.to_id()),
+                    ObjectPatProp::KeyValue(kv) => binding_ids(&kv.value, ids),
+                    ObjectPatProp::Rest(r) => binding_ids(&r.arg, ids),
+                }
+            }
+        }
+        Pat::Expr(_) | Pat::Invalid(_) => {}
+    }
+}
+
+/// return true if the module awaits outside of functions
+fn has_top_level_await(module: &swc_ecmascript::ast::Module) -> bool {
+    let mut finder = AwaitFinder { found: false };
+    module.visit_with(&mut finder);
+    finder.found
+}
+
+struct AwaitFinder {
+    found: bool,
+}
+
+impl Visit for AwaitFinder {
+    // an await inside a function belongs to the function
+    fn visit_function(&mut self, _n: &Function) {}
+    fn visit_arrow_expr(&mut self, _n: &ArrowExpr) {}
+    fn visit_constructor(&mut self, _n: &Constructor) {}
+    fn visit_getter_prop(&mut self, _n: &GetterProp) {}
+    fn visit_setter_prop(&mut self, _n: &SetterProp) {}
+    fn visit_static_block(&mut self, _n: &StaticBlock) {}
+
+    fn visit_await_expr(&mut self, _n: &AwaitExpr) {
+        self.found = true;
+    }
+}
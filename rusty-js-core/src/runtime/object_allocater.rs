@@ -35,6 +35,17 @@ impl ObjectAllocator {
         return &mut *ptr;
     }
 
+    /// return true if the allocator has to grow on the next allocation
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// the number of bytes acquired when the allocator grows
+    pub fn growth_size(&self) -> usize {
+        self.pages.len().max(1) * 128 * OBJ_SIZE
+    }
+
     unsafe fn add_page(&mut self, num: usize) {
         let mut map = std::alloc::alloc(Layout::array::<JObjectInner>(128 * num).unwrap())
             as *mut JObjectInner;
@@ -70,6 +81,7 @@ impl ObjectAllocator {
                     obj.extensible = true;
                     obj.values.clear();
                     obj.wrapped_value = Default::default();
+                    obj.private_elements = None;
 
                     self.next = Some(obj);
                 } else if obj.flag == GcFlag::Old {
@@ -0,0 +1,85 @@
+use std::sync::{Arc, Weak};
+
+use super::Runtime;
+use crate::bytecodes::OpCode;
+use crate::error::Error;
+use crate::interpreter::clousure::Clousure;
+use crate::value::JValue;
+
+/// a classic script compiled by `Runtime::compile`.
+///
+/// functions, classes and constants of the script are registered on the runtime
+/// that compiled it, a script can only run on that runtime.
+pub struct Script {
+    runtime: Weak<Runtime>,
+    pub(super) filename: String,
+    pub(super) source_hash: u64,
+
+    pub(super) bytecodes: Vec<OpCode>,
+    clousure: Clousure,
+    /// offset of the operational stack
+    pub(super) op_stack: usize,
+
+    pub(super) strict: bool,
+    /// var and function declarations defined on the global object
+    pub(super) globals: Vec<String>,
+}
+
+impl Script {
+    pub(crate) fn new(
+        runtime: &Arc<Runtime>,
+        filename: &str,
+        source_hash: u64,
+        bytecodes: Vec<OpCode>,
+        op_stack: usize,
+        strict: bool,
+        globals: Vec<String>,
+    ) -> Self {
+        Self {
+            runtime: Arc::downgrade(runtime),
+            filename: filename.to_string(),
+            source_hash,
+            clousure: Clousure::create(&bytecodes),
+            bytecodes,
+            op_stack,
+            strict,
+            globals,
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// hash of the source text, used to key a code cache
+    pub fn source_hash(&self) -> u64 {
+        self.source_hash
+    }
+
+    /// hash a source text the same way as `Script::source_hash`
+    pub fn hash_source(source: &str) -> u64 {
+        fxhash::hash64(source)
+    }
+
+    /// run the script, the source is not parsed again
+    pub fn run(&mut self, runtime: &Arc<Runtime>) -> Result<JValue, Error> {
+        if self.runtime.as_ptr() != Arc::as_ptr(runtime) {
+            return Err(Error::RuntimeError(format!(
+                "script '{}' is compiled by another runtime",
+                self.filename
+            )));
+        }
+
+        runtime.declare_globals(&self.globals);
+
+        let global = runtime.global_this.into();
+        let op_stack = self.op_stack;
+        let cl = &mut self.clousure;
+
+        runtime.with_global(self.strict, global, |rt| rt.run_clousure(cl, op_stack))
+    }
+}
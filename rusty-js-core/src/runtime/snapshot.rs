@@ -0,0 +1,993 @@
+// heap snapshots of an initialized runtime.
+//
+// layout of a snapshot:
+//
+// - magic, format version, engine version and the ids of the native functions
+// - tables of field names, variable names, strings, constants, regexs and templates
+// - number of functions and classes
+// - the origin of every object in the heap
+// - the object graph: the top level this, the global variables and every object
+// - one record for each function and class referenced by the heap
+//
+// native functions cannot be serialized, they are referred to by the id they are
+// registered under. builtins are named by their group and the order they are created in,
+// the host registers its own functions on the restoring runtime before the heap is restored.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use num_traits::ToPrimitive;
+use parking_lot::RwLock;
+
+use super::code_cache::ENGINE_VERSION;
+use super::{Runtime, RuntimeOptions, Variable};
+use crate::bultins::class::{JSClass, JSClassInstance, PrivateElement, PrivateName};
+use crate::bultins::function::{CaptureStack, JSContext, JSFunction, JSFunctionInstance};
+use crate::bultins::object::{JObject, JObjectInner, JObjectValue, PropCell, PropFlag, PropKey};
+use crate::bultins::regex::RegExp;
+use crate::bultins::symbol::JSymbol;
+use crate::bytecodes::cache::{Decoder, Encoder};
+use crate::error::Error;
+use crate::runtime::FuncID;
+use crate::utils::string_interner::SYMBOLS;
+use crate::value::JValue;
+
+const MAGIC: &[u8; 8] = b"RJSSNAPS";
+
+/// bump when the layout of the snapshot changes
+const FORMAT_VERSION: u32 = 7;
+
+const ORIGIN_NEW: u8 = 0;
+const ORIGIN_GLOBAL: u8 = 1;
+const ORIGIN_PROTOTYPE: u8 = 2;
+
+const VALUE_UNDEFINED: u8 = 0;
+const VALUE_NULL: u8 = 1;
+const VALUE_TRUE: u8 = 2;
+const VALUE_FALSE: u8 = 3;
+const VALUE_INT: u8 = 4;
+const VALUE_NUMBER: u8 = 5;
+const VALUE_STRING: u8 = 6;
+const VALUE_BIGINT: u8 = 7;
+const VALUE_SYMBOL: u8 = 8;
+const VALUE_OBJECT: u8 = 9;
+
+const INNER_EMPTY: u8 = 0;
+const INNER_STRING: u8 = 1;
+const INNER_NUMBER: u8 = 2;
+const INNER_BIGINT: u8 = 3;
+const INNER_BOOLEAN: u8 = 4;
+const INNER_SYMBOL: u8 = 5;
+const INNER_ARRAY: u8 = 6;
+const INNER_FUNCTION: u8 = 7;
+const INNER_NATIVE_FUNCTION: u8 = 8;
+const INNER_CLASS: u8 = 9;
+const INNER_REGEX: u8 = 10;
+const INNER_MAP: u8 = 11;
+const INNER_SET: u8 = 12;
+
+const KEY_NAME: u8 = 0;
+const KEY_SYMBOL: u8 = 1;
+
+const SYMBOL_WELL_KNOWN: u8 = 0;
+const SYMBOL_REGISTERED: u8 = 1;
+const SYMBOL_UNIQUE: u8 = 2;
+
+const PRIVATE_FIELD: u8 = 0;
+const PRIVATE_METHOD: u8 = 1;
+const PRIVATE_ACCESSOR: u8 = 2;
+
+const CAPTURE_NONE: u8 = 0;
+const CAPTURE_NEED_ALLOC: u8 = 1;
+const CAPTURE_NEW: u8 = 2;
+const CAPTURE_REF: u8 = 3;
+
+const VARIABLE_VAR: u8 = 0;
+const VARIABLE_LET: u8 = 1;
+const VARIABLE_CONST: u8 = 2;
+
+/// a native function held by a function object
+pub(crate) type NativeFn =
+    Arc<RwLock<dyn Fn(JSContext, JValue, &[JValue]) -> Result<JValue, JValue>>>;
+
+/// the native functions reconnected by their id when a snapshot is restored
+#[derive(Default)]
+pub(crate) struct NativeRegistry {
+    functions: HashMap<String, NativeFn>,
+    ids: HashMap<*const (), String>,
+    /// the group of builtins being installed and the number of functions created in it
+    pub(crate) builtins: Option<(&'static str, usize)>,
+}
+
+impl NativeRegistry {
+    pub(crate) fn register(&mut self, id: String, f: &NativeFn) {
+        if let Some(old) = self.functions.insert(id.clone(), f.clone()) {
+            self.ids.remove(&(Arc::as_ptr(&old) as *const ()));
+        }
+        self.ids.insert(Arc::as_ptr(f) as *const (), id);
+    }
+
+    /// register a function created while a group of builtins is installed
+    pub(crate) fn register_builtin(&mut self, f: &NativeFn) {
+        if let Some((group, n)) = &mut self.builtins {
+            let id = format!("{}#{}", group, n);
+            *n += 1;
+            self.register(id, f);
+        }
+    }
+
+    fn id(&self, f: &NativeFn) -> Option<&str> {
+        self.ids
+            .get(&(Arc::as_ptr(f) as *const ()))
+            .map(|id| id.as_str())
+    }
+}
+
+impl Runtime {
+    /// serialize the heap of the runtime: the global object, the builtin prototypes,
+    /// the global variables and every object reachable from them.
+    ///
+    /// objects wrapping promises, generators, proxies, errors, weak collections,
+    /// array buffers or custom objects cannot be serialized.
+    pub fn snapshot(&self) -> Result<Vec<u8>, Error> {
+        let mut heap = HeapEncoder::new(self);
+
+        // the roots keep their identity when restored
+        heap.object(self.global_this)?;
+        for p in self.prototypes.all() {
+            heap.object(p)?;
+        }
+
+        heap.value(self.global)?;
+
+        let mut variables = Vec::new();
+        for (name, v) in &self.variables {
+            let (kind, value) = match v {
+                Variable::Var(v) => (VARIABLE_VAR, *v),
+                Variable::Let(v) => (VARIABLE_LET, *v),
+                Variable::Const(v) => (VARIABLE_CONST, *v),
+                _ => {
+                    return Err(Error::RuntimeError(
+                        "cannot snapshot a runtime with imported modules".to_string(),
+                    ))
+                }
+            };
+            variables.push((*name, kind, value));
+        }
+        heap.enc.write_u32(variables.len() as u32);
+        for (name, kind, value) in variables {
+            heap.enc.write_var_name(name);
+            heap.enc.write_u8(kind);
+            heap.value(value)?;
+        }
+
+        // encoding an object may discover new ones
+        let mut next = 0;
+        while next < heap.queue.len() {
+            let obj = heap.queue[next];
+            heap.object_body(obj)?;
+            next += 1;
+        }
+
+        let graph = std::mem::take(&mut heap.enc.buf);
+        let mut enc = heap.enc;
+
+        self.encode_records(&mut enc);
+
+        let mut out = Encoder::default();
+        out.buf.extend_from_slice(MAGIC);
+        out.write_u32(FORMAT_VERSION);
+        out.write_str(ENGINE_VERSION);
+        out.write_u32(heap.native_ids.len() as u32);
+        for id in &heap.native_ids {
+            out.write_str(id);
+        }
+
+        self.write_tables(&mut out, &enc)?;
+
+        out.write_u32(heap.queue.len() as u32);
+        out.buf.extend_from_slice(&heap.origins.buf);
+
+        out.write_bytes(&graph);
+        out.buf.extend_from_slice(&enc.buf);
+        Ok(out.buf)
+    }
+
+    /// write a snapshot of the heap to a file
+    pub fn write_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let snapshot = self.snapshot()?;
+        std::fs::write(path, snapshot).map_err(|e| Error::RuntimeError(e.to_string()))
+    }
+
+    /// create a runtime and restore the heap from a snapshot,
+    /// `register` registers the native functions of the host before the heap is restored.
+    ///
+    /// the options must enable the builtins used by the heap of the snapshot.
+    pub fn from_snapshot<F>(
+        options: RuntimeOptions,
+        snapshot: &[u8],
+        register: F,
+    ) -> Result<Arc<Self>, Error>
+    where
+        F: FnOnce(&Arc<Self>),
+    {
+        let runtime = Self::with_options(options)?;
+        register(&runtime);
+        runtime.restore_snapshot(snapshot)?;
+        Ok(runtime)
+    }
+
+    /// create a runtime and restore the heap from a snapshot file
+    pub fn from_snapshot_file<P, F>(
+        options: RuntimeOptions,
+        path: P,
+        register: F,
+    ) -> Result<Arc<Self>, Error>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Arc<Self>),
+    {
+        let snapshot = std::fs::read(path).map_err(|e| Error::RuntimeError(e.to_string()))?;
+        Self::from_snapshot(options, &snapshot, register)
+    }
+
+    /// restore the heap from a snapshot into a newly created runtime.
+    ///
+    /// native functions are reconnected by their id, the functions of the host
+    /// must be registered with `register_native_function` before restoring.
+    pub fn restore_snapshot(self: &Arc<Self>, snapshot: &[u8]) -> Result<(), Error> {
+        let invalid = || Error::InvalidSnapshot("the snapshot is corrupted".to_string());
+
+        if snapshot.len() < MAGIC.len() || &snapshot[..MAGIC.len()] != MAGIC {
+            return Err(Error::InvalidSnapshot("not a heap snapshot".to_string()));
+        }
+
+        let mut dec = Decoder::new(&snapshot[MAGIC.len()..]);
+
+        let format = dec.read_u32().ok_or_else(invalid)?;
+        let engine = dec.read_str().ok_or_else(invalid)?;
+        if format != FORMAT_VERSION || engine != ENGINE_VERSION {
+            return Err(Error::InvalidSnapshot(format!(
+                "the snapshot is created by engine version {}, expected {}",
+                engine, ENGINE_VERSION
+            )));
+        }
+
+        let mut natives = Vec::new();
+        for _ in 0..dec.read_u32().ok_or_else(invalid)? {
+            let id = dec.read_str().ok_or_else(invalid)?;
+            let f = self.natives.functions.get(id).ok_or_else(|| {
+                Error::InvalidSnapshot(format!("the native function {} is not registered", id))
+            })?;
+            natives.push(f.clone());
+        }
+
+        let mut staged = self.load_tables(&mut dec).ok_or_else(invalid)?;
+
+        // nothing is allocated or registered until the whole snapshot is read
+        let prototypes = self.prototypes.all();
+        let mut origins = Vec::new();
+        for _ in 0..dec.read_u32().ok_or_else(invalid)? {
+            let origin = match dec.read_u8().ok_or_else(invalid)? {
+                ORIGIN_NEW => None,
+                ORIGIN_GLOBAL => Some(self.global_this),
+                ORIGIN_PROTOTYPE => {
+                    let i = dec.read_u32().ok_or_else(invalid)?;
+                    Some(*prototypes.get(i as usize).ok_or_else(invalid)?)
+                }
+                _ => return Err(invalid()),
+            };
+            origins.push(origin);
+        }
+
+        let graph = dec.read_bytes().ok_or_else(invalid)?;
+        self.load_records(&mut dec, &mut staged)
+            .ok_or_else(invalid)?;
+        self.register_staged(staged, &dec);
+
+        let objects = origins
+            .into_iter()
+            .map(|origin| {
+                let obj = origin.unwrap_or_else(|| JObject {
+                    inner: self.allocate_obj(),
+                });
+                // keep the objects alive until they are reachable from the roots
+                self.user_own_value(obj.into());
+                obj
+            })
+            .collect();
+
+        let mut heap = HeapDecoder {
+            runtime: self,
+            dec: dec.with_tables(graph),
+            objects,
+            natives,
+            captures: Vec::new(),
+            brands: HashMap::new(),
+            symbols: HashMap::new(),
+        };
+        let re = heap.restore();
+
+        for obj in &heap.objects {
+            self.user_drop_value((*obj).into());
+        }
+
+        re.ok_or_else(invalid)
+    }
+}
+
+fn unsupported(kind: &str) -> Error {
+    Error::RuntimeError(format!("cannot snapshot {} objects", kind))
+}
+
+struct HeapEncoder<'a> {
+    runtime: &'a Runtime,
+    enc: Encoder,
+    /// the origin of each object, in order of their index
+    origins: Encoder,
+
+    objects: HashMap<*const JObjectInner, u32>,
+    queue: Vec<JObject>,
+
+    prototypes: HashMap<*const JObjectInner, u32>,
+    /// the native functions referenced by the heap, in order of their index
+    native_ids: Vec<String>,
+    natives: HashMap<*const (), u32>,
+    functions: HashMap<*const JSFunction, u32>,
+    classes: HashMap<*const JSClass, u32>,
+    captures: HashMap<*const Box<[JValue]>, u32>,
+}
+
+impl<'a> HeapEncoder<'a> {
+    fn new(runtime: &'a Runtime) -> Self {
+        let mut prototypes = HashMap::new();
+        for (i, p) in runtime.prototypes.all().iter().enumerate() {
+            prototypes.insert(p.inner as *const JObjectInner, i as u32);
+        }
+
+        let mut classes = HashMap::new();
+        for (i, c) in runtime.classes.iter().enumerate() {
+            if let Some(c) = c {
+                classes.insert(Arc::as_ptr(c), i as u32);
+            }
+        }
+
+        Self {
+            runtime,
+            enc: Encoder::default(),
+            origins: Encoder::default(),
+            objects: HashMap::new(),
+            queue: Vec::new(),
+            prototypes,
+            native_ids: Vec::new(),
+            natives: HashMap::new(),
+            functions: runtime.function_ids(),
+            classes,
+            captures: HashMap::new(),
+        }
+    }
+
+    /// return the index of an object, the object is queued if not seen before
+    fn object(&mut self, obj: JObject) -> Result<u32, Error> {
+        let ptr = obj.inner as *const JObjectInner;
+        if let Some(i) = self.objects.get(&ptr) {
+            return Ok(*i);
+        }
+
+        if obj == self.runtime.global_this {
+            self.origins.write_u8(ORIGIN_GLOBAL);
+        } else if let Some(i) = self.prototypes.get(&ptr) {
+            self.origins.write_u8(ORIGIN_PROTOTYPE);
+            self.origins.write_u32(*i);
+        } else {
+            self.origins.write_u8(ORIGIN_NEW);
+        }
+
+        let i = self.queue.len() as u32;
+        self.objects.insert(ptr, i);
+        self.queue.push(obj);
+        Ok(i)
+    }
+
+    fn value(&mut self, v: JValue) -> Result<(), Error> {
+        if v.is_undefined() {
+            self.enc.write_u8(VALUE_UNDEFINED);
+        } else if v.is_null() {
+            self.enc.write_u8(VALUE_NULL);
+        } else if v.is_true() {
+            self.enc.write_u8(VALUE_TRUE);
+        } else if v.is_false() {
+            self.enc.write_u8(VALUE_FALSE);
+        } else if let Some(i) = v.as_int() {
+            self.enc.write_u8(VALUE_INT);
+            self.enc.write_u32(i as u32);
+        } else if let Some(n) = v.as_number() {
+            self.enc.write_u8(VALUE_NUMBER);
+            self.enc.write_u64(n.to_bits());
+        } else if let Some(s) = v.as_string() {
+            self.enc.write_u8(VALUE_STRING);
+            self.enc.write_str(s.as_str());
+        } else if let Some(b) = v.as_bigint() {
+            self.enc.write_u8(VALUE_BIGINT);
+            self.bigint(&b.value)?;
+        } else if let Some(s) = v.as_symbol() {
+            self.enc.write_u8(VALUE_SYMBOL);
+            self.symbol(s)?;
+        } else if let Some(obj) = v.as_object() {
+            let i = self.object(obj)?;
+            self.enc.write_u8(VALUE_OBJECT);
+            self.enc.write_u32(i);
+        } else {
+            return Err(Error::RuntimeError(format!(
+                "cannot snapshot value {}",
+                v.to_string()
+            )));
+        }
+        Ok(())
+    }
+
+    fn bigint(&mut self, b: &num_bigint::BigInt) -> Result<(), Error> {
+        let v = b
+            .to_i128()
+            .ok_or_else(|| Error::RuntimeError("cannot snapshot bigint larger then i128".to_string()))?;
+        self.enc.buf.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    /// symbol ids are only valid in the runtime that created them,
+    /// a symbol is written as the symbol it is looked up by or re-created from
+    fn symbol(&mut self, id: u32) -> Result<(), Error> {
+        if let Some((name, _)) = SYMBOLS.iter().find(|(_, k)| k.0 == id) {
+            self.enc.write_u8(SYMBOL_WELL_KNOWN);
+            self.enc.write_str(name);
+            return Ok(());
+        }
+
+        let description = self
+            .runtime
+            .symbol_description(id)
+            .ok_or_else(|| Error::RuntimeError(format!("cannot snapshot unknown symbol {}", id)))?;
+
+        if let Some(key) = self.runtime.symbol_key_for(JValue::create_symbol(id)) {
+            self.enc.write_u8(SYMBOL_REGISTERED);
+            self.enc.write_str(key);
+            return Ok(());
+        }
+
+        // the id identifies the symbol within the snapshot
+        self.enc.write_u8(SYMBOL_UNIQUE);
+        self.enc.write_u32(id);
+        match description {
+            Some(d) => {
+                self.enc.write_u8(1);
+                self.enc.write_str(d);
+            }
+            None => self.enc.write_u8(0),
+        }
+        Ok(())
+    }
+
+    fn property_key(&mut self, key: PropKey) -> Result<(), Error> {
+        let is_symbol = SYMBOLS.values().any(|k| *k == key)
+            || self.runtime.symbol_description(key.0).is_some();
+        if is_symbol {
+            self.enc.write_u8(KEY_SYMBOL);
+            self.symbol(key.0)
+        } else {
+            self.enc.write_u8(KEY_NAME);
+            self.enc.write_field_name(key.0);
+            Ok(())
+        }
+    }
+
+    fn object_body(&mut self, obj: JObject) -> Result<(), Error> {
+        let inner = obj.inner;
+
+        match inner.__proto__ {
+            Some(p) => {
+                let i = self.object(p)?;
+                self.enc.write_u8(1);
+                self.enc.write_u32(i);
+            }
+            None => self.enc.write_u8(0),
+        }
+        self.enc.write_u8(inner.extensible as u8);
+
+        self.enc.write_u32(inner.values.len() as u32);
+        for (key, cell) in inner.values.iter() {
+            self.property_key(*key)?;
+            self.enc.write_u8(cell.flag.0);
+            self.value(cell.value)?;
+            self.value(cell.setter)?;
+        }
+
+        match &inner.wrapped_value {
+            JObjectValue::Empty => self.enc.write_u8(INNER_EMPTY),
+            JObjectValue::String(s) => {
+                self.enc.write_u8(INNER_STRING);
+                self.enc.write_str(s.as_str());
+            }
+            JObjectValue::Number(n) => {
+                self.enc.write_u8(INNER_NUMBER);
+                self.enc.write_u64(n.to_bits());
+            }
+            JObjectValue::BigInt(b) => {
+                self.enc.write_u8(INNER_BIGINT);
+                self.bigint(&b.value)?;
+            }
+            JObjectValue::Boolean(b) => {
+                self.enc.write_u8(INNER_BOOLEAN);
+                self.enc.write_u8(*b as u8);
+            }
+            JObjectValue::Symbol(s) => {
+                self.enc.write_u8(INNER_SYMBOL);
+                self.symbol(s.0)?;
+            }
+            JObjectValue::Array(a) => {
+                self.enc.write_u8(INNER_ARRAY);
+                self.enc.write_u32(a.len() as u32);
+                for (flag, v) in a.iter() {
+                    self.enc.write_u8(flag.0);
+                    self.value(*v)?;
+                }
+            }
+            JObjectValue::Function(f) => {
+                self.enc.write_u8(INNER_FUNCTION);
+                self.function_instance(f)?;
+            }
+            JObjectValue::NativeFunction(f) => {
+                let ptr = Arc::as_ptr(f) as *const ();
+                let i = match self.natives.get(&ptr) {
+                    Some(i) => *i,
+                    None => {
+                        let id = self
+                            .runtime
+                            .natives
+                            .id(f)
+                            .ok_or_else(|| unsupported("unregistered native function"))?;
+                        self.native_ids.push(id.to_string());
+                        let i = self.native_ids.len() as u32 - 1;
+                        self.natives.insert(ptr, i);
+                        i
+                    }
+                };
+                self.enc.write_u8(INNER_NATIVE_FUNCTION);
+                self.enc.write_u32(i);
+            }
+            JObjectValue::Class(c) => {
+                let id = *self
+                    .classes
+                    .get(&Arc::as_ptr(&c.class))
+                    .ok_or_else(|| unsupported("unregistered class"))?;
+                self.enc.write_u8(INNER_CLASS);
+                let i = self.enc.classes.local(id);
+                self.enc.write_u32(i);
+
+                match c.super_ {
+                    Some(s) => {
+                        let i = self.object(s)?;
+                        self.enc.write_u8(1);
+                        self.enc.write_u32(i);
+                    }
+                    None => self.enc.write_u8(0),
+                }
+                match &c.constructor_instance {
+                    Some(f) => {
+                        self.enc.write_u8(1);
+                        self.function_instance(f)?;
+                    }
+                    None => self.enc.write_u8(0),
+                }
+                match &c.initializer_instance {
+                    Some(f) => {
+                        self.enc.write_u8(1);
+                        self.function_instance(f)?;
+                    }
+                    None => self.enc.write_u8(0),
+                }
+                self.enc.write_u32(c.brand);
+                self.enc.write_u32(c.private_methods.len() as u32);
+                for (name, element) in &c.private_methods {
+                    self.private_element(*name, element)?;
+                }
+            }
+            JObjectValue::Regex(r) => {
+                self.enc.write_u8(INNER_REGEX);
+                self.enc.write_str(&r.source);
+                self.enc.write_str(&r.flags.to_flags_string());
+            }
+            JObjectValue::Map(m) => {
+                self.enc.write_u8(INNER_MAP);
+                self.enc.write_u32(m.len() as u32);
+                for (k, v) in m.iter() {
+                    self.value(*k)?;
+                    self.value(*v)?;
+                }
+            }
+            JObjectValue::Set(s) => {
+                self.enc.write_u8(INNER_SET);
+                self.enc.write_u32(s.len() as u32);
+                for k in s.keys() {
+                    self.value(*k)?;
+                }
+            }
+            JObjectValue::NewTarget => return Err(unsupported("new target")),
+            JObjectValue::Module(_) => return Err(unsupported("module")),
+            JObjectValue::Error(_) => return Err(unsupported("error")),
+            JObjectValue::ArrayIterator(_) => return Err(unsupported("array iterator")),
+            JObjectValue::Generator(_) => return Err(unsupported("generator")),
+            JObjectValue::Promise(_) => return Err(unsupported("promise")),
+            JObjectValue::PromiseResolver(_) => return Err(unsupported("promise resolver")),
+            JObjectValue::Proxy(_) => return Err(unsupported("proxy")),
+            JObjectValue::WeakMap(_) => return Err(unsupported("weakmap")),
+            JObjectValue::WeakSet(_) => return Err(unsupported("weakset")),
+            JObjectValue::ArrayBuffer(_) => return Err(unsupported("array buffer")),
+            JObjectValue::DataView(_) => return Err(unsupported("data view")),
+            JObjectValue::TypedArray(_) => return Err(unsupported("typed array")),
+            JObjectValue::CustomObject(_) => return Err(unsupported("custom")),
+        }
+
+        match &inner.private_elements {
+            Some(elements) => {
+                self.enc.write_u32(elements.len() as u32);
+                for (name, element) in elements.iter() {
+                    self.enc.write_u32(name.brand);
+                    self.private_element(name.description, element)?;
+                }
+            }
+            None => self.enc.write_u32(0),
+        }
+        Ok(())
+    }
+
+    fn private_element(&mut self, description: u32, element: &PrivateElement) -> Result<(), Error> {
+        self.enc.write_field_name(description);
+        match element {
+            PrivateElement::Field(v) => {
+                self.enc.write_u8(PRIVATE_FIELD);
+                self.value(*v)?;
+            }
+            PrivateElement::Method(m) => {
+                self.enc.write_u8(PRIVATE_METHOD);
+                let i = self.object(*m)?;
+                self.enc.write_u32(i);
+            }
+            PrivateElement::Accessor(getter, setter) => {
+                self.enc.write_u8(PRIVATE_ACCESSOR);
+                for f in [getter, setter] {
+                    match f {
+                        Some(f) => {
+                            let i = self.object(*f)?;
+                            self.enc.write_u8(1);
+                            self.enc.write_u32(i);
+                        }
+                        None => self.enc.write_u8(0),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn function_instance(&mut self, f: &JSFunctionInstance) -> Result<(), Error> {
+        let id = *self
+            .functions
+            .get(&Arc::as_ptr(&f.func))
+            .ok_or_else(|| unsupported("unregistered function"))?;
+        let i = self.enc.functions.local(id);
+        self.enc.write_u32(i);
+
+        match f.this {
+            Some(this) => {
+                self.enc.write_u8(1);
+                self.value(this)?;
+            }
+            None => self.enc.write_u8(0),
+        }
+
+        match &f.capture_stack {
+            CaptureStack::None => self.enc.write_u8(CAPTURE_NONE),
+            CaptureStack::NeedAlloc(n) => {
+                self.enc.write_u8(CAPTURE_NEED_ALLOC);
+                self.enc.write_u32(*n);
+            }
+            CaptureStack::Allocated(a) => {
+                // closures of the same scope share their captures
+                let ptr = Arc::as_ptr(a);
+                if let Some(i) = self.captures.get(&ptr) {
+                    self.enc.write_u8(CAPTURE_REF);
+                    self.enc.write_u32(*i);
+                } else {
+                    self.captures.insert(ptr, self.captures.len() as u32);
+                    self.enc.write_u8(CAPTURE_NEW);
+                    self.enc.write_u32(a.len() as u32);
+                    for v in a.iter() {
+                        self.value(*v)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct HeapDecoder<'a, 'b> {
+    runtime: &'a Runtime,
+    dec: Decoder<'b>,
+    objects: Vec<JObject>,
+    /// the native functions registered under the ids of the snapshot
+    natives: Vec<NativeFn>,
+    captures: Vec<Arc<Box<[JValue]>>>,
+    /// the brands of the snapshot mapped to new brands of the runtime
+    brands: HashMap<u32, u32>,
+    /// the unique symbols of the snapshot mapped to new symbols
+    symbols: HashMap<u32, u32>,
+}
+
+impl<'a, 'b> HeapDecoder<'a, 'b> {
+    fn restore(&mut self) -> Option<()> {
+        let runtime = self.runtime;
+        let rt = runtime.to_mut();
+
+        rt.global = self.value()?;
+
+        for _ in 0..self.dec.read_u32()? {
+            let name = self.dec.read_var_name()?;
+            let variable = match self.dec.read_u8()? {
+                VARIABLE_VAR => Variable::Var(self.value()?),
+                VARIABLE_LET => Variable::Let(self.value()?),
+                VARIABLE_CONST => Variable::Const(self.value()?),
+                _ => return None,
+            };
+            rt.variables.insert(name, variable);
+        }
+
+        for i in 0..self.objects.len() {
+            self.object_body(self.objects[i])?;
+        }
+
+        Some(())
+    }
+
+    fn object(&mut self) -> Option<JObject> {
+        let i = self.dec.read_u32()?;
+        self.objects.get(i as usize).copied()
+    }
+
+    fn value(&mut self) -> Option<JValue> {
+        let v = match self.dec.read_u8()? {
+            VALUE_UNDEFINED => JValue::UNDEFINED,
+            VALUE_NULL => JValue::NULL,
+            VALUE_TRUE => JValue::TRUE,
+            VALUE_FALSE => JValue::FALSE,
+            VALUE_INT => JValue::create_int(self.dec.read_u32()? as i32),
+            VALUE_NUMBER => JValue::create_number(f64::from_bits(self.dec.read_u64()?)),
+            VALUE_STRING => {
+                let s = self.dec.read_str()?;
+                JValue::create_string(self.runtime.allocate_string(s))
+            }
+            VALUE_BIGINT => JValue::create_bigint_allocated(self.bigint()?),
+            VALUE_SYMBOL => JValue::create_symbol(self.symbol()?),
+            VALUE_OBJECT => self.object()?.into(),
+            _ => return None,
+        };
+        Some(v)
+    }
+
+    /// look up a well known or registered symbol, a unique symbol is created once
+    fn symbol(&mut self) -> Option<u32> {
+        let sym = match self.dec.read_u8()? {
+            SYMBOL_WELL_KNOWN => SYMBOLS.get(self.dec.read_str()?)?.0,
+            SYMBOL_REGISTERED => {
+                let key = self.dec.read_str()?;
+                self.runtime.symbol_for(key).as_symbol()?
+            }
+            SYMBOL_UNIQUE => {
+                let id = self.dec.read_u32()?;
+                let description = match self.dec.read_u8()? {
+                    0 => None,
+                    _ => Some(self.dec.read_str()?),
+                };
+                let runtime = self.runtime;
+                *self
+                    .symbols
+                    .entry(id)
+                    .or_insert_with(|| runtime.new_symbol(description).as_symbol().unwrap())
+            }
+            _ => return None,
+        };
+        Some(sym)
+    }
+
+    fn bigint(&mut self) -> Option<&'static mut crate::bultins::JSBigInt> {
+        let lo = self.dec.read_u64()? as u128;
+        let hi = self.dec.read_u64()? as u128;
+        let b = self.runtime.allocate_bigint();
+        b.set_value(((hi << 64) | lo) as i128);
+        Some(b)
+    }
+
+    fn object_body(&mut self, obj: JObject) -> Option<()> {
+        let inner = obj.inner.to_mut();
+
+        inner.__proto__ = match self.dec.read_u8()? {
+            0 => None,
+            _ => Some(self.object()?),
+        };
+        inner.extensible = self.dec.read_u8()? != 0;
+
+        // the roots are created by the runtime before restoring
+        inner.values.clear();
+        for _ in 0..self.dec.read_u32()? {
+            let name = match self.dec.read_u8()? {
+                KEY_NAME => self.dec.read_field_name()?,
+                KEY_SYMBOL => self.symbol()?,
+                _ => return None,
+            };
+            let flag = PropFlag(self.dec.read_u8()?);
+            let value = self.value()?;
+            let setter = self.value()?;
+            inner
+                .values
+                .insert(PropKey(name), PropCell { flag, value, setter });
+        }
+
+        inner.wrapped_value = match self.dec.read_u8()? {
+            INNER_EMPTY => JObjectValue::Empty,
+            INNER_STRING => {
+                let s = self.dec.read_str()?;
+                JObjectValue::String(self.runtime.allocate_string(s))
+            }
+            INNER_NUMBER => JObjectValue::Number(f64::from_bits(self.dec.read_u64()?)),
+            INNER_BIGINT => JObjectValue::BigInt(self.bigint()?),
+            INNER_BOOLEAN => JObjectValue::Boolean(self.dec.read_u8()? != 0),
+            INNER_SYMBOL => JObjectValue::Symbol(JSymbol(self.symbol()?)),
+            INNER_ARRAY => {
+                let len = self.dec.read_u32()?;
+                // every element takes at least a byte
+                let mut a = Vec::with_capacity((len as usize).min(self.dec.remaining()));
+                for _ in 0..len {
+                    let flag = PropFlag(self.dec.read_u8()?);
+                    a.push((flag, self.value()?));
+                }
+                JObjectValue::Array(Arc::new(a))
+            }
+            INNER_FUNCTION => JObjectValue::Function(Arc::new(self.function_instance()?)),
+            INNER_NATIVE_FUNCTION => {
+                let id = self.dec.read_u32()?;
+                JObjectValue::NativeFunction(self.natives.get(id as usize)?.clone())
+            }
+            INNER_CLASS => {
+                let i = self.dec.read_u32()?;
+                let id = *self.dec.classes.get(i as usize)?;
+                let class = self.runtime.classes.get(id as usize)?.clone()?;
+                let super_ = match self.dec.read_u8()? {
+                    0 => None,
+                    _ => Some(self.object()?),
+                };
+                let constructor_instance = match self.dec.read_u8()? {
+                    0 => None,
+                    _ => Some(Arc::new(self.function_instance()?)),
+                };
+                let initializer_instance = match self.dec.read_u8()? {
+                    0 => None,
+                    _ => Some(Arc::new(self.function_instance()?)),
+                };
+                let brand = self.dec.read_u32()?;
+                let brand = self.brand(brand);
+                let mut private_methods = Vec::new();
+                for _ in 0..self.dec.read_u32()? {
+                    private_methods.push(self.private_element()?);
+                }
+                JObjectValue::Class(Arc::new(JSClassInstance {
+                    class,
+                    super_,
+                    constructor_instance,
+                    initializer_instance,
+                    private_methods,
+                    brand,
+                }))
+            }
+            INNER_REGEX => {
+                let source = self.dec.read_str()?;
+                let flags = self.dec.read_str()?;
+                JObjectValue::Regex(Box::new(RegExp::with_flags(source, flags).ok()?))
+            }
+            INNER_MAP => {
+                let mut m = HashMap::new();
+                for _ in 0..self.dec.read_u32()? {
+                    let k = self.value()?;
+                    let v = self.value()?;
+                    m.insert(k, v);
+                }
+                JObjectValue::Map(Box::new(m))
+            }
+            INNER_SET => {
+                let mut s = HashMap::new();
+                for _ in 0..self.dec.read_u32()? {
+                    s.insert(self.value()?, ());
+                }
+                JObjectValue::Set(Box::new(s))
+            }
+            _ => return None,
+        };
+
+        inner.private_elements = None;
+        for _ in 0..self.dec.read_u32()? {
+            let brand = self.dec.read_u32()?;
+            let brand = self.brand(brand);
+            let (description, element) = self.private_element()?;
+            obj.insert_private_element(PrivateName { brand, description }, element);
+        }
+
+        Some(())
+    }
+
+    /// classes restored from a snapshot get new brands, so they never share
+    /// private names with the classes already in the runtime
+    fn brand(&mut self, brand: u32) -> u32 {
+        if brand == 0 {
+            return 0;
+        }
+        let runtime = self.runtime;
+        *self
+            .brands
+            .entry(brand)
+            .or_insert_with(|| runtime.new_private_brand())
+    }
+
+    fn private_element(&mut self) -> Option<(u32, PrivateElement)> {
+        let name = self.dec.read_field_name()?;
+        let element = match self.dec.read_u8()? {
+            PRIVATE_FIELD => PrivateElement::Field(self.value()?),
+            PRIVATE_METHOD => PrivateElement::Method(self.object()?),
+            PRIVATE_ACCESSOR => {
+                let getter = match self.dec.read_u8()? {
+                    0 => None,
+                    _ => Some(self.object()?),
+                };
+                let setter = match self.dec.read_u8()? {
+                    0 => None,
+                    _ => Some(self.object()?),
+                };
+                PrivateElement::Accessor(getter, setter)
+            }
+            _ => return None,
+        };
+        Some((name, element))
+    }
+
+    fn function_instance(&mut self) -> Option<JSFunctionInstance> {
+        let i = self.dec.read_u32()?;
+        let id = *self.dec.functions.get(i as usize)?;
+        let func = self.runtime.get_function(FuncID(id))?;
+
+        let this = match self.dec.read_u8()? {
+            0 => None,
+            _ => Some(self.value()?),
+        };
+
+        let capture_stack = match self.dec.read_u8()? {
+            CAPTURE_NONE => CaptureStack::None,
+            CAPTURE_NEED_ALLOC => CaptureStack::NeedAlloc(self.dec.read_u32()?),
+            CAPTURE_NEW => {
+                let len = self.dec.read_u32()?;
+                let mut values = Vec::with_capacity((len as usize).min(self.dec.remaining()));
+                for _ in 0..len {
+                    values.push(self.value()?);
+                }
+                let a = Arc::new(values.into_boxed_slice());
+                self.captures.push(a.clone());
+                CaptureStack::Allocated(a)
+            }
+            CAPTURE_REF => {
+                let i = self.dec.read_u32()?;
+                CaptureStack::Allocated(self.captures.get(i as usize)?.clone())
+            }
+            _ => return None,
+        };
+
+        Some(func.create_instance_with_capture(this, capture_stack))
+    }
+}
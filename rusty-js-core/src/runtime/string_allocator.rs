@@ -33,6 +33,18 @@ impl StringAllocator {
         }
     }
 
+    /// return true if the string is already allocated
+    #[inline]
+    pub fn contains(&self, s: &str) -> bool {
+        self.indexes.contains_key(&fxhash::hash64(s))
+    }
+
+    /// the number of bytes acquired when allocating the string
+    #[inline]
+    pub fn allocation_size(s: &str) -> usize {
+        s.len() + 4
+    }
+
     pub fn garbage_collect(&mut self) {}
 }
 
@@ -0,0 +1,193 @@
+// TypeScript sources are run by erasing their types,
+// imports and exports of types have nothing to link to and are removed before translating
+
+use std::collections::HashSet;
+
+use swc_atoms::JsWord;
+use swc_common::Mark;
+use swc_ecmascript::ast::*;
+use swc_ecmascript::transforms::resolver;
+use swc_ecmascript::visit::{FoldWith, Visit, VisitWith};
+
+use crate::bytecodes::bytecode_builder::declared_idents;
+
+/// remove the imports that are only used as types and the exports of types,
+/// as the TypeScript compiler does.
+///
+/// must be called with the swc globals set.
+pub(crate) fn elide_type_imports(module: &mut Module) {
+    let used = used_imports(module);
+
+    // local names that only refer to types
+    let mut types = HashSet::new();
+    let mut values = HashSet::new();
+    for item in &module.body {
+        let decl = match item {
+            ModuleItem::Stmt(Stmt::Decl(d)) => d,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(e)) => &e.decl,
+            _ => continue,
+        };
+        match decl {
+            Decl::TsInterface(i) => {
+                types.insert(i.id.sym.clone());
+            }
+            Decl::TsTypeAlias(t) => {
+                types.insert(t.id.sym.clone());
+            }
+            d => values.extend(declared_idents(d).into_iter().map(|i| i.sym.clone())),
+        }
+    }
+
+    module.body.retain_mut(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(i)) => {
+            if i.type_only {
+                return false;
+            }
+            // imported for side effects
+            if i.specifiers.is_empty() {
+                return true;
+            }
+            i.specifiers.retain(|s| {
+                let (local, type_only) = match s {
+                    ImportSpecifier::Named(n) => (&n.local, n.is_type_only),
+                    ImportSpecifier::Default(d) => (&d.local, false),
+                    ImportSpecifier::Namespace(n) => (&n.local, false),
+                };
+                let used = !type_only && used.contains(&local.sym);
+                if !used {
+                    types.insert(local.sym.clone());
+                }
+                used
+            });
+            !i.specifiers.is_empty()
+        }
+        ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(i)) => !i.is_type_only,
+        _ => true,
+    });
+
+    module.body.retain_mut(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(n)) => {
+            if n.type_only {
+                return false;
+            }
+            // export {}
+            if n.specifiers.is_empty() {
+                return true;
+            }
+            let local = n.src.is_none();
+            n.specifiers.retain(|s| match s {
+                ExportSpecifier::Named(s) => {
+                    let type_only = match &s.orig {
+                        ModuleExportName::Ident(i) => {
+                            local && types.contains(&i.sym) && !values.contains(&i.sym)
+                        }
+                        ModuleExportName::Str(_) => false,
+                    };
+                    !s.is_type_only && !type_only
+                }
+                _ => true,
+            });
+            !n.specifiers.is_empty()
+        }
+        _ => true,
+    });
+}
+
+/// the local names of the imports referenced as values.
+///
+/// a copy of the module is resolved, so locals shadowing an import are told apart
+fn used_imports(module: &Module) -> HashSet<JsWord> {
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+    let resolved = module
+        .clone()
+        .fold_with(&mut resolver(unresolved_mark, top_level_mark, true));
+
+    let mut refs = ValueRefs::default();
+    resolved.visit_with(&mut refs);
+
+    let mut used = HashSet::new();
+    for item in &resolved.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::Import(i)) = item {
+            for s in &i.specifiers {
+                let local = match s {
+                    ImportSpecifier::Named(n) => &n.local,
+                    ImportSpecifier::Default(d) => &d.local,
+                    ImportSpecifier::Namespace(n) => &n.local,
+                };
+                if refs.0.contains(&local.to_id()) {
+                    used.insert(local.sym.clone());
+                }
+            }
+        }
+    }
+    used
+}
+
+/// the bindings referenced as values outside of types,
+/// property names and binding patterns are not references
+#[derive(Default)]
+struct ValueRefs(HashSet<Id>);
+
+impl Visit for ValueRefs {
+    fn visit_expr(&mut self, e: &Expr) {
+        match e {
+            Expr::Ident(i) => {
+                self.0.insert(i.to_id());
+            }
+            _ => e.visit_children_with(self),
+        }
+    }
+
+    // { User }
+    fn visit_prop(&mut self, p: &Prop) {
+        match p {
+            Prop::Shorthand(i) => {
+                self.0.insert(i.to_id());
+            }
+            _ => p.visit_children_with(self),
+        }
+    }
+
+    // export { User }
+    fn visit_named_export(&mut self, n: &NamedExport) {
+        if n.src.is_some() || n.type_only {
+            return;
+        }
+        for s in &n.specifiers {
+            if let ExportSpecifier::Named(s) = s {
+                if let ModuleExportName::Ident(i) = &s.orig {
+                    if !s.is_type_only {
+                        self.0.insert(i.to_id());
+                    }
+                }
+            }
+        }
+    }
+
+    // import x = User.x
+    fn visit_ts_entity_name(&mut self, n: &TsEntityName) {
+        match n {
+            TsEntityName::Ident(i) => {
+                self.0.insert(i.to_id());
+            }
+            TsEntityName::TsQualifiedName(q) => q.left.visit_with(self),
+        }
+    }
+
+    // the bindings of an import are not references
+    fn visit_import_decl(&mut self, _: &ImportDecl) {}
+
+    fn visit_ts_type(&mut self, _: &TsType) {}
+
+    fn visit_ts_type_param_decl(&mut self, _: &TsTypeParamDecl) {}
+
+    fn visit_ts_type_param_instantiation(&mut self, _: &TsTypeParamInstantiation) {}
+
+    fn visit_ts_interface_decl(&mut self, _: &TsInterfaceDecl) {}
+
+    fn visit_ts_type_alias_decl(&mut self, _: &TsTypeAliasDecl) {}
+
+    // class implements
+    fn visit_ts_expr_with_type_args(&mut self, _: &TsExprWithTypeArgs) {}
+}
@@ -0,0 +1,817 @@
+use std::cell::RefCell;
+
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::{ser, Serialize};
+
+use num_traits::ToPrimitive;
+
+use crate::bultins::object::{JObject, PropFlag, PropKey};
+use crate::error::Error;
+use crate::runtime::Runtime;
+use crate::value::JValue;
+use crate::JSContext;
+
+/// deserialize a javascript value into a rust type,
+/// the runtime attached on the current thread is used to call getters.
+///
+/// errors are returned as type errors with the path of the property.
+pub fn from_value<T: DeserializeOwned>(value: JValue) -> Result<T, Error> {
+    let runtime = Runtime::try_current()?;
+    let ctx = JSContext {
+        stack: runtime.host_stack(),
+        runtime: &runtime,
+    };
+    let ancestors = RefCell::new(Vec::new());
+    T::deserialize(ValueDeserializer {
+        value,
+        path: String::new(),
+        ctx,
+        ancestors: &ancestors,
+    })
+    .map_err(|e| Error::TypeError(e.to_string()))
+}
+
+/// serialize a rust value into newly allocated javascript values
+/// on the runtime attached on the current thread.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JValue, Error> {
+    let runtime = Runtime::try_current()?;
+    value
+        .serialize(ValueSerializer {
+            runtime: &runtime,
+            path: String::new(),
+        })
+        .map_err(|e| Error::TypeError(e.to_string()))
+}
+
+#[derive(Debug)]
+struct SerdeError {
+    message: String,
+    /// path of the property that fails
+    path: Option<String>,
+}
+
+impl SerdeError {
+    /// record the path of the innermost property that fails
+    fn at(mut self, path: &str) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.to_string());
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(p) if !p.is_empty() => write!(f, "{} at {}", self.message, p),
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl de::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            path: None,
+        }
+    }
+}
+
+impl ser::Error for SerdeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            path: None,
+        }
+    }
+}
+
+fn field_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+fn index_path(parent: &str, index: usize) -> String {
+    format!("{}[{}]", parent, index)
+}
+
+struct ValueDeserializer<'a> {
+    value: JValue,
+    path: String,
+    ctx: JSContext<'a>,
+    /// the objects being deserialized, a cycle is refused
+    ancestors: &'a RefCell<Vec<JObject>>,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn child(&self, value: JValue, path: String) -> Self {
+        Self {
+            value,
+            path,
+            ctx: self.ctx,
+            ancestors: self.ancestors,
+        }
+    }
+
+    /// the enumerable own properties of an object
+    fn entries(&self, obj: JObject) -> Result<Vec<(String, JValue)>, SerdeError> {
+        let mut entries = Vec::new();
+        for (key, cell) in obj.inner.values.iter() {
+            if !cell.flag.is_enumerable() {
+                continue;
+            }
+            let name = self.ctx.runtime.get_field_name(key.0).to_string();
+            let value = obj
+                .get_property(*key, self.ctx)
+                .map_err(|e| de::Error::custom(e.to_string()))
+                .map_err(|e: SerdeError| e.at(&field_path(&self.path, &name)))?;
+            entries.push((name, value));
+        }
+        Ok(entries)
+    }
+}
+
+fn visit_number<'de, V: Visitor<'de>>(n: f64, visitor: V) -> Result<V::Value, SerdeError> {
+    // integral numbers can be deserialized into integer types
+    if n.fract() == 0.0 && n >= i64::MIN as f64 && n <= i64::MAX as f64 {
+        visitor.visit_i64(n as i64)
+    } else {
+        visitor.visit_f64(n)
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let v = self.value;
+
+        if v.is_undefined() || v.is_null() {
+            return visitor.visit_unit();
+        }
+        if v.is_true() || v.is_false() {
+            return visitor.visit_bool(v.is_true());
+        }
+        if let Some(i) = v.as_int() {
+            return visitor.visit_i64(i as i64);
+        }
+        if let Some(n) = v.as_number() {
+            return visit_number(n, visitor);
+        }
+        if let Some(s) = v.as_string() {
+            return visitor.visit_str(s.as_str());
+        }
+        if let Some(b) = v.as_bigint() {
+            if let Some(i) = b.value.to_i64() {
+                return visitor.visit_i64(i);
+            }
+            if let Some(i) = b.value.to_u64() {
+                return visitor.visit_u64(i);
+            }
+            if let Some(i) = b.value.to_i128() {
+                return visitor.visit_i128(i);
+            }
+            return Err(de::Error::custom("bigint is out of range"));
+        }
+        if let Some(obj) = v.as_object() {
+            if v.is_callable() {
+                return Err(de::Error::custom("cannot deserialize a function"));
+            }
+            if self.ancestors.borrow().contains(&obj) {
+                return Err(de::Error::custom("cannot deserialize a cyclic object"));
+            }
+
+            let ancestors = self.ancestors;
+            ancestors.borrow_mut().push(obj);
+            let re = if let Some(a) = obj.as_array() {
+                let values: Vec<JValue> = a.iter().map(|(_, v)| *v).collect();
+                visitor.visit_seq(SeqDeserializer {
+                    values: values.into_iter(),
+                    index: 0,
+                    de: self,
+                })
+            } else {
+                match self.entries(obj) {
+                    Ok(entries) => visitor.visit_map(MapDeserializer {
+                        entries: entries.into_iter(),
+                        value: None,
+                        de: self,
+                    }),
+                    Err(e) => Err(e),
+                }
+            };
+            ancestors.borrow_mut().pop();
+            return re;
+        }
+
+        Err(de::Error::custom(format!(
+            "cannot deserialize a value of type {}",
+            v.typ()
+        )))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.value.is_undefined() || self.value.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        // a unit variant is a string, other variants are objects with a single key
+        if let Some(s) = self.value.as_string() {
+            return visitor.visit_enum(StringDeserializer::<SerdeError>::new(s.to_string()));
+        }
+        if let Some(obj) = self.value.as_object() {
+            let mut entries = self.entries(obj)?;
+            if entries.len() == 1 {
+                let (variant, value) = entries.pop().unwrap();
+                return visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value,
+                    de: self,
+                });
+            }
+        }
+        Err(de::Error::custom(
+            "expected a string or an object with a single key",
+        ))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct SeqDeserializer<'a> {
+    values: std::vec::IntoIter<JValue>,
+    index: usize,
+    de: ValueDeserializer<'a>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        let value = match self.values.next() {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let path = index_path(&self.de.path, self.index);
+        self.index += 1;
+
+        seed.deserialize(self.de.child(value, path.clone()))
+            .map(Some)
+            .map_err(|e| e.at(&path))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct MapDeserializer<'a> {
+    entries: std::vec::IntoIter<(String, JValue)>,
+    /// the entry of the last key
+    value: Option<(String, JValue)>,
+    de: ValueDeserializer<'a>,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        let (key, value) = match self.entries.next() {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+        self.value = Some((key.clone(), value));
+        seed.deserialize(StringDeserializer::<SerdeError>::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, SerdeError> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        let path = field_path(&self.de.path, &key);
+
+        seed.deserialize(self.de.child(value, path.clone()))
+            .map_err(|e| e.at(&path))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumDeserializer<'a> {
+    variant: String,
+    value: JValue,
+    de: ValueDeserializer<'a>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumDeserializer<'a> {
+    type Error = SerdeError;
+    type Variant = ValueDeserializer<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), SerdeError> {
+        let path = field_path(&self.de.path, &self.variant);
+        let variant = seed.deserialize(StringDeserializer::<SerdeError>::new(self.variant))?;
+        Ok((variant, self.de.child(self.value, path)))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for ValueDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, SerdeError> {
+        let path = self.path.clone();
+        seed.deserialize(self).map_err(|e| e.at(&path))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, SerdeError> {
+        let path = self.path.clone();
+        de::Deserializer::deserialize_seq(self, visitor).map_err(|e| e.at(&path))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let path = self.path.clone();
+        de::Deserializer::deserialize_map(self, visitor).map_err(|e| e.at(&path))
+    }
+}
+
+struct ValueSerializer<'a> {
+    runtime: &'a Runtime,
+    path: String,
+}
+
+impl<'a> ValueSerializer<'a> {
+    fn string(&self, s: &str) -> JValue {
+        JValue::create_string(self.runtime.allocate_string(s))
+    }
+
+
+    fn seq(self, len: Option<usize>) -> SeqSerializer<'a> {
+        SeqSerializer {
+            runtime: self.runtime,
+            path: self.path,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        }
+    }
+
+    fn map(self) -> MapSerializer<'a> {
+        let obj = self.runtime.create_object();
+        // the object is not reachable until it is returned
+        self.runtime.user_own_value(obj.into());
+        MapSerializer {
+            runtime: self.runtime,
+            path: self.path,
+            obj,
+            key: None,
+        }
+    }
+}
+
+impl<'a> ser::Serializer for ValueSerializer<'a> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = VariantSerializer<'a, SeqSerializer<'a>>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = VariantSerializer<'a, MapSerializer<'a>>;
+
+    fn serialize_bool(self, v: bool) -> Result<JValue, SerdeError> {
+        Ok(if v { JValue::TRUE } else { JValue::FALSE })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_bigint(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<JValue, SerdeError> {
+        let v = i128::try_from(v).map_err(|_| ser::Error::custom("u128 is out of range"))?;
+        Ok(JValue::create_bigint(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<JValue, SerdeError> {
+        Ok(JValue::create_number(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<JValue, SerdeError> {
+        Ok(self.string(v.encode_utf8(&mut [0; 4])))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JValue, SerdeError> {
+        Ok(self.string(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<JValue, SerdeError> {
+        let values = v
+            .iter()
+            .map(|b| (PropFlag::THREE, JValue::create_number(*b as f64)))
+            .collect();
+        Ok(JObject::with_array(values).into())
+    }
+
+    fn serialize_none(self) -> Result<JValue, SerdeError> {
+        Ok(JValue::NULL)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<JValue, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JValue, SerdeError> {
+        Ok(JValue::NULL)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JValue, SerdeError> {
+        Ok(JValue::NULL)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<JValue, SerdeError> {
+        Ok(self.string(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<JValue, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<JValue, SerdeError> {
+        let path = field_path(&self.path, variant);
+        let value = value
+            .serialize(ValueSerializer {
+                runtime: self.runtime,
+                path: path.clone(),
+            })
+            .map_err(|e| e.at(&path))?;
+        Ok(wrap_variant(self.runtime, variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer<'a>, SerdeError> {
+        Ok(self.seq(len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>, SerdeError> {
+        Ok(self.seq(Some(len)))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'a>, SerdeError> {
+        Ok(self.seq(Some(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        let path = field_path(&self.path, variant);
+        let runtime = self.runtime;
+        Ok(VariantSerializer {
+            runtime,
+            variant,
+            inner: ValueSerializer { runtime, path }.seq(Some(len)),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, SerdeError> {
+        Ok(self.map())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer<'a>, SerdeError> {
+        Ok(self.map())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        let path = field_path(&self.path, variant);
+        let runtime = self.runtime;
+        Ok(VariantSerializer {
+            runtime,
+            variant,
+            inner: ValueSerializer { runtime, path }.map(),
+        })
+    }
+}
+
+/// an object with a single key, used for enum variants
+fn wrap_variant(runtime: &Runtime, variant: &str, value: JValue) -> JValue {
+    // the value is not reachable until it is inserted
+    runtime.user_own_value(value);
+    let obj = runtime.create_object();
+    runtime.user_drop_value(value);
+
+    let key = PropKey(runtime.register_field_name(variant));
+    obj.insert_property(key, value, PropFlag::THREE);
+    obj.into()
+}
+
+struct SeqSerializer<'a> {
+    runtime: &'a Runtime,
+    path: String,
+    values: Vec<(PropFlag, JValue)>,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let path = index_path(&self.path, self.values.len());
+        let value = value
+            .serialize(ValueSerializer {
+                runtime: self.runtime,
+                path: path.clone(),
+            })
+            .map_err(|e| e.at(&path))?;
+
+        // the elements are not reachable until the array is created
+        self.runtime.user_own_value(value);
+        self.values.push((PropFlag::THREE, value));
+        Ok(())
+    }
+
+    fn finish(self) -> JValue {
+        // the elements are released when the serializer drops
+        JObject::with_array(self.values.clone()).into()
+    }
+}
+
+impl<'a> Drop for SeqSerializer<'a> {
+    fn drop(&mut self) {
+        for (_, v) in &self.values {
+            self.runtime.user_drop_value(*v);
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+struct MapSerializer<'a> {
+    runtime: &'a Runtime,
+    path: String,
+    obj: JObject,
+    /// the last serialized key
+    key: Option<String>,
+}
+
+impl<'a> MapSerializer<'a> {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), SerdeError> {
+        let path = field_path(&self.path, key);
+        let value = value
+            .serialize(ValueSerializer {
+                runtime: self.runtime,
+                path: path.clone(),
+            })
+            .map_err(|e| e.at(&path))?;
+
+        let key = PropKey(self.runtime.register_field_name(key));
+        self.obj.insert_property(key, value, PropFlag::THREE);
+        Ok(())
+    }
+
+    fn finish(self) -> JValue {
+        // the object is released when the serializer drops
+        self.obj.into()
+    }
+}
+
+impl<'a> Drop for MapSerializer<'a> {
+    fn drop(&mut self) {
+        self.runtime.user_drop_value(self.obj.into());
+    }
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let key = key.serialize(ValueSerializer {
+            runtime: self.runtime,
+            path: self.path.clone(),
+        })?;
+
+        // property keys are strings
+        if key.is_string() || key.is_number() || key.is_bigint() {
+            self.key = Some(key.to_string());
+            Ok(())
+        } else {
+            Err(ser::Error::custom("map keys must be strings or numbers"))
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("serialize_value is called before serialize_key"))?;
+        self.insert(&key, value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+/// serialize the fields of a variant, the result is wrapped in an object keyed by the variant
+struct VariantSerializer<'a, S> {
+    runtime: &'a Runtime,
+    variant: &'static str,
+    inner: S,
+}
+
+impl<'a> ser::SerializeTupleVariant for VariantSerializer<'a, SeqSerializer<'a>> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        let value = self.inner.finish();
+        Ok(wrap_variant(self.runtime, self.variant, value))
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for VariantSerializer<'a, MapSerializer<'a>> {
+    type Ok = JValue;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.inner.insert(key, value)
+    }
+
+    fn end(self) -> Result<JValue, SerdeError> {
+        let value = self.inner.finish();
+        Ok(wrap_variant(self.runtime, self.variant, value))
+    }
+}
@@ -5,6 +5,8 @@ use super::string_interner::{NAMES, SYMBOLS};
 pub struct JSIterator<'a> {
     ctx: JSContext<'a>,
     iterator: JObject,
+    /// the iterator is exhausted or has thrown
+    done: bool,
 }
 
 impl<'a> JSIterator<'a> {
@@ -16,7 +18,7 @@ impl<'a> JSIterator<'a> {
             )
             .into());
         }
-        let iter = iter.call(obj, &[], ctx)?;
+        let iter = iter.call_with_context(obj, &[], ctx)?;
         if !iter.is_object() {
             return Err(Error::TypeError(
                 "object is not iterator: [@@Iterator]() must return object".into(),
@@ -26,13 +28,38 @@ impl<'a> JSIterator<'a> {
         return Ok(Self {
             ctx: ctx,
             iterator: iter.as_object().unwrap(),
+            done: false,
         });
     }
-}
 
-impl<'a> Iterator for JSIterator<'a> {
-    type Item = Result<JValue, JValue>;
-    fn next(&mut self) -> Option<Self::Item> {
+    /// the iterator object
+    pub fn object(&self) -> JValue {
+        self.iterator.into()
+    }
+
+    /// 7.4.8 IteratorClose with a normal completion, no op if the iterator is done
+    pub fn close(&mut self) -> Result<(), JValue> {
+        if self.done {
+            return Ok(());
+        }
+        self.done = true;
+
+        let iter: JValue = self.iterator.into();
+        let ret = iter.get_method(NAMES["return"], self.ctx)?;
+        if ret.is_undefined() {
+            return Ok(());
+        }
+        let re = ret.call_with_context(iter, &[], self.ctx)?;
+        if !re.is_object() {
+            return Err(Error::TypeError(
+                "object is not iterator: Iterator.return() must return object".into(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn step(&mut self) -> Option<Result<JValue, JValue>> {
         let re = self.iterator.get_property(NAMES["next"], self.ctx);
         let next_fn = match re {
             Ok(v) => v,
@@ -45,7 +72,7 @@ impl<'a> Iterator for JSIterator<'a> {
             )
             .into()));
         }
-        let re = next_fn.call(self.iterator.into(), &[], self.ctx);
+        let re = next_fn.call_with_context(self.iterator.into(), &[], self.ctx);
         let v = match re {
             Ok(v) => v,
             Err(e) => return Some(Err(e)),
@@ -66,3 +93,19 @@ impl<'a> Iterator for JSIterator<'a> {
         return Some(Ok(value));
     }
 }
+
+impl<'a> Iterator for JSIterator<'a> {
+    type Item = Result<JValue, JValue>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let re = self.step();
+        // an iterator that throws is not closed
+        match &re {
+            Some(Ok(_)) => {}
+            _ => self.done = true,
+        };
+        re
+    }
+}
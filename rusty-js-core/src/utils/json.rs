@@ -0,0 +1,294 @@
+use crate::bultins::object::JObject;
+use crate::bultins::object_property::{PropFlag, ToProperyKey};
+use crate::runtime::Runtime;
+use crate::value::JValue;
+
+/// a JSON document read by the host,
+/// parsed without a runtime
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    /// the entries are kept in order, conditions are matched in order
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// the value of a key, the last one wins as in JSON.parse
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// create the value as JSON.parse does,
+    /// keys such as "__proto__" are own data properties
+    pub(crate) fn to_value(&self, runtime: &Runtime) -> JValue {
+        match self {
+            Json::Null => JValue::NULL,
+            Json::Bool(b) => (*b).into(),
+            Json::Number(n) => JValue::create_number(*n),
+            Json::Str(s) => JValue::create_string(runtime.allocate_string(s)),
+            Json::Array(elements) => {
+                // the elements are rooted until the array holds them
+                let values: Vec<_> = elements
+                    .iter()
+                    .map(|e| {
+                        let value = e.to_value(runtime);
+                        runtime.user_own_value(value);
+                        (PropFlag::THREE, value)
+                    })
+                    .collect();
+                let array = JObject::with_array(values);
+                for (_, value) in array.as_array().unwrap().iter() {
+                    runtime.user_drop_value(*value);
+                }
+                array.into()
+            }
+            Json::Object(entries) => {
+                let obj = runtime.create_object();
+                runtime.user_own_value(obj.into());
+                for (key, value) in entries {
+                    let value = value.to_value(runtime);
+                    obj.insert_own_property(key.to_key(runtime), value, PropFlag::THREE);
+                }
+                runtime.user_drop_value(obj.into());
+                obj.into()
+            }
+        }
+    }
+}
+
+/// arrays and objects nested deeper are rejected before the stack runs out
+const MAX_DEPTH: usize = 512;
+
+/// parse the text as a JSON document, RFC 8259
+pub(crate) fn parse_json(filename: &str, text: &str) -> Result<Json, String> {
+    let mut parser = Parser {
+        text: text.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let re = parser.value().and_then(|v| {
+        parser.skip_whitespace();
+        if parser.pos < parser.text.len() {
+            return Err("unexpected token");
+        }
+        Ok(v)
+    });
+    re.map_err(|e| {
+        format!(
+            "{} is not valid JSON: {} at position {}",
+            filename, e, parser.pos
+        )
+    })
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    /// the arrays and objects being parsed
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), &'static str> {
+        if self.text[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err("unexpected token")
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, &'static str> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{' | b'[') => {
+                if self.depth == MAX_DEPTH {
+                    return Err("nested too deeply");
+                }
+                self.depth += 1;
+                let re = if self.peek() == Some(b'{') {
+                    self.object()
+                } else {
+                    self.array()
+                };
+                self.depth -= 1;
+                re
+            }
+            Some(b'"') => Ok(Json::Str(self.string()?)),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err("unexpected token"),
+            None => Err("unexpected end of input"),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, &'static str> {
+        self.pos += 1;
+        let mut entries = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err("expected a string key");
+            }
+            let key = self.string()?;
+
+            self.skip_whitespace();
+            self.expect(":")?;
+            let value = self.value()?;
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err("expected ',' or '}'"),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, &'static str> {
+        self.pos += 1;
+        let mut elements = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(elements));
+                }
+                _ => return Err("expected ',' or ']'"),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        self.pos += 1;
+        let mut units = Vec::new();
+        let mut start = self.pos;
+
+        loop {
+            match self.peek() {
+                None => return Err("unterminated string"),
+                Some(b'"') => break,
+                Some(0..=0x1f) => return Err("control character in string"),
+                Some(b'\\') => {
+                    units.extend(self.utf16(start)?);
+                    self.pos += 1;
+                    let unit = match self.peek() {
+                        Some(b'"') => b'"' as u16,
+                        Some(b'\\') => b'\\' as u16,
+                        Some(b'/') => b'/' as u16,
+                        Some(b'b') => 0x08,
+                        Some(b'f') => 0x0c,
+                        Some(b'n') => b'\n' as u16,
+                        Some(b'r') => b'\r' as u16,
+                        Some(b't') => b'\t' as u16,
+                        Some(b'u') => {
+                            let hex = self
+                                .text
+                                .get(self.pos + 1..self.pos + 5)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+                                .ok_or("invalid unicode escape")?;
+                            self.pos += 4;
+                            u16::from_str_radix(hex, 16).unwrap()
+                        }
+                        _ => return Err("invalid escape"),
+                    };
+                    units.push(unit);
+                    self.pos += 1;
+                    start = self.pos;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+
+        units.extend(self.utf16(start)?);
+        self.pos += 1;
+        // lone surrogates cannot be held by a rust string
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    /// the unescaped text from start to the current position
+    fn utf16(&self, start: usize) -> Result<Vec<u16>, &'static str> {
+        let s = std::str::from_utf8(&self.text[start..self.pos]).map_err(|_| "invalid utf-8")?;
+        Ok(s.encode_utf16().collect())
+    }
+
+    fn number(&mut self) -> Result<Json, &'static str> {
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            let from = p.pos;
+            while let Some(b'0'..=b'9') = p.peek() {
+                p.pos += 1;
+            }
+            p.pos > from
+        };
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        // no leading zeros
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+        } else if !digits(self) {
+            return Err("invalid number");
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !digits(self) {
+                return Err("invalid number");
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err("invalid number");
+            }
+        }
+
+        let text = std::str::from_utf8(&self.text[start..self.pos]).unwrap();
+        text.parse().map(Json::Number).map_err(|_| "invalid number")
+    }
+}
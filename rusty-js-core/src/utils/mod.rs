@@ -1,4 +1,5 @@
 pub mod iterator;
+pub(crate) mod json;
 pub mod nohasher;
 pub mod pointer_vec;
 pub mod string_interner;
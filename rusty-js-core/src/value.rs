@@ -642,7 +642,7 @@ impl JValue {
         if target.is_object() {
             let handler = target.get_method(SYMBOLS["hasInstance"], ctx)?;
             if !handler.is_undefined() {
-                return Ok(handler.call(target, &[self], ctx)?.to_bool().into());
+                return Ok(handler.call_with_context(target, &[self], ctx)?.to_bool().into());
             }
             if !target.is_callable() {
                 return Err(Error::TypeError(
@@ -716,7 +716,7 @@ impl JValue {
                     "number"
                 };
 
-                let re = to_prim.call(self, &[Self::create_static_string(hint)], ctx)?;
+                let re = to_prim.call_with_context(self, &[Self::create_static_string(hint)], ctx)?;
                 if !re.is_object() {
                     return Ok(re);
                 }
@@ -725,7 +725,7 @@ impl JValue {
                 for name in names {
                     let m = self.get_method(name, ctx)?;
                     if m.is_callable() {
-                        let re = m.call(self, &[], ctx)?;
+                        let re = m.call_with_context(self, &[], ctx)?;
                         if !re.is_object() {
                             return Ok(re);
                         }
@@ -923,10 +923,9 @@ impl JValue {
             match s.parse::<i128>() {
                 Ok(v) => return Ok(Self::create_bigint(v)),
                 Err(e) => {
-                    return Err(Error::SyntaxError(format!(
-                        "cannot convert string to bigint: {}",
-                        e.to_string()
-                    ))
+                    return Err(Error::SyntaxError(
+                        format!("cannot convert string to bigint: {}", e.to_string()).into(),
+                    )
                     .into())
                 }
             };
@@ -1061,7 +1060,10 @@ impl JValue {
 
     pub fn is_callable(self) -> bool {
         if let Some(obj) = self.as_object() {
-            return obj.is_function_instance() || obj.is_class() || obj.is_native_function();
+            return obj.is_function_instance()
+                || obj.is_class()
+                || obj.is_native_function()
+                || obj.is_promise_resolver();
         }
         return false;
     }
@@ -1299,7 +1301,7 @@ impl JValue {
     }
 
     #[inline]
-    pub fn call(self, this: Self, args: &[Self], ctx: JSContext) -> Result<Self, Self> {
+    pub fn call_with_context(self, this: Self, args: &[Self], ctx: JSContext) -> Result<Self, Self> {
         if !self.is_callable() {
             return Err(Error::CallOnNonFunction.into());
         }
@@ -1314,4 +1316,50 @@ impl JValue {
             return Err(Error::CallOnNonFunction.into());
         }
     }
+
+    /// call the value as a function from the host,
+    /// the runtime attached on the current thread is used
+    pub fn call(self, this: Self, args: &[Self]) -> Result<Self, Error> {
+        let runtime = Runtime::try_current()?;
+        runtime.call_from_host(|ctx| self.call_with_context(this, args, ctx))
+    }
+
+    /// call a method of the value from the host with the value as this
+    pub fn call_method(self, name: &str, args: &[Self]) -> Result<Self, Error> {
+        let runtime = Runtime::try_current()?;
+        runtime.call_from_host(|ctx| {
+            let method = self.get_property(name, ctx)?;
+            if !method.is_callable() {
+                return Err(Error::TypeError(format!("{} is not a function", name)).into());
+            }
+            method.call_with_context(self, args, ctx)
+        })
+    }
+
+    /// call the value as a constructor from the host
+    pub fn construct(self, args: &[Self]) -> Result<Self, Error> {
+        let runtime = Runtime::try_current()?;
+        runtime.call_from_host(|ctx| {
+            if ctx.runtime.stack_from(ctx.stack).len() < args.len() {
+                return Err(Error::RangeError("Maximum call stack size exceeded".to_owned()).into());
+            }
+            unsafe { std::ptr::copy_nonoverlapping(args.as_ptr(), ctx.stack, args.len()) };
+            let mut re = crate::operations::Result(Self::UNDEFINED, false);
+            crate::operations::invoke_new(self, ctx.runtime, ctx.stack, args.len(), &mut re);
+            if re.1 {
+                return Err(re.0);
+            }
+            Ok(re.0)
+        })
+    }
+
+    /// a future settled with the promise,
+    /// values other than promises are returned as is.
+    ///
+    /// the promise is settled by the event loop or a microtask checkpoint
+    pub fn into_future(self) -> impl std::future::Future<Output = Result<Self, Error>> + 'static {
+        let future = Runtime::try_current()
+            .map(|runtime| crate::bultins::promise::PromiseFuture::new(runtime, self));
+        async move { future?.await }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,40 @@
+use rusty_js_core::{JValue, RuntimeBuilder};
+
+#[test]
+fn async_host() {
+    let runtime = RuntimeBuilder::new().virtual_clock(true).build().unwrap();
+    runtime.clone().attach();
+
+    let echo = runtime.create_async_function(|_this, args| async move {
+        Ok(args.get(0).copied().unwrap_or(JValue::UNDEFINED))
+    });
+    runtime.declare_variable("echo", echo.into());
+
+    let never = runtime.create_async_function(|_this, _args| futures::future::pending());
+    runtime.declare_variable("never", never.into());
+
+    let re = runtime.clone().execute_script(
+        "async.js",
+        r#"
+        var out, reason;
+        echo(21).then((v) => out = v * 2);
+        never().catch((e) => reason = e.name);
+        var timer = new Promise((resolve) => setTimeout(() => resolve(5), 10));
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // the pending task is rejected with an AbortError
+    runtime.cancel_async_tasks();
+
+    let timer = runtime.get_global("timer").unwrap();
+    let (re, v) = futures::executor::block_on(async {
+        futures::join!(runtime.run_event_loop(), timer.into_future())
+    });
+    assert!(re.is_ok());
+    assert_eq!(v.unwrap().as_number(), Some(5.0));
+
+    assert_eq!(runtime.get_global("out").unwrap().as_number(), Some(42.0));
+    let reason = runtime.get_global("reason").unwrap();
+    assert_eq!(reason.to_string(), "AbortError");
+}
@@ -0,0 +1,66 @@
+use rusty_js_core::{Builtins, Error, Runtime, RuntimeBuilder};
+
+#[test]
+fn runtime_builder() {
+    // the worker threads are spawned when the runtime is built,
+    // counted first as the other runtimes add threads to the process
+    #[cfg(target_os = "linux")]
+    {
+        let threads = || std::fs::read_dir("/proc/self/task").unwrap().count();
+        let before = threads();
+        let runtime = RuntimeBuilder::new()
+            .worker_threads(2)
+            .baseline_jit(false)
+            .build()
+            .unwrap();
+        assert_eq!(threads(), before + 2);
+        drop(runtime);
+    }
+
+    let runtime = RuntimeBuilder::new()
+        .worker_threads(0)
+        .baseline_jit(false)
+        .stack_size(4096)
+        .operational_stack_size(1024)
+        .builtins(Builtins::OBJECT | Builtins::NUMBER)
+        .build()
+        .unwrap();
+    assert_eq!(runtime.stack_size(), 4096);
+    assert_eq!(runtime.operational_stack_size(), 1024);
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        let a = 0;
+        for (let i = 0; i < 100; i++) { a += i }
+        globalThis.out = typeof globalThis.Math + "," + typeof Object + "," + a;
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "undefined,function,4950"
+    );
+
+    let runtime = Runtime::new();
+    let re = runtime
+        .clone()
+        .execute_script("", "globalThis.out = typeof Math");
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "object");
+    let re = runtime.clone().execute("", "undeclared = 1");
+    assert!(re.is_ok());
+
+    // assigning an undeclared variable throws in strict mode
+    let runtime = RuntimeBuilder::new().strict_mode(true).build().unwrap();
+    let re = runtime.clone().execute("", "undeclared = 1");
+    assert!(matches!(re, Err(Error::ReferenceError(_))));
+
+    // invalid sizes are refused without panicking
+    let re = RuntimeBuilder::new().stack_size(0).build();
+    assert!(matches!(re, Err(Error::RangeError(_))));
+    let re = RuntimeBuilder::new()
+        .operational_stack_size(usize::MAX)
+        .build();
+    assert!(matches!(re, Err(Error::RangeError(_))));
+}
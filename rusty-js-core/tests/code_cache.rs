@@ -0,0 +1,72 @@
+use rusty_js_core::{Error, Runtime};
+
+#[test]
+fn code_cache() {
+    let source = r#"
+        class Counter { constructor(){ this.n = 0 } inc(){ return ++this.n } }
+        var c = new Counter();
+        c.inc(); c.inc();
+        var big = 123456789012345678901234567890n;
+        var matched = /a+b/g.test("aaab");
+    "#;
+
+    let runtime = Runtime::new();
+    let script = runtime.compile("counter.js", source).unwrap();
+    let cache = runtime.serialize_script(&script).unwrap();
+
+    let other = Runtime::new();
+    let mut script = other.load_cached_script(source, &cache).unwrap();
+    assert!(script.run(&other).is_ok());
+
+    let re = other
+        .clone()
+        .execute_script("", "if (c.inc() !== 3 || !matched) throw 1;");
+    assert!(re.is_ok());
+
+    // the cache is rejected for a different source
+    let re = other.load_cached_script("var c = 1;", &cache);
+    assert!(matches!(re, Err(Error::InvalidCodeCache(_))));
+
+    // bigints larger than i128 cannot be cached
+    let script = runtime
+        .compile(
+            "big.js",
+            "var huge = 1361129467683753853853498429727072845824n;",
+        )
+        .unwrap();
+    let re = runtime.serialize_script(&script);
+    assert!(matches!(re, Err(Error::RuntimeError(_))));
+}
+
+#[test]
+fn code_cache_corrupted() {
+    let source = r#"
+        class Point { constructor(x){ this.x = x } get double(){ return this.x * 2 } }
+        var total = 0;
+        for (var i = 0; i < 3; i++) { try { total += new Point(i).double } catch (e) {} }
+    "#;
+
+    let runtime = Runtime::new();
+    let script = runtime.compile("point.js", source).unwrap();
+    let cache = runtime.serialize_script(&script).unwrap();
+
+    // truncated entries and corrupted lengths, ids or jump targets are refused without panicking
+    let other = Runtime::new();
+    for len in 0..cache.len() {
+        let re = other.load_cached_script(source, &cache[..len]);
+        assert!(matches!(re, Err(Error::InvalidCodeCache(_))));
+    }
+    for at in 0..cache.len() - 4 {
+        let mut corrupted = cache.clone();
+        corrupted[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let _ = other.load_cached_script(source, &corrupted);
+    }
+
+    // the refused entries leave the runtime usable
+    let mut script = other.load_cached_script(source, &cache).unwrap();
+    assert!(script.run(&other).is_ok());
+    let re = other
+        .clone()
+        .execute_script("", "if (total !== 6) throw 1;");
+    assert!(re.is_ok());
+}
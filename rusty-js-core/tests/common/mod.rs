@@ -0,0 +1,49 @@
+// helpers shared by the integration tests
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use rusty_js_core::{ImportAssertion, ImportResolver};
+
+/// module sources served from memory by their resolved name
+pub struct Modules {
+    sources: HashMap<&'static str, &'static str>,
+    /// prepended to a specifier to name the module
+    scheme: Option<&'static str>,
+}
+
+impl Modules {
+    pub fn new<const N: usize>(sources: [(&'static str, &'static str); N]) -> Self {
+        Self {
+            sources: HashMap::from(sources),
+            scheme: None,
+        }
+    }
+
+    /// resolve `./dep.js` to `<scheme>dep.js`
+    pub fn with_scheme(mut self, scheme: &'static str) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+}
+
+impl ImportResolver for Modules {
+    fn resolve(&mut self, specifier: &str, _referrer: Option<&str>) -> Result<String, String> {
+        match self.scheme {
+            Some(scheme) => Ok(format!("{}{}", scheme, specifier.trim_start_matches("./"))),
+            None => Ok(specifier.to_string()),
+        }
+    }
+
+    fn import(&mut self, name: &str, asserts: ImportAssertion) -> Result<String, String> {
+        if let Some(ty) = asserts.assertions.get("type") {
+            if ty != "javascript" && ty != "json" {
+                return Err(format!("unsupported module type {}", ty));
+            }
+        }
+        match self.sources.get(name) {
+            Some(source) => Ok(source.to_string()),
+            None => Err(format!("cannot find module {}", name)),
+        }
+    }
+}
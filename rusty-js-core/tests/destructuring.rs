@@ -0,0 +1,173 @@
+use rusty_js_core::{Runtime, ToProperyKey};
+
+#[test]
+fn object_destructuring() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var key = "b";
+        var source = { a: 1, b: 2, c: 3, d: 4 };
+        var { a, [key]: b, ...rest } = source;
+
+        var x, y, others;
+        ({ x, ["y" + ""]: y = 10, ...others } = { x: 5, z: 6 });
+
+        var calls = 0;
+        function count() { calls++; return 7; }
+        var { p = count(), q: { r = count() } = {} } = { p: 1 };
+
+        globalThis.out = [
+            a, b, rest.a, rest.b, rest.c, rest.d,
+            x, y, others.x, others.y, others.z,
+            p, r, calls,
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "1,2,,,3,4,5,10,,,6,1,7,1"
+    );
+
+    // the excluded keys are never read by the rest element
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var reads = 0;
+        var key = "b";
+        var { a, [key]: b, ...rest } = {
+            get a() { reads++; return 1; },
+            get b() { reads++; return 2; },
+            get c() { reads++; return 3; },
+        };
+        globalThis.out = [a, b, reads, rest.a, rest.b, rest.c].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "1,2,3,,,3");
+
+    // destructuring undefined throws a TypeError
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        try {
+            var { ...nothing } = undefined;
+            globalThis.out = "ok";
+        } catch (e) {
+            globalThis.out = e.name;
+        }
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "TypeError");
+}
+
+#[test]
+fn rest_keys() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    // an own "__proto__" data property is copied like any other key
+    let proto_key = "__proto__".to_key(&runtime);
+    let source = runtime.create_object();
+    source.insert_property(proto_key, 1.0.into(), Default::default());
+    runtime.declare_variable("source", source.into());
+    runtime.declare_variable("sym", runtime.new_symbol(Some("sym")));
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var { ...copy } = source;
+
+        var keyed = { other: 2 };
+        keyed[sym] = 1;
+        var { [sym]: s, ...rest } = keyed;
+
+        globalThis.copy = copy;
+        globalThis.out = [s, rest[sym], rest.other].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "1,,2");
+
+    let copy = runtime.get_global("copy").unwrap().as_object().unwrap();
+    assert!(copy.has_owned_property(proto_key));
+}
+
+#[test]
+fn array_destructuring() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var obj = {};
+        var arr = [0, 0];
+        var i = 1;
+        var result = ([obj.a, arr[i], , ...obj.rest] = [1, 2, 3, 4, 5]);
+
+        var [m, [n = 8, o] = [], { s = 9 } = {}] = [1];
+
+        globalThis.out = [
+            obj.a, arr[1], obj.rest.join("-"), result.length,
+            m, n, o, s,
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "1,2,4-5,5,1,8,,9"
+    );
+}
+
+#[test]
+fn iterator_closing() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.declare_variable("iterator", runtime.well_known_symbol("iterator").unwrap());
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var closed = 0;
+        var steps = 0;
+        function iterable(len) {
+            var obj = {};
+            obj[iterator] = function () {
+                var i = 0;
+                return {
+                    next() {
+                        steps++;
+                        i++;
+                        return { value: i, done: i > len };
+                    },
+                    return() {
+                        closed++;
+                        return {};
+                    },
+                };
+            };
+            return obj;
+        }
+
+        // closed, the iterator is not done
+        var [a] = iterable(3);
+        // not closed, the rest element exhausts the iterator
+        var [b, ...c] = iterable(3);
+        // not closed, the iterator is done
+        var [d, e, f] = iterable(1);
+
+        globalThis.out = [a, b, c.join("-"), d, e, f, closed, steps].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "1,1,2-3,1,,,1,7"
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use rusty_js_core::Runtime;
+
+use common::Modules;
+
+#[test]
+fn dynamic_import() {
+    let runtime = Runtime::new();
+    runtime.set_import_resolver(Modules::new([
+        (
+            "math",
+            "export const pi = 3; export default function twice(x) { return x * 2 }",
+        ),
+        ("throws", "throw 'boom'"),
+    ]));
+
+    let re = runtime.clone().execute_script(
+        "main.js",
+        r#"
+        var log = [];
+        var p = import("math");
+        if (!(p instanceof Promise)) throw 1;
+
+        p.then((m) => log.push(m.pi, m.default(4)));
+        import("math", { with: { type: "javascript" } }).then(() => log.push("with"));
+        import("math", { with: { type: "css" } }).catch(() => log.push("type"));
+        import("missing").catch(() => log.push("missing"));
+        import("throws").catch((e) => log.push(e));
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // errors reject the promise instead of throwing,
+    // failed fetches settle before the fetched modules are evaluated
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        if (log.length !== 6) throw 1;
+        if (log[0] !== "type" || log[1] !== "missing") throw 2;
+        if (log[2] !== 3 || log[3] !== 8 || log[4] !== "with" || log[5] !== "boom") throw 3;
+        "#,
+    );
+    assert!(re.is_ok());
+}
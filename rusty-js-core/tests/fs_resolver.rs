@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use rusty_js_core::{FsResolver, ImportResolver, Runtime};
+
+fn write(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn fs_resolver() {
+    let root = std::env::temp_dir().join(format!("rusty-js-fs-resolver-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+
+    write(&root, "data.json", r#"{ "n": 5 }"#);
+    write(
+        &root,
+        "lib/math.js",
+        r#"import { two } from "./util/two"; export const four = two * 2;"#,
+    );
+    write(&root, "lib/util/two.mjs", "export const two = 2;");
+    write(&root, "lib/meta.js", "export const url = import.meta.url;");
+    write(
+        &root,
+        "lib/lazy/index.js",
+        r#"export function load() { return import("../../data.json") }"#,
+    );
+    write(
+        &root,
+        "node_modules/pkg/package.json",
+        r#"{
+            "name": "pkg",
+            "exports": {
+                ".": { "require": "./cjs.js", "import": "./esm.js" },
+                "./feature/*": "./features/*.js",
+                "./feature/internal/*": null,
+                "./escape": "./../../data.json"
+            }
+        }"#,
+    );
+    write(
+        &root,
+        "node_modules/pkg/esm.js",
+        r#"export const kind = "esm";"#,
+    );
+    write(
+        &root,
+        "node_modules/pkg/cjs.js",
+        r#"export const kind = "cjs";"#,
+    );
+    write(
+        &root,
+        "node_modules/pkg/features/x.js",
+        "export const x = 1;",
+    );
+    write(
+        &root,
+        "node_modules/pkg/features/internal/y.js",
+        "export const y = 2;",
+    );
+    write(
+        &root,
+        "node_modules/legacy/package.json",
+        r#"{ "main": "lib/main" }"#,
+    );
+    write(
+        &root,
+        "node_modules/legacy/lib/main.js",
+        "export default 7;",
+    );
+
+    let runtime = Runtime::new();
+    runtime.set_import_resolver(FsResolver::new(&root));
+
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"
+        import { four } from "./lib/math.js";
+        import { kind } from "pkg";
+        import { x } from "pkg/feature/x";
+        import legacy from "legacy";
+        import { load } from "./lib/lazy";
+        import { url } from "./lib/meta.js";
+
+        globalThis.url = url;
+        globalThis.out = four + "," + kind + "," + x + "," + legacy;
+        load().then((m) => globalThis.n = m.default.n);
+        import("pkg/feature/internal/y").catch((e) => globalThis.hidden = e.message);
+        import("./missing").catch(() => globalThis.missing = true);
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "4,esm,1,7");
+    // the import() in lib/lazy is resolved against lib/lazy/index.js
+    assert_eq!(runtime.get_global("n").unwrap().as_number(), Some(5.0));
+    assert_eq!(
+        runtime.get_global("hidden").unwrap().to_string(),
+        "package path ./feature/internal/y is not exported from package pkg"
+    );
+    assert!(runtime.get_global("missing").unwrap().is_true());
+
+    // filesystem modules are named by their file:// url
+    let url = runtime.get_global("url").unwrap().to_string();
+    assert!(url.starts_with("file:///"), "{}", url);
+    assert!(url.ends_with("/lib/meta.js"), "{}", url);
+
+    // targets and subpaths cannot leave the package directory
+    let mut resolver = FsResolver::new(&root);
+    assert!(resolver.resolve("pkg/escape", None).is_err());
+    assert!(resolver.resolve("pkg/feature/../../../data", None).is_err());
+    assert!(resolver.resolve("legacy/../pkg/esm.js", None).is_err());
+    assert!(resolver.resolve("legacy/lib/main", None).is_ok());
+
+    // the conditions are matched in the order of the exports
+    let mut resolver = FsResolver::new(&root).with_conditions(&["require"]);
+    let path = resolver.resolve("pkg", Some("lib/math.js")).unwrap();
+    assert!(path.ends_with("cjs.js"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,182 @@
+use rusty_js_core::Runtime;
+
+#[test]
+fn yield_delegate() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.declare_variable("iterator", runtime.well_known_symbol("iterator").unwrap());
+
+    // next values are forwarded, the return value of the inner generator is the result
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        function* inner() {
+            const a = yield 1;
+            const b = yield a + 1;
+            return b * 10;
+        }
+        function* outer() {
+            const r = yield* inner();
+            yield r;
+            yield* [7, 8];
+        }
+        var g = outer();
+        globalThis.out = [
+            g.next().value,
+            g.next(5).value,
+            g.next(3).value,
+            g.next().value,
+            g.next().value,
+            g.next().done,
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "1,6,30,7,8,true"
+    );
+
+    // return and throw are forwarded to the inner iterator
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var log = [];
+        var iterable = {};
+        iterable[iterator] = function () {
+            var i = 0;
+            return {
+                next() { i++; return { value: i, done: false }; },
+                return(v) { log.push("return " + v); return { value: "r" + v, done: true }; },
+            };
+        };
+        function* delegating() { yield* iterable; }
+
+        var g = delegating();
+        g.next();
+        g.next();
+        var r = g.return(9);
+        log.push(r.value, r.done, g.next().done);
+
+        function* catcher() {
+            try {
+                yield 1;
+            } catch (e) {
+                return "caught " + e;
+            }
+        }
+        function* rethrow() { const r = yield* catcher(); yield r; }
+        var g2 = rethrow();
+        g2.next();
+        log.push(g2.throw("x").value);
+
+        // an iterator without throw is closed before the TypeError
+        var g3 = delegating();
+        g3.next();
+        try {
+            g3.throw("y");
+        } catch (e) {
+            log.push(e.name);
+        }
+        globalThis.out = log.join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "return 9,r9,true,true,caught x,return undefined,TypeError"
+    );
+}
+
+#[test]
+fn async_yield_delegate() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var log = [];
+        async function* inner() {
+            const x = yield 1;
+            yield x;
+            return "done";
+        }
+        async function* outer() {
+            const r = yield* inner();
+            yield r;
+            // the values of a sync iterator are awaited
+            yield* [Promise.resolve(4)];
+        }
+        var g = outer();
+        g.next().then((r) => log.push(r.value));
+        g.next("a").then((r) => log.push(r.value));
+        g.next().then((r) => log.push(r.value));
+        g.next().then((r) => log.push(r.value));
+        g.next().then((r) => log.push(r.done));
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+
+    let re = runtime
+        .clone()
+        .execute_script("", "globalThis.out = log.join();");
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "1,a,done,4,true"
+    );
+}
+
+#[test]
+fn gc_while_suspended() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.declare_variable("iterator", runtime.well_known_symbol("iterator").unwrap());
+
+    // the iterator delegated to is only reachable from the suspended body
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        function makeIter() {
+            var values = ["a", "b", "c"];
+            var it = { i: 0 };
+            it.next = function () {
+                return { value: values[this.i++], done: this.i > 3 };
+            };
+            var obj = {};
+            obj[iterator] = function () { return it; };
+            return obj;
+        }
+        function* g() {
+            const r = yield* makeIter();
+            yield r;
+        }
+        var gen = g();
+        var out = [gen.next().value];
+        "#,
+    );
+    assert!(re.is_ok());
+
+    runtime.gc();
+    // reuse the slots of anything freed
+    let re = runtime.clone().execute_script(
+        "",
+        "for (var k = 0; k < 10000; k++) ({ i: k, next: [k] });",
+    );
+    assert!(re.is_ok());
+    runtime.gc();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        out.push(gen.next().value, gen.next().value, gen.next().value, gen.next().done);
+        globalThis.out = out.join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "a,b,c,,true");
+}
@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rusty_js_core::Runtime;
+
+#[test]
+fn heap_limit() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.set_heap_limit(Some(runtime.heap_size() + 1024 * 1024));
+
+    // the RangeError can be caught and the script continues once memory is released
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var keep = [];
+        try {
+            while (true) keep.push({ a: 1 });
+        } catch (e) {
+            globalThis.out = e.name;
+        }
+        keep = [];
+
+        var after = [];
+        for (var i = 0; i < 100; i++) after.push({});
+        globalThis.out += "," + after.length;
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "RangeError,100"
+    );
+
+    // strings count against the limit as well
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var s = "ab";
+        try {
+            while (true) s = s + s;
+        } catch (e) {
+            globalThis.out = e.name;
+        }
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "RangeError");
+}
+
+#[test]
+fn heap_limit_callback() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.set_heap_limit(Some(runtime.heap_size() + 1024 * 1024));
+
+    // the callback raises the limit twice, then refuses
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    runtime.set_heap_limit_callback(move |limit, required| {
+        assert!(required > limit);
+        if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+            Some(required * 2)
+        } else {
+            None
+        }
+    });
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var keep = [];
+        try {
+            while (true) keep.push({ a: 1 });
+        } catch (e) {
+            globalThis.out = e.name;
+        }
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "RangeError");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // without a limit nothing is refused
+    runtime.set_heap_limit(None);
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        for (var i = 0; i < 10000; i++) keep.push({ a: i });
+        globalThis.out = keep.length > 10000;
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "true");
+}
+
+#[test]
+fn heap_limit_garbage() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.set_heap_limit(Some(runtime.heap_size() + 1024 * 1024));
+
+    // unreachable objects are collected before the limit is reported
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var last;
+        for (var i = 0; i < 200000; i++) last = { a: i };
+        globalThis.out = last.a;
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "199999");
+}
+
+#[test]
+fn heap_limit_reset() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    // host allocations cannot throw, the error they leave is not raised by the next execution
+    runtime.set_heap_limit(Some(runtime.heap_size()));
+    for _ in 0..1000 {
+        runtime.user_own_value(runtime.create_object().into());
+    }
+    runtime.set_heap_limit(None);
+
+    let re = runtime
+        .clone()
+        .execute_script("", "for (var i = 0; i < 10; i++) {}");
+    assert!(re.is_ok());
+}
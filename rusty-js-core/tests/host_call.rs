@@ -0,0 +1,71 @@
+use rusty_js_core::{Error, JValue, Runtime};
+
+#[test]
+fn host_call() {
+    let runtime = Runtime::new();
+    let re = runtime.clone().execute_script(
+        "handlers.js",
+        r#"
+        function onMessage(a, b){ return a + b }
+        function Point(x){ this.x = x }
+        Point.prototype.getX = function(){ return this.x };
+        var counter = { n: 1, inc(by){ return this.n += by } };
+        "#,
+    );
+    assert!(re.is_ok());
+    runtime.clone().attach();
+
+    let on_message = runtime.get_global("onMessage").unwrap();
+    let args = [JValue::create_number(1.0), JValue::create_number(2.0)];
+    let re = on_message.call(JValue::UNDEFINED, &args);
+    assert_eq!(re.unwrap().as_number(), Some(3.0));
+
+    let counter = runtime.get_global("counter").unwrap();
+    let re = counter.call_method("inc", &[JValue::create_number(2.0)]);
+    assert_eq!(re.unwrap().as_number(), Some(3.0));
+
+    let point = runtime.get_global("Point").unwrap();
+    let p = point.construct(&[JValue::create_number(5.0)]).unwrap();
+    assert_eq!(p.call_method("getX", &[]).unwrap().as_number(), Some(5.0));
+
+    assert!(matches!(
+        runtime.get_global("missing"),
+        Err(Error::ReferenceError(_))
+    ));
+    assert!(counter.call_method("missing", &[]).is_err());
+}
+
+#[test]
+fn host_call_reentry() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    // a native function calls back into a handler while the outer host call is running
+    let callback = runtime.create_native_function(|_ctx, _this, args| {
+        let handler = args[0];
+        handler
+            .call(JValue::UNDEFINED, &[JValue::create_number(2.0)])
+            .map_err(JValue::from)
+    });
+    runtime.declare_variable("callback", callback.into());
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        function outer(a, b, c) {
+            var inner = callback(function (x) { return x * 10 });
+            return a + "," + b + "," + c + "," + inner;
+        }
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let outer = runtime.get_global("outer").unwrap();
+    let args = [
+        JValue::create_number(1.0),
+        JValue::create_number(3.0),
+        JValue::create_number(5.0),
+    ];
+    let re = outer.call(JValue::UNDEFINED, &args);
+    assert_eq!(re.unwrap().to_string(), "1,3,5,20");
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use rusty_js_core::{ImportMap, Runtime};
+
+use common::Modules;
+
+const MAP: &str = r#"{
+    "imports": {
+        "lodash": "./vendor/lodash.js",
+        "helper": "./helper.js",
+        "lib/": "./src/lib/",
+        "blocked": null
+    },
+    "scopes": {
+        "./vendor/": { "helper": "./vendor/helper-v2.js" }
+    }
+}"#;
+
+#[test]
+fn import_map() {
+    let runtime = Runtime::new();
+    runtime.set_import_resolver(Modules::new([
+        (
+            "/app/vendor/lodash.js",
+            r#"import { v } from "helper"; export const lodash = "lodash" + v;"#,
+        ),
+        ("/app/vendor/helper-v2.js", "export const v = 2;"),
+        ("/app/helper.js", "export const v = 1;"),
+        ("/app/src/lib/math.js", "export const one = 1;"),
+    ]));
+    runtime.set_import_map(ImportMap::parse(MAP, "/app/importmap.json").unwrap());
+
+    let re = runtime.clone().execute_module(
+        "/app/main.js",
+        r#"
+        import { lodash } from "lodash";
+        import { v } from "helper";
+        import { one } from "lib/math.js";
+
+        globalThis.out = lodash + "," + v + "," + one;
+        import("blocked").catch((e) => globalThis.blocked = e.message);
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+
+    // the scope of the vendored module wins over the top level imports
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "lodash2,1,1"
+    );
+    assert_eq!(
+        runtime.get_global("blocked").unwrap().to_string(),
+        "blocked is blocked by the import map"
+    );
+}
+
+#[test]
+fn import_map_resolve() {
+    let map = ImportMap::parse(
+        r#"{ "imports": { "a/": "https://cdn.test/a/", "b/": "./b.js", "./old.js": "./new.js" } }"#,
+        "https://site.test/app/map.json",
+    )
+    .unwrap();
+
+    assert_eq!(
+        map.resolve("a/x/y.js", None).unwrap().as_deref(),
+        Some("https://cdn.test/a/x/y.js")
+    );
+    // relative specifiers are matched after resolving against the referrer
+    assert_eq!(
+        map.resolve("../app/old.js", Some("https://site.test/lib/main.js"))
+            .unwrap()
+            .as_deref(),
+        Some("https://site.test/app/new.js")
+    );
+    assert_eq!(map.resolve("c", None).unwrap(), None);
+
+    // a prefix mapped to an address without a trailing slash is blocked
+    assert!(map.resolve("b/x.js", None).is_err());
+
+    assert!(ImportMap::parse("{ imports: ", "map.json").is_err());
+    assert!(ImportMap::parse(r#"{ "imports": [] }"#, "map.json").is_err());
+
+    // the map is strict JSON
+    assert!(ImportMap::parse(r#"{ "imports": {}, }"#, "map.json").is_err());
+    assert!(ImportMap::parse(r#"{ "imports": { 'a': "/a.js" } }"#, "map.json").is_err());
+    assert!(ImportMap::parse("{ /* map */ \"imports\": {} }", "map.json").is_err());
+    assert!(ImportMap::parse(r#"{ "imports": {} } {}"#, "map.json").is_err());
+
+    // deep nesting is an error, not a stack overflow
+    let deep = format!(
+        r#"{{ "imports": {}{} }}"#,
+        "[".repeat(100_000),
+        "]".repeat(100_000)
+    );
+    let e = ImportMap::parse(&deep, "map.json").err().unwrap();
+    assert!(e.contains("nested too deeply"), "{}", e);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use rusty_js_core::{JValue, Runtime, ToProperyKey};
+
+use common::Modules;
+
+#[test]
+fn import_meta() {
+    let runtime = Runtime::new();
+    runtime.set_import_resolver(
+        Modules::new([("app:/dep.js", "export const meta = import.meta;")]).with_scheme("app:/"),
+    );
+    runtime.set_import_meta_hook(|runtime, url, meta| {
+        let env = runtime.create_object();
+        meta.insert_property("env".to_key(runtime), env.into(), Default::default());
+
+        let name = url.trim_start_matches("app:/").trim_end_matches(".js");
+        env.insert_property(
+            "name".to_key(runtime),
+            JValue::create_string(name.to_string().into()),
+            Default::default(),
+        );
+    });
+
+    let re = runtime.clone().execute_module(
+        "app:/main.js",
+        r#"
+        import { meta } from "./dep.js";
+        if (import.meta !== import.meta) throw 1;
+        if (meta === import.meta) throw 2;
+        if (import.meta.hasOwnProperty !== undefined) throw 3;
+        globalThis.out = [
+            import.meta.url,
+            meta.url,
+            import.meta.resolve("./util.js"),
+            import.meta.env.name,
+            meta.env.name,
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "app:/main.js,app:/dep.js,app:/util.js,main,dep"
+    );
+}
@@ -0,0 +1,48 @@
+use rusty_js_core::Runtime;
+
+#[test]
+fn execution_budget() {
+    let runtime = Runtime::new();
+    runtime.set_execution_budget(Some(1000));
+
+    let re = runtime.clone().execute("", "while(true){}");
+    assert!(matches!(re, Err(rusty_js_core::Error::Terminated)));
+
+    // termination cannot be caught
+    let re = runtime
+        .clone()
+        .execute("", "try{ while(true){} } catch(e){ }");
+    assert!(matches!(re, Err(rusty_js_core::Error::Terminated)));
+
+    runtime.set_execution_budget(None);
+    let re = runtime.clone().execute("", "let i = 0; while(i < 5000){ i++ }");
+    assert!(re.is_ok());
+}
+
+#[test]
+fn interrupt_handle() {
+    let runtime = Runtime::new();
+    let handle = runtime.interrupt_handle();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        handle.interrupt();
+    });
+
+    let re = runtime.clone().execute("", "while(true){}");
+    assert!(matches!(re, Err(rusty_js_core::Error::Terminated)));
+}
+
+#[test]
+fn stale_interrupt() {
+    let runtime = Runtime::new();
+    let handle = runtime.interrupt_handle();
+
+    // nothing is running, the request does not reach the next execution
+    handle.interrupt();
+    let re = runtime
+        .clone()
+        .execute("", "let i = 0; while(i < 100){ i++ }");
+    assert!(re.is_ok());
+    assert!(!handle.is_interrupted());
+}
@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rusty_js_core::{Error, Runtime, RuntimeBuilder};
+
+#[test]
+fn microtask() {
+    let runtime = Runtime::new();
+    let re = runtime.clone().execute_script(
+        "order.js",
+        r#"
+        var log = [];
+        Promise.resolve().then(() => log.push("a"));
+        queueMicrotask(() => log.push("q"));
+        new Promise((resolve) => resolve(1))
+            .then((v) => v + 1)
+            .then((v) => log.push(v));
+        Promise.reject(3).catch((e) => log.push(e));
+        log.push("b");
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // the jobs ran in fifo order after the script
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        if (log.length !== 5) throw 1;
+        if (log[0] !== "b" || log[1] !== "a" || log[2] !== "q") throw 2;
+        if (log[3] !== 3 || log[4] !== 2) throw 3;
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // without a handler the first exception is returned after the queue is drained
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var ran = 0;
+        queueMicrotask(() => { throw 7 });
+        queueMicrotask(() => { ran++; throw 8 });
+        queueMicrotask(() => ran++);
+        "#,
+    );
+    match re {
+        Err(Error::Value(v)) => assert_eq!(v.to_string(), "7"),
+        _ => panic!("expected the exception of the first job"),
+    }
+    assert!(!runtime.has_pending_microtasks());
+    assert_eq!(runtime.get_global("ran").unwrap().to_string(), "2");
+
+    // exceptions are reported to the handler and the later jobs still run
+    let reported = Rc::new(RefCell::new(Vec::new()));
+    let log = reported.clone();
+    runtime.set_exception_handler(move |e| log.borrow_mut().push(e.to_string()));
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        ran = 0;
+        queueMicrotask(() => { throw 7 });
+        Promise.resolve().then(() => ran++);
+        queueMicrotask(() => { throw 8 });
+        queueMicrotask(() => ran++);
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("ran").unwrap().to_string(), "2");
+    assert_eq!(*reported.borrow(), vec!["7", "8"]);
+}
+
+#[test]
+fn async_await() {
+    let runtime = RuntimeBuilder::new().virtual_clock(true).build().unwrap();
+    runtime.clone().attach();
+
+    // the caller runs until the body is resumed, even for a settled value
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var log = [];
+        async function f(p) {
+            log.push("a");
+            const v = await p;
+            log.push(v);
+            return v;
+        }
+        async function* g() {
+            yield await new Promise((r) => setTimeout(() => r("g"), 20));
+        }
+        f(new Promise((r) => setTimeout(() => r("timer"), 10)));
+        f("settled");
+        g().next().then((r) => log.push(r.value));
+        log.push("b");
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+
+    let re = runtime
+        .clone()
+        .execute_script("", "globalThis.out = log.join();");
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "a,a,b,settled,timer,g"
+    );
+}
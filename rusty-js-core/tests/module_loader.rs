@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use rusty_js_core::{
+    AsyncImportResolver, ImportAssertion, ModuleFuture, ModuleKind, ModuleSource, Runtime,
+    ToProperyKey,
+};
+
+/// pending on the first poll, like a request to a remote store
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+struct Store {
+    sources: HashMap<&'static str, &'static str>,
+    requested: Rc<RefCell<Vec<String>>>,
+}
+
+impl AsyncImportResolver for Store {
+    fn import(&mut self, name: &str, asserts: ImportAssertion) -> ModuleFuture {
+        self.requested.borrow_mut().push(name.to_string());
+
+        let source = self.sources.get(name).map(|s| s.to_string());
+        let kind = ModuleKind::guess(name, &asserts);
+        let url = format!("store://{}", name);
+
+        Box::pin(async move {
+            YieldOnce(false).await;
+            match source {
+                Some(source) => Ok(ModuleSource { source, url, kind }),
+                None => Err(format!("{} is not in the store", url)),
+            }
+        })
+    }
+}
+
+#[test]
+fn module_loader() {
+    let runtime = Runtime::new();
+    let requested = Rc::new(RefCell::new(Vec::new()));
+    runtime.set_async_import_resolver(Store {
+        sources: HashMap::from([
+            ("a.js", "import { c } from 'c.js'; export const a = c + 1;"),
+            ("b.js", "export const b = 10;"),
+            ("c.js", "export const c = 1;"),
+            ("data.json", r#"{ "d": 100 }"#),
+        ]),
+        requested: requested.clone(),
+    });
+
+    let re = futures::executor::block_on(runtime.clone().execute_module_async(
+        "main.js",
+        r#"
+        import { a } from "a.js";
+        import { b } from "b.js";
+        import data from "data.json";
+        globalThis.sum = a + b + data.d;
+        "#,
+    ));
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("sum").unwrap().as_number(), Some(112.0));
+
+    // the imports of a level are requested together
+    assert_eq!(
+        *requested.borrow(),
+        vec!["a.js", "b.js", "data.json", "c.js"]
+    );
+
+    // import() fetches without blocking and is settled by the event loop
+    let re = runtime.clone().execute_script(
+        "main.js",
+        r#"
+        var b, missing;
+        import("b.js").then((m) => b = m.b);
+        import("missing.js").catch((e) => missing = e.message);
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("b").unwrap().as_number(), Some(10.0));
+    assert_eq!(
+        runtime.get_global("missing").unwrap().to_string(),
+        "store://missing.js is not in the store"
+    );
+}
+
+#[test]
+fn json_module() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+    runtime.set_async_import_resolver(Store {
+        sources: HashMap::from([
+            (
+                "data.json",
+                r#"{ "__proto__": { "polluted": true }, "list": [1, "ab", null], "n": -1.5e2 }"#,
+            ),
+            ("loose.json", "{ 'a': 1, }"),
+        ]),
+        requested: Rc::new(RefCell::new(Vec::new())),
+    });
+
+    let re = futures::executor::block_on(runtime.clone().execute_module_async(
+        "main.js",
+        r#"
+        import data from "data.json";
+        globalThis.data = data;
+        globalThis.out = typeof data.polluted + "," + data.list.length + "," + data.list[1]
+            + "," + data.list[2] + "," + data.n;
+        "#,
+    ));
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "undefined,3,ab,null,-150"
+    );
+
+    // "__proto__" is an own data property, the prototype is not replaced
+    let data = runtime.get_global("data").unwrap().as_object().unwrap();
+    assert!(data.has_owned_property("__proto__".to_key(&runtime)));
+
+    // JSON modules are parsed strictly, not as JavaScript
+    let re = futures::executor::block_on(
+        runtime
+            .clone()
+            .execute_module_async("loose.js", "import loose from 'loose.json';"),
+    );
+    assert!(re.is_err());
+}
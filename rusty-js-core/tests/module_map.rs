@@ -0,0 +1,195 @@
+mod common;
+
+use rusty_js_core::Runtime;
+
+use common::Modules;
+
+fn runtime() -> std::sync::Arc<Runtime> {
+    let runtime = Runtime::new();
+    runtime.set_import_resolver(Modules::new([
+        (
+            "counter.js",
+            r#"
+            order.push("counter");
+            export let count = 0;
+            export function inc() { count += 1; }
+            "#,
+        ),
+        (
+            "a.js",
+            r#"import { inc } from "counter.js"; inc(); export const a = 1;"#,
+        ),
+        (
+            "b.js",
+            r#"import { count } from "counter.js"; export function read() { return count; }"#,
+        ),
+        (
+            "even.js",
+            r#"
+            import { odd } from "odd.js";
+            export function even(n) { return n === 0 ? true : odd(n - 1); }
+            order.push("even");
+            "#,
+        ),
+        (
+            "odd.js",
+            r#"
+            import { even } from "even.js";
+            export function odd(n) { return n === 0 ? false : even(n - 1); }
+            order.push("odd");
+            "#,
+        ),
+        (
+            "throws.js",
+            r#"order.push("throws"); throw new Error("boom");"#,
+        ),
+        (
+            "first.js",
+            r#"import { second } from "second.js"; export let first = "first";"#,
+        ),
+        (
+            "second.js",
+            r#"
+            import { first } from "first.js";
+            try { order.push(first); } catch (e) { order.push(e.message); }
+            export const second = "second";
+            "#,
+        ),
+        (
+            "broken.js",
+            r#"import { missing } from "counter.js"; export const broken = 1;"#,
+        ),
+    ]));
+
+    let re = runtime.clone().execute_script("", "var order = [];");
+    assert!(re.is_ok());
+    runtime
+}
+
+#[test]
+fn module_instances() {
+    let runtime = runtime();
+
+    // both importers share one instance and see the current count
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"
+        import { a } from "a.js";
+        import { read } from "b.js";
+        import { count, inc } from "counter.js";
+        inc();
+        globalThis.out = order.length + "," + count + "," + read();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "1,2,2");
+}
+
+#[test]
+fn module_cycles() {
+    let runtime = runtime();
+
+    // the module imported first by the cycle is evaluated last
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"
+        import { even } from "even.js";
+        order.push("main");
+        globalThis.out = even(10) + "," + order.join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "true,odd,even,main"
+    );
+}
+
+#[test]
+fn module_bindings() {
+    let runtime = runtime();
+
+    let re = runtime
+        .clone()
+        .execute_module("main.js", r#"import { inc } from "counter.js"; inc();"#);
+    assert!(re.is_ok());
+
+    // a later script declares its own binding
+    let re = runtime
+        .clone()
+        .execute_script("", "let count = 10; globalThis.out = count;");
+    assert!(re.is_ok());
+
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"import { read } from "b.js"; globalThis.out += "," + read();"#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "10,1");
+
+    // a lexical export cannot be read before its declaration is evaluated
+    let re = runtime
+        .clone()
+        .execute_module("main.js", r#"import { first } from "first.js";"#);
+    assert!(re.is_ok());
+    let re = runtime
+        .clone()
+        .execute_script("", "globalThis.out = order[order.length - 1];");
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "Cannot access 'first' before initialization"
+    );
+}
+
+#[test]
+fn module_errors() {
+    let runtime = runtime();
+
+    let re = runtime
+        .clone()
+        .execute_module("main.js", r#"import { missing } from "counter.js";"#);
+    assert_eq!(
+        re.unwrap_err().message(),
+        "The requested module 'counter.js' does not provide an export named 'missing'"
+    );
+
+    // a module that throws is evaluated once and throws the same error again
+    let re = runtime.clone().execute_script(
+        "main.js",
+        r#"
+        var errors = [];
+        import("throws.js").catch((e) => errors.push(e.message));
+        import("throws.js").catch((e) => errors.push(e.message));
+        "#,
+    );
+    assert!(re.is_ok());
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        if (errors.length !== 2 || errors[0] !== "boom" || errors[1] !== "boom") throw 1;
+        if (order.length !== 1 || order[0] !== "throws") throw 2;
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // the records of a failed link are dropped and linked again
+    for _ in 0..2 {
+        let re = runtime
+            .clone()
+            .execute_module("main.js", r#"import { broken } from "broken.js";"#);
+        assert_eq!(
+            re.unwrap_err().message(),
+            "The requested module 'counter.js' does not provide an export named 'missing'"
+        );
+    }
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"import { count, inc } from "counter.js"; inc(); globalThis.out = count;"#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "1");
+}
@@ -0,0 +1,149 @@
+use rusty_js_core::Runtime;
+
+#[test]
+fn private_elements() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r##"
+        class Counter {
+            #count = 0;
+            #step;
+            static #instances = 0;
+
+            constructor(step) {
+                this.#step = step;
+                Counter.#instances++;
+            }
+            #add() { this.#count += this.#step; }
+            get #doubled() { return this.#count * 2; }
+            set #value(v) { this.#count = v; }
+
+            increment() { this.#add(); return this.#count; }
+            reset(v) { this.#value = v; return this.#doubled; }
+            static instances() { return Counter.#instances; }
+            static isCounter(o) { return #count in o; }
+        }
+        var a = new Counter(2);
+        var b = new Counter(5);
+        a.increment();
+        a.increment();
+        b.increment();
+        globalThis.out = [
+            a.increment(),
+            b.increment(),
+            a.reset(10),
+            Counter.instances(),
+            Counter.isCounter(a),
+            Counter.isCounter({}),
+            a["#count"] === undefined,
+        ].join();
+        "##,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "6,10,20,2,true,false,true"
+    );
+}
+
+#[test]
+fn private_brand_checks() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var log = [];
+        class A {
+            #x = 1;
+            #m() {}
+            get #g() { return 1; }
+            static read(o) { return o.#x; }
+            static write(o) { o.#x = 2; }
+            static writeMethod(o) { o.#m = 1; }
+            static writeGetter(o) { o.#g = 1; }
+            static has(o) { return #x in o; }
+        }
+        // the same description in another class is another private name
+        class B {
+            #x = 1;
+            static read(o) { return o.#x; }
+        }
+        var tests = [
+            () => A.read({}),
+            () => A.write({}),
+            () => A.read(1),
+            () => A.writeMethod(new A()),
+            () => A.writeGetter(new A()),
+            () => A.has(1),
+            () => B.read(new A()),
+        ];
+        for (var t of tests) {
+            try {
+                t();
+                log.push("ok");
+            } catch (e) {
+                log.push(e.name);
+            }
+        }
+        log.push(A.read(new A()), B.read(new B()));
+        globalThis.out = log.join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "TypeError,TypeError,TypeError,TypeError,TypeError,TypeError,TypeError,1,1"
+    );
+
+    // a private name must be declared by an enclosing class
+    let re = runtime
+        .clone()
+        .execute_script("", "class C { m() { return this.#y; } }");
+    assert!(re.is_err());
+}
+
+#[test]
+fn private_names_per_evaluation() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    // every class created by a factory has its own private names
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        const M = B => class extends B {
+            #x = 1;
+            static #count = 0;
+            static has(o) { return #x in o; }
+            static read(o) { return o.#x; }
+            static count() { return ++this.#count; }
+        };
+        const C1 = M(Object);
+        const C2 = M(Object);
+        const a = new C1();
+        const b = new C2();
+        var read;
+        try {
+            C1.read(b);
+            read = "ok";
+        } catch (e) {
+            read = e.name;
+        }
+        C1.count();
+        globalThis.out = [
+            C1.has(a), C1.has(b), C2.has(b), C2.has(a),
+            C1.read(a), read, C1.count(), C2.count(),
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "true,false,true,false,1,TypeError,2,1"
+    );
+}
@@ -0,0 +1,69 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rusty_js_core::{RejectionOperation, Runtime};
+
+#[test]
+fn rejection() {
+    let runtime = Runtime::new();
+
+    let ops = Rc::new(RefCell::new(Vec::new()));
+    let tracked = ops.clone();
+    runtime.set_rejection_handler(move |_promise, reason, op| {
+        tracked.borrow_mut().push((reason.as_number(), op));
+    });
+
+    let re = runtime.clone().execute_script(
+        "reject.js",
+        r#"
+        var late = Promise.reject(1);
+        Promise.reject(2).catch(() => {});
+        new Promise((_, reject) => reject(3)).then(() => {});
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // the derived promise of then rejects without a handler as well
+    assert_eq!(
+        *ops.borrow(),
+        vec![
+            (Some(1.0), RejectionOperation::Reject),
+            (Some(2.0), RejectionOperation::Reject),
+            (Some(2.0), RejectionOperation::Handle),
+            (Some(3.0), RejectionOperation::Reject),
+            (Some(3.0), RejectionOperation::Handle),
+            (Some(3.0), RejectionOperation::Reject),
+        ]
+    );
+    ops.borrow_mut().clear();
+
+    let re = runtime.clone().execute_script("", "late.catch(() => {})");
+    assert!(re.is_ok());
+    assert_eq!(*ops.borrow(), vec![(Some(1.0), RejectionOperation::Handle)]);
+}
+
+#[test]
+fn unhandled_rejections_are_released() {
+    let runtime = Runtime::new();
+
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var kept = Promise.reject(1);
+        Promise.reject(2);
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // taking drains the list, a handler added later is not tracked
+    assert_eq!(runtime.take_unhandled_rejections().len(), 2);
+    assert!(runtime.take_unhandled_rejections().is_empty());
+    let re = runtime.clone().execute_script("", "kept.catch(() => {})");
+    assert!(re.is_ok());
+
+    // the event loop drops the rejections it leaves unhandled
+    let re = runtime.clone().execute_script("", "Promise.reject(3)");
+    assert!(re.is_ok());
+    futures::executor::block_on(runtime.run_event_loop()).unwrap();
+    assert!(runtime.take_unhandled_rejections().is_empty());
+}
@@ -0,0 +1,47 @@
+use rusty_js_core::{Runtime, ToProperyKey};
+
+#[test]
+fn script_and_module() {
+    let runtime = Runtime::new();
+
+    // var and function declarations of a script live on the global object
+    let re = runtime
+        .clone()
+        .execute_script("", "var answer = 42; function f(){}; if (this !== globalThis) throw 1;");
+    assert!(re.is_ok());
+    assert!(runtime
+        .global_this()
+        .has_owned_property("answer".to_key(&runtime)));
+    assert!(runtime.global_this().has_owned_property("f".to_key(&runtime)));
+
+    // modules are strict and top level this is undefined
+    let re = runtime
+        .clone()
+        .execute_module("", "if (this !== undefined) throw 1;");
+    assert!(re.is_ok());
+
+    let re = runtime.clone().execute_module("", "undeclared = 1;");
+    assert!(re.is_err());
+}
+
+#[test]
+fn compiled_script() {
+    let runtime = Runtime::new();
+
+    let mut script = runtime
+        .compile("counter.js", "var count = (count || 0) + 1;")
+        .unwrap();
+
+    for _ in 0..3 {
+        assert!(script.run(&runtime).is_ok());
+    }
+
+    let count = runtime
+        .clone()
+        .execute_script("", "if (count !== 3) throw 1;");
+    assert!(count.is_ok());
+
+    // a script only runs on the runtime that compiled it
+    let other = Runtime::new();
+    assert!(script.run(&other).is_err());
+}
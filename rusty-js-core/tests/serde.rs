@@ -0,0 +1,86 @@
+#![cfg(feature = "serde")]
+
+use rusty_js_core::{from_value, to_value, Error, Runtime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Mode {
+    Fast,
+    Limit(u32),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    ports: Vec<u16>,
+    debug: Option<bool>,
+    mode: Mode,
+}
+
+#[test]
+fn serde() {
+    let runtime = Runtime::new();
+    let re = runtime.clone().execute_script(
+        "config.js",
+        r#"
+        var config = { name: "app", ports: [80, 443], mode: { Limit: 3 } };
+        var broken = { name: "app", ports: [80, "443"], mode: "Fast" };
+        "#,
+    );
+    assert!(re.is_ok());
+    runtime.clone().attach();
+
+    let config: Config = from_value(runtime.get_global("config").unwrap()).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "app".to_string(),
+            ports: vec![80, 443],
+            debug: None,
+            mode: Mode::Limit(3),
+        }
+    );
+
+    let re = from_value::<Config>(runtime.get_global("broken").unwrap());
+    match re {
+        Err(Error::TypeError(msg)) => assert!(msg.ends_with("at ports[1]"), "{}", msg),
+        _ => panic!("expected a type error"),
+    }
+
+    // round trip through a js value
+    let value = to_value(&config).unwrap();
+    let back: Config = from_value(value).unwrap();
+    assert_eq!(back, config);
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Node {
+    next: Option<Box<Node>>,
+}
+
+#[test]
+fn serde_cycle() {
+    let runtime = Runtime::new();
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var list = { next: { next: null } };
+        var cycle = {};
+        cycle.next = cycle;
+        "#,
+    );
+    assert!(re.is_ok());
+    runtime.clone().attach();
+
+    // a shared object is not a cycle
+    assert!(from_value::<Node>(runtime.get_global("list").unwrap()).is_ok());
+
+    let re = from_value::<Node>(runtime.get_global("cycle").unwrap());
+    match re {
+        Err(Error::TypeError(msg)) => {
+            assert_eq!(msg, "cannot deserialize a cyclic object at next")
+        }
+        _ => panic!("expected a type error"),
+    }
+}
@@ -0,0 +1,133 @@
+use rusty_js_core::{Error, JSContext, JValue, Runtime};
+
+#[test]
+fn snapshot() {
+    let runtime = Runtime::new();
+    let re = runtime.clone().execute_script(
+        "setup.js",
+        r#"
+        function counter(){ var n = 0; return function(){ return ++n } }
+        var next = counter();
+        next();
+        var config = { name: "app", tags: ["a", "b"], nested: { x: 1 } };
+        Array.prototype.first = function(){ return this[0] };
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let snapshot = runtime.snapshot().unwrap();
+
+    let restored = Runtime::from_snapshot(Default::default(), &snapshot, |_| {}).unwrap();
+    let re = restored.clone().execute_script(
+        "",
+        r#"
+        if (next() !== 2) throw 1;
+        if (config.name !== "app" || config.tags.first() !== "a") throw 2;
+        if (config.nested.x !== 1) throw 3;
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let re = Runtime::from_snapshot(Default::default(), b"not a snapshot", |_| {});
+    assert!(matches!(re, Err(Error::InvalidSnapshot(_))));
+
+    // a corrupted length is refused before anything is allocated for it
+    let runtime = Runtime::new();
+    let elements = vec!["1"; 37].join(",");
+    let re = runtime
+        .clone()
+        .execute_script("", &format!("var list = [{}];", elements));
+    assert!(re.is_ok());
+    let mut snapshot = runtime.snapshot().unwrap();
+    let at = snapshot
+        .windows(5)
+        .position(|w| w == [6, 37, 0, 0, 0])
+        .unwrap();
+    snapshot[at + 1..at + 5].copy_from_slice(&u32::MAX.to_le_bytes());
+    let re = Runtime::from_snapshot(Default::default(), &snapshot, |_| {});
+    assert!(matches!(re, Err(Error::InvalidSnapshot(_))));
+}
+
+#[test]
+fn snapshot_symbols() {
+    let runtime = Runtime::new();
+    runtime.declare_variable("tag", runtime.new_symbol(Some("tag")));
+    runtime.declare_variable("shared", runtime.symbol_for("app.shared"));
+    let re = runtime.clone().execute_script(
+        "setup.js",
+        r#"
+        var pair = [tag, shared, tag];
+        var list = [1, 2, 3];
+        "#,
+    );
+    assert!(re.is_ok());
+
+    let snapshot = runtime.snapshot().unwrap();
+
+    // symbols created after the snapshot must not alias the restored ones
+    let restored = Runtime::from_snapshot(Default::default(), &snapshot, |_| {}).unwrap();
+    restored.declare_variable("other", restored.new_symbol(Some("other")));
+    let lookup = restored.symbol_for("app.shared");
+    restored.declare_variable("lookup", lookup);
+    let re = restored.clone().execute_script(
+        "",
+        r#"
+        // arrays are iterated by the well known Symbol.iterator of their prototype
+        var sum = 0;
+        for (var n of list) sum += n;
+        globalThis.out = [
+            pair[0] === pair[2], pair[0] === other, pair[1] === lookup,
+            pair[0] === pair[1], typeof pair[0], sum,
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        restored.get_global("out").unwrap().to_string(),
+        "true,false,true,false,symbol,6"
+    );
+    assert_eq!(restored.symbol_key_for(lookup), Some("app.shared"));
+}
+
+#[test]
+fn snapshot_natives() {
+    let double = |ctx: JSContext, _this: JValue, args: &[JValue]| -> Result<JValue, JValue> {
+        let n = args.first().map_or(Ok(0.0), |v| v.to_number(ctx))?;
+        Ok(JValue::create_number(n * 2.0))
+    };
+
+    let runtime = Runtime::new();
+    // created before the host function, the ids do not depend on the order
+    runtime.create_native_function(|_ctx, _this, _args| Ok(JValue::UNDEFINED));
+    let f = runtime.register_native_function("app.double", double);
+    runtime.declare_variable("double", f.into());
+    let re = runtime
+        .clone()
+        .execute_script("setup.js", "var api = { double, assign: Object.assign };");
+    assert!(re.is_ok());
+
+    let snapshot = runtime.snapshot().unwrap();
+
+    // the host function must be registered before restoring
+    let re = Runtime::from_snapshot(Default::default(), &snapshot, |_| {});
+    match re {
+        Err(Error::InvalidSnapshot(e)) => assert!(e.contains("app.double")),
+        _ => panic!("expected the unregistered function to be reported"),
+    }
+
+    let restored = Runtime::from_snapshot(Default::default(), &snapshot, |rt| {
+        rt.register_native_function("app.double", double);
+    })
+    .unwrap();
+    let re = restored.clone().execute_script(
+        "",
+        "globalThis.out = api.double(21) + ',' + api.assign({}, { a: 1 }).a;",
+    );
+    assert!(re.is_ok());
+    assert_eq!(restored.get_global("out").unwrap().to_string(), "42,1");
+
+    // functions without an id cannot be restored
+    let unregistered = runtime.create_native_function(|_ctx, _this, _args| Ok(JValue::UNDEFINED));
+    runtime.declare_variable("unregistered", unregistered.into());
+    assert!(runtime.snapshot().is_err());
+}
@@ -0,0 +1,19 @@
+use rusty_js_core::{Error, Runtime};
+
+#[test]
+fn syntax_error() {
+    let runtime = Runtime::new();
+
+    let re = runtime.clone().execute("input.js", "let a = 1;\nlet b = ;");
+    let e = match re {
+        Err(Error::SyntaxError(e)) => e,
+        _ => panic!("expected a syntax error"),
+    };
+
+    assert!(!e.diagnostics.is_empty());
+    let d = &e.diagnostics[0];
+    assert_eq!(d.filename, "input.js");
+    assert_eq!(d.line, 2);
+    assert_eq!(d.column, 9);
+    assert_eq!(d.span.start, 19);
+}
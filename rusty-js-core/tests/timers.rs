@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use rusty_js_core::RuntimeBuilder;
+
+#[test]
+fn timers() {
+    let runtime = RuntimeBuilder::new().virtual_clock(true).build().unwrap();
+    let re = runtime.clone().execute_script(
+        "timers.js",
+        r#"
+        var log = [];
+        setTimeout((x) => log.push(x), 20, "late");
+        setTimeout(() => {
+            log.push("early");
+            Promise.resolve().then(() => log.push("micro"));
+        }, 10);
+        var ticks = 0;
+        var id = setInterval(() => { if (++ticks == 3) clearInterval(id) }, 5);
+        clearTimeout(setTimeout(() => log.push("cleared"), 1));
+        "#,
+    );
+    assert!(re.is_ok());
+
+    runtime.advance_clock(Duration::from_millis(12)).unwrap();
+    assert_eq!(runtime.now(), Duration::from_millis(12));
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        if (log.length !== 2 || log[0] !== "early" || log[1] !== "micro") throw 1;
+        if (ticks !== 2) throw 2;
+        "#,
+    );
+    assert!(re.is_ok());
+
+    // the virtual clock jumps to the remaining timers
+    futures::executor::block_on(runtime.run_event_loop()).unwrap();
+    assert_eq!(runtime.now(), Duration::from_millis(20));
+    let re = runtime.clone().execute_script(
+        "",
+        "if (log.length !== 3 || log[2] !== 'late' || ticks !== 3) throw 1",
+    );
+    assert!(re.is_ok());
+}
+
+#[test]
+fn zero_delay_interval() {
+    for virtual_clock in [true, false] {
+        let runtime = RuntimeBuilder::new()
+            .virtual_clock(virtual_clock)
+            .build()
+            .unwrap();
+        let re = runtime.clone().execute_script(
+            "",
+            r#"
+            var ticks = 0;
+            var id = setInterval(() => { if (++ticks == 5) clearInterval(id) }, 0);
+            "#,
+        );
+        assert!(re.is_ok());
+
+        // the interval yields to the event loop between ticks
+        futures::executor::block_on(runtime.run_event_loop()).unwrap();
+        let re = runtime.clone().execute_script("", "if (ticks !== 5) throw ticks");
+        assert!(re.is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use rusty_js_core::RuntimeBuilder;
+
+use common::Modules;
+
+#[test]
+fn top_level_await() {
+    let runtime = RuntimeBuilder::new().virtual_clock(true).build().unwrap();
+    runtime.clone().attach();
+    runtime.set_import_resolver(Modules::new([
+        (
+            "secrets.js",
+            r#"
+            order.push("secrets");
+            export const key = await new Promise((r) => setTimeout(() => r("s3cret"), 10));
+            order.push("loaded");
+            "#,
+        ),
+        (
+            "config.js",
+            r#"import { key } from "secrets.js"; order.push("config"); export const config = { key };"#,
+        ),
+        ("sync.js", r#"order.push("sync");"#),
+        (
+            "throws.js",
+            r#"await null; throw new Error("boom");"#,
+        ),
+    ]));
+
+    let re = runtime.clone().execute_script("", "var order = [];");
+    assert!(re.is_ok());
+
+    // the sibling runs while the async module is suspended,
+    // the importers run once it is evaluated
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"
+        import { config } from "config.js";
+        import "sync.js";
+        order.push("main");
+        globalThis.out = config.key + "," + order.join();
+        "#,
+    );
+    let evaluation = re.unwrap();
+    assert!(evaluation.as_object().map_or(false, |o| o.is_promise()));
+
+    let (re, v) = futures::executor::block_on(async {
+        futures::join!(runtime.run_event_loop(), evaluation.into_future())
+    });
+    assert!(re.is_ok());
+    assert!(v.unwrap().is_undefined());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "s3cret,secrets,sync,loaded,config,main"
+    );
+
+    // import() waits for the async module graph
+    let re = runtime.clone().execute_script(
+        "",
+        r#"
+        var log = [];
+        import("config.js").then((m) => log.push(m.config.key));
+        import("throws.js").catch((e) => log.push(e.message));
+        "#,
+    );
+    assert!(re.is_ok());
+    let re = futures::executor::block_on(runtime.run_event_loop());
+    assert!(re.is_ok());
+
+    let re = runtime.clone().execute_script(
+        "",
+        "if (log.length !== 2 || log[0] !== 's3cret' || log[1] !== 'boom') throw 1;",
+    );
+    assert!(re.is_ok());
+}
+
+#[test]
+fn top_level_await_roots() {
+    let runtime = RuntimeBuilder::new().virtual_clock(true).build().unwrap();
+    runtime.clone().attach();
+
+    // the receiver is only held by the module body while it is suspended
+    let re = runtime.clone().execute_module(
+        "main.js",
+        r#"
+        globalThis.out = ({ n: "kept,", f(x) { return this.n + x; } })
+            .f(await new Promise((r) => setTimeout(() => r("resumed"), 10)));
+        "#,
+    );
+    let evaluation = re.unwrap();
+
+    runtime.gc();
+    // reuse the slots of anything freed
+    let re = runtime
+        .clone()
+        .execute_script("", "for (var k = 0; k < 10000; k++) ({ n: k, f: [k] });");
+    assert!(re.is_ok());
+    runtime.gc();
+
+    let (re, v) = futures::executor::block_on(async {
+        futures::join!(runtime.run_event_loop(), evaluation.into_future())
+    });
+    assert!(re.is_ok());
+    assert!(v.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "kept,resumed"
+    );
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use rusty_js_core::{ImportAssertion, ImportResolver, Runtime};
+
+#[test]
+fn type_stripping() {
+    let runtime = Runtime::new();
+    runtime.clone().attach();
+
+    let re = runtime.clone().execute_typescript(
+        "main.ts",
+        r#"
+        interface Shape { area(): number }
+        type Pair<T> = [T, T];
+
+        enum Color { Red, Green = 5, Blue }
+        enum Flag { A = 1 << 0, B = 1 << 1, AB = A | B }
+        enum Dir { Up = "UP", Down = "DOWN" }
+        declare const host: string;
+
+        namespace Geometry {
+            const factor: number = 2;
+            export function double(n: number): number { return n * factor; }
+            export namespace Inner { export const one = 1; }
+        }
+
+        abstract class Base<T> {
+            abstract name(): string;
+            describe(this: Base<T>): string { return this.name(); }
+        }
+        class Square extends Base<number> implements Shape {
+            declare tag: string;
+            [key: string]: any;
+            constructor(public size: number, private label: string = "sq") { super(); }
+            area(): number { return this.size * this.size; }
+            name() { return this.label; }
+        }
+
+        function first(x: string): string;
+        function first(x: number): number;
+        function first(x: any): any { return x; }
+
+        let value = <number>(Geometry.double(3) as unknown) satisfies number;
+        const pair: Pair<number> = [value!, first<number>(1)];
+        const sq = new Square(3);
+
+        globalThis.out = [
+            Color.Red, Color.Green, Color.Blue, Color[5],
+            Flag.AB, Dir.Up, Dir["UP"],
+            Geometry.Inner.one, pair.join("-"),
+            sq.area(), sq.describe(), sq.size, "tag" in sq,
+        ].join();
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(
+        runtime.get_global("out").unwrap().to_string(),
+        "0,5,6,Green,3,UP,,1,6-1,9,sq,3,false"
+    );
+
+    // a member after a string member needs an initializer
+    let re = runtime
+        .clone()
+        .execute_typescript("", r#"enum E { A = "a", B }"#);
+    assert!(re.is_err());
+}
+
+struct Modules {
+    sources: HashMap<&'static str, &'static str>,
+    requested: Rc<RefCell<Vec<String>>>,
+}
+
+impl ImportResolver for Modules {
+    fn import(&mut self, name: &str, _asserts: ImportAssertion) -> Result<String, String> {
+        self.requested.borrow_mut().push(name.to_string());
+        match self.sources.get(name) {
+            Some(source) => Ok(source.to_string()),
+            None => Err(format!("cannot find module {}", name)),
+        }
+    }
+}
+
+#[test]
+fn typescript_modules() {
+    let runtime = Runtime::new();
+    let requested = Rc::new(RefCell::new(Vec::new()));
+    runtime.set_import_resolver(Modules {
+        sources: HashMap::from([
+            (
+                "types.ts",
+                "export interface User { name: string } export type Id = number;",
+            ),
+            (
+                "user.ts",
+                r#"
+                import type { Id } from "types.ts";
+                import { User } from "types.ts";
+                export interface Named { name: string }
+                export enum Role { Admin, Guest }
+                export function greet(u: User, id: Id): string {
+                    return `${u.name}#${id}`;
+                }
+                export { Named as Alias };
+                "#,
+            ),
+        ]),
+        requested: requested.clone(),
+    });
+
+    let re = runtime.clone().execute_typescript(
+        "main.ts",
+        r#"
+        import { greet, Role, type Named } from "user.ts";
+        const user: Named = { name: "ada" };
+        globalThis.out = greet(user, Role.Guest);
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "ada#1");
+
+    // modules only imported as types are never loaded
+    assert_eq!(*requested.borrow(), vec!["user.ts"]);
+}
+
+#[test]
+fn typescript_type_import_references() {
+    let runtime = Runtime::new();
+    let requested = Rc::new(RefCell::new(Vec::new()));
+    runtime.set_import_resolver(Modules {
+        sources: HashMap::from([("types.ts", "export interface User { name: string }")]),
+        requested: requested.clone(),
+    });
+
+    // property names, object keys and shadowing locals do not reference the import
+    let re = runtime.clone().execute_typescript(
+        "main.ts",
+        r#"
+        import { User } from "types.ts";
+        const user: User = { name: "ada" };
+        const keys = { User: 1 };
+        function shadow(User: string): string {
+            return User;
+        }
+        globalThis.out = keys.User + shadow("x") + user.name + (user.User === undefined);
+        "#,
+    );
+    assert!(re.is_ok());
+    assert_eq!(runtime.get_global("out").unwrap().to_string(), "1xadatrue");
+    assert!(requested.borrow().is_empty());
+}
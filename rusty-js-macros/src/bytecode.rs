@@ -12,12 +12,55 @@ struct Varient{
     write:Option<String>
 }
 
+/// how a field is written to the code cache
+enum CacheKind{
+    /// the field implements CacheField
+    Value,
+    /// an interned object field name
+    FieldName,
+    /// an interned dynamic variable name
+    VarName,
+}
+
+enum CacheVarient{
+    Unit(String),
+    Named(String, Vec<(String, CacheKind)>),
+    Unnamed(String, usize),
+}
+
 
 pub fn register_bytecodes(input:TokenStream) -> TokenStream{
     let mut ast = syn::parse_macro_input!(input as ItemEnum);
 
     let mut varients: Vec<Varient> = Vec::new();
 
+    let mut cache_varients = Vec::new();
+
+    for v in &ast.variants{
+        let name = v.ident.to_string();
+        match &v.fields{
+            syn::Fields::Unit => cache_varients.push(CacheVarient::Unit(name)),
+            syn::Fields::Unnamed(f) => cache_varients.push(CacheVarient::Unnamed(name, f.unnamed.len())),
+            syn::Fields::Named(f) => {
+                let fields = f.named.iter().map(|f|{
+                    let mut kind = CacheKind::Value;
+                    for attr in &f.attrs{
+                        if let Some(id) = attr.path.get_ident(){
+                            if id == "field"{
+                                kind = CacheKind::FieldName;
+                            }
+                            if id == "var"{
+                                kind = CacheKind::VarName;
+                            }
+                        }
+                    }
+                    (f.ident.as_ref().unwrap().to_string(), kind)
+                }).collect();
+                cache_varients.push(CacheVarient::Named(name, fields));
+            }
+        }
+    }
+
     'outer:for v in &mut ast.variants{
         let mut fields = Vec::new();
         let mut reads = Vec::new();
@@ -86,7 +129,60 @@ pub fn register_bytecodes(input:TokenStream) -> TokenStream{
         }
     };
 
+    let mut encode = String::new();
+    let mut decode = String::new();
+
+    for (tag, v) in cache_varients.iter().enumerate(){
+        match v{
+            CacheVarient::Unit(name) => {
+                encode += &format!("            Self::{} => enc.write_u16({}),\n", name, tag);
+                decode += &format!("            {} => Some(Self::{}),\n", tag, name);
+            }
+            CacheVarient::Unnamed(name, len) => {
+                let fields = (0..*len).map(|i| format!("f{}", i)).collect::<Vec<_>>();
+                let writes = fields.iter().map(|f| format!("CacheField::encode(&{}, enc);", f)).collect::<Vec<_>>().join(" ");
+                let reads = fields.iter().map(|_| "CacheField::decode(dec)?".to_string()).collect::<Vec<_>>().join(", ");
+
+                encode += &format!("            Self::{}({}) => {{ enc.write_u16({}); {} }}\n", name, fields.join(", "), tag, writes);
+                decode += &format!("            {} => Some(Self::{}({})),\n", tag, name, reads);
+            }
+            CacheVarient::Named(name, fields) => {
+                let names = fields.iter().map(|(f, _)| f.clone()).collect::<Vec<_>>().join(", ");
+                let writes = fields.iter().map(|(f, kind)| match kind{
+                    CacheKind::Value => format!("CacheField::encode(&{}, enc);", f),
+                    CacheKind::FieldName => format!("enc.write_field_name({});", f),
+                    CacheKind::VarName => format!("enc.write_var_name({});", f),
+                }).collect::<Vec<_>>().join(" ");
+                let reads = fields.iter().map(|(f, kind)| match kind{
+                    CacheKind::Value => format!("{}: CacheField::decode(dec)?", f),
+                    CacheKind::FieldName => format!("{}: dec.read_field_name()?", f),
+                    CacheKind::VarName => format!("{}: dec.read_var_name()?", f),
+                }).collect::<Vec<_>>().join(", ");
+
+                encode += &format!("            Self::{} {{ {} }} => {{ enc.write_u16({}); {} }}\n", name, names, tag, writes);
+                decode += &format!("            {} => Some(Self::{} {{ {} }}),\n", tag, name, reads);
+            }
+        }
+    }
+
     let stream = format!(r#"
+impl {} {{
+    /// write the opcode into the code cache
+    pub(crate) fn encode(self, enc: &mut Encoder){{
+        match self {{
+{}
+        }}
+    }}
+
+    /// read an opcode from the code cache
+    pub(crate) fn decode(dec: &mut Decoder) -> Option<Self>{{
+        match dec.read_u16()? {{
+{}
+            _ => None
+        }}
+    }}
+}}
+
 impl ByteCode for {} {{
     fn writes_to(self) -> Option<Register>{{
         match self {{
@@ -103,7 +199,7 @@ impl ByteCode for {} {{
     }}
 }}
 "#, 
-    ast.ident, write_to, reads_from
+    ast.ident, encode, decode, ast.ident, write_to, reads_from
     );
 
     
@@ -234,7 +234,7 @@ mod test{
 
 }
 
-#[proc_macro_derive(ByteCode, attributes(r, w))]
+#[proc_macro_derive(ByteCode, attributes(r, w, field, var))]
 pub fn register_bytecodes(input:TokenStream) -> TokenStream{
     bytecode::register_bytecodes(input)
 }
\ No newline at end of file